@@ -2,13 +2,25 @@ use std::collections::HashMap;
 use std::fs::File;
 use std::io::{prelude::*, BufReader};
 
-use crate::instructions::InstructionSet;
-use crate::memory::InnerData;
+use crate::instructions::{InstructionSet, Operands};
+use crate::memory::{InnerData, StructType};
+
+/// Opens the optional section of struct types at the start of a binary.
+const TYPE_SECTION: u8 = 255;
+const STARTSTR: u8 = 12;
+const ENDSTR: u8 = 13;
+
+/// A program together with the struct types its `NEW` instructions refer to.
+pub struct Binary {
+    pub types: Vec<StructType>,
+    pub program: Vec<InstructionSet>,
+}
 
 #[derive(Debug)]
 enum State {
     ReadOneArg,
     ReadTwoArgs,
+    ReadLabelAndCount,
     GenInstruction,
     ReadInstruction,
     ReadObject,
@@ -24,6 +36,7 @@ fn read_file_line_by_line(filepath: &str) -> Result<Vec<u8>, Box<dyn std::error:
     Ok(file_bytes)
 }
 
+#[allow(clippy::char_lit_as_u8, clippy::needless_borrow)]
 fn in_range_or_promote(data_arg: &String, range: &[u8],
                        in_range_type: &str, out_range_type: &str) -> InnerData {
     let mut value_bytes = Vec::new();
@@ -41,7 +54,101 @@ fn in_range_or_promote(data_arg: &String, range: &[u8],
     }
 }
 
+/// Integer literals may carry a Rust-style type suffix (`200u8`, `5i64`) to pick
+/// the type explicitly instead of the narrowest signed type that fits. Literals
+/// with a decimal point or an `f64` suffix are floats.
+fn typed_literal(data_arg: &str) -> Option<InnerData> {
+    if let Some(digits) = data_arg.strip_suffix("f64") {
+        return Some(InnerData::from(digits, "FLOAT"));
+    }
+
+    if data_arg.contains('.') {
+        return Some(InnerData::from(data_arg, "FLOAT"));
+    }
+
+    let suffixes = [
+        ("i8", "INT"),
+        ("i16", "INT16"),
+        ("i32", "INT32"),
+        ("i64", "INT64"),
+        ("u8", "U8"),
+        ("u16", "U16"),
+        ("u32", "U32"),
+        ("u64", "U64"),
+    ];
+
+    for (suffix, variant) in suffixes {
+        if let Some(digits) = data_arg.strip_suffix(suffix) {
+            return Some(InnerData::from(digits, variant));
+        }
+    }
+
+    None
+}
+
+/// Data slot operands wider than a byte are written like stack literals, as
+/// decimal digits between `STARTSTR` and `ENDSTR`. Checking for the digits keeps
+/// a one-byte slot index equal to `STARTSTR` readable.
+fn is_wide_slot(buffer: &[u8], start: usize) -> bool {
+    let digits = buffer[start..].iter().take_while(|byte| byte.is_ascii_digit()).count();
+    digits > 0 && buffer.get(start + digits) == Some(&ENDSTR)
+}
+
+/// Reads a `STARTSTR ... ENDSTR` string starting at `i`, returning it and the
+/// index just past it.
+fn read_string(buffer: &[u8], i: usize) -> (String, usize) {
+    if buffer.get(i) != Some(&STARTSTR) {
+        panic!("Expected a string in type section");
+    }
+
+    let mut j = i + 1;
+    let mut value = String::new();
+
+    while buffer[j] != ENDSTR {
+        value.push(buffer[j] as char);
+        j += 1;
+    }
+
+    (value, j + 1)
+}
+
+/// Reads the type section, if there is one: `TYPE_SECTION count`, then for each
+/// type its name, field count and field names. Returns the types and the index
+/// where the instructions start.
+fn read_types(buffer: &[u8]) -> (Vec<StructType>, usize) {
+    let mut types = Vec::new();
+
+    if buffer.first() != Some(&TYPE_SECTION) {
+        return (types, 0);
+    }
+
+    let count = buffer[1];
+    let mut i = 2;
+
+    for _ in 0..count {
+        let (name, next) = read_string(buffer, i);
+        let field_count = buffer[next];
+        i = next + 1;
+
+        let mut fields = Vec::new();
+        for _ in 0..field_count {
+            let (field, next) = read_string(buffer, i);
+            fields.push(field);
+            i = next;
+        }
+
+        types.push(StructType { name, fields });
+    }
+
+    (types, i)
+}
+
 pub fn read_from_file(filepath: &str) -> Vec<InstructionSet> {
+    read_binary(filepath).program
+}
+
+#[allow(clippy::useless_vec, clippy::unnecessary_cast)]
+pub fn read_binary(filepath: &str) -> Binary {
     let mut buffer = Vec::new();
     match read_file_line_by_line(filepath) {
         Ok(lines) => {
@@ -50,22 +157,13 @@ pub fn read_from_file(filepath: &str) -> Vec<InstructionSet> {
         Err(e) => println!("Error: {}", e),
     }
 
-    let instruction_with_arg = vec![
-        8, // JMP
-        10, // JZ
-        11, // JN
-        16, // CALL
-    ];
-
-    let instruction_with_two_args = vec![
-        0,  // LOAD
-        9,  // POP
-    ];
+    let (types, start) = read_types(&buffer);
 
     let mut object_instructions_with_end = HashMap::new();
-    object_instructions_with_end.insert(12, 13); // 12 = STARTSTR, 13 = ENDSTR
+    object_instructions_with_end.insert(STARTSTR, ENDSTR);
 
     let object_offsets = vec![2, 3]; // 2 = STACK_OFFSET, 3 = STACK_OFFSET_STR
+    let slot_offsets = [4, 5, 6]; // 4 = DATA_MEMORY_OFFSET, 5 = ADDR_OFFSET, 6 = PTR_OFFSET
 
     let mut program = Vec::new();
     let mut state = State::ReadInstruction;
@@ -76,7 +174,7 @@ pub fn read_from_file(filepath: &str) -> Vec<InstructionSet> {
     let mut instruction: u8;
     let mut arg;
 
-    let mut i = 0;
+    let mut i = start;
     while i < buffer.len() {
         match state {
             State::ReadOneArg => {
@@ -87,16 +185,31 @@ pub fn read_from_file(filepath: &str) -> Vec<InstructionSet> {
                 i += 1;
             },
             State::ReadTwoArgs => {
-                arg = buffer[i] as i8;
-                arg_stack.push(InnerData::INT(arg));
+                let offset = buffer[i];
+                arg_stack.push(InnerData::INT(offset as i8));
 
                 i += 1;
                 arg = buffer[i] as i8;
-                arg_stack.push(InnerData::INT(arg));
+
+                if offset == 7 { // 7 = STACK_OFFSET_BOOL
+                    arg_stack.push(InnerData::BOOL(arg != 0));
+                } else {
+                    arg_stack.push(InnerData::INT(arg));
+                }
 
                 state = State::GenInstruction;
                 i += 1;
             },
+            State::ReadLabelAndCount => {
+                let label = buffer[i] as i8;
+                let count = buffer[i + 1] as i8;
+
+                arg_stack.push(InnerData::INT(count));
+                arg_stack.push(InnerData::INT(label));
+
+                state = State::GenInstruction;
+                i += 2;
+            },
             State::GenInstruction => {
                 instruction = match instruction_stack.pop() {
                     Some(instruction) => instruction as u8,
@@ -115,17 +228,21 @@ pub fn read_from_file(filepath: &str) -> Vec<InstructionSet> {
                 instruction = buffer[i] as u8;
                 instruction_stack.push(instruction);
 
-                if instruction_with_arg.contains(&instruction) {
-                    state = State::ReadOneArg;
-                } else if instruction_with_two_args.contains(&instruction) {
-                    if object_offsets.contains(&buffer[i+1]) && object_instructions_with_end.contains_key(&buffer[i + 2]) {
-                        state = State::ReadObject;
-                    } else {
-                        state = State::ReadTwoArgs;
-                    }
-                } 
-                else {
-                    state = State::GenInstruction;
+                match InstructionSet::operands(instruction) {
+                    Some(Operands::One) => state = State::ReadOneArg,
+                    Some(Operands::LabelAndCount) => state = State::ReadLabelAndCount,
+                    Some(Operands::ValueAndOffset) => {
+                        let is_object = object_offsets.contains(&buffer[i+1]) && object_instructions_with_end.contains_key(&buffer[i + 2]);
+                        let wide_slot = slot_offsets.contains(&buffer[i+1]) && buffer[i + 2] == STARTSTR &&
+                                        is_wide_slot(&buffer, i + 3);
+
+                        if is_object || wide_slot {
+                            state = State::ReadObject;
+                        } else {
+                            state = State::ReadTwoArgs;
+                        }
+                    },
+                    Some(Operands::Zero) | None => state = State::GenInstruction,
                 }
 
                 i += 1;
@@ -146,39 +263,52 @@ pub fn read_from_file(filepath: &str) -> Vec<InstructionSet> {
 
                 match offset {
                     2 => {
-                        match data_arg.len() {
-                            1 | 2 => {
-                                arg_stack.push(InnerData::from(&data_arg, "INT"));
-                            },
-                            3 => {
-                                let range = vec![1, 2, 8];
-                                arg_stack.push(in_range_or_promote(&data_arg, &range, 
-                                                                   "INT", "INT16"));
-                            },
-                            4 => {
-                                arg_stack.push(InnerData::from(&data_arg, "INT16"));
-                            },
-                            5 => {
-                                let range = vec![3, 2, 7, 6, 7];
-                                arg_stack.push(in_range_or_promote(&data_arg, &range, 
-                                                                   "INT16", "INT32"));
-                            },
-                            6 | 7 | 8 | 9 => {
-                                arg_stack.push(InnerData::from(&data_arg, "INT32"));
-                            }
-                            10 => {
-                                let range = vec![2, 1, 4, 7, 4, 8, 3, 6, 4, 7];
-                                arg_stack.push(in_range_or_promote(&data_arg, &range, 
-                                                                   "INT32", "ERR"));
-                            }
-                            _ => {
-
+                        if let Some(value) = typed_literal(&data_arg) {
+                            arg_stack.push(value);
+                        } else {
+                            match data_arg.len() {
+                                1 | 2 => {
+                                    arg_stack.push(InnerData::from(&data_arg, "INT"));
+                                },
+                                3 => {
+                                    let range = vec![1, 2, 8];
+                                    arg_stack.push(in_range_or_promote(&data_arg, &range, 
+                                                                       "INT", "INT16"));
+                                },
+                                4 => {
+                                    arg_stack.push(InnerData::from(&data_arg, "INT16"));
+                                },
+                                5 => {
+                                    let range = vec![3, 2, 7, 6, 7];
+                                    arg_stack.push(in_range_or_promote(&data_arg, &range, 
+                                                                       "INT16", "INT32"));
+                                },
+                                6..=9 => {
+                                    arg_stack.push(InnerData::from(&data_arg, "INT32"));
+                                }
+                                10 => {
+                                    let range = vec![2, 1, 4, 7, 4, 8, 3, 6, 4, 7];
+                                    arg_stack.push(in_range_or_promote(&data_arg, &range, 
+                                                                       "INT32", "INT64"));
+                                }
+                                11..=18 => {
+                                    arg_stack.push(InnerData::from(&data_arg, "INT64"));
+                                }
+                                _ => {
+                                    arg_stack.push(match data_arg.parse::<i64>() {
+                                        Ok(value) => InnerData::INT64(value),
+                                        Err(_) => InnerData::from(&data_arg, "BIGINT"),
+                                    });
+                                }
                             }
                         }
                     },
                     3 => {
                         arg_stack.push(InnerData::STR(data_arg));
                     },
+                    4..=6 => {
+                        arg_stack.push(InnerData::from(&data_arg, "U32"));
+                    },
                     _ => {
                         panic!("Unknown object offset");
                     }
@@ -197,5 +327,8 @@ pub fn read_from_file(filepath: &str) -> Vec<InstructionSet> {
         None
     ));
 
-    program
+    Binary {
+        types,
+        program,
+    }
 }
\ No newline at end of file
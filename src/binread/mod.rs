@@ -1,3 +1,3 @@
 pub mod bin_read;
 
-pub use bin_read::read_from_file;
\ No newline at end of file
+pub use bin_read::{read_from_file, read_binary, Binary};
\ No newline at end of file
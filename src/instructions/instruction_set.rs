@@ -1,6 +1,17 @@
 use crate::memory::InnerData;
 
-#[derive(Debug)]
+/// The operands an instruction carries after its opcode.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Operands {
+    Zero,
+    One,
+    /// A value and the stack or memory offset it applies to, as for `LOAD` and `POP`.
+    ValueAndOffset,
+    /// A label and an argument count, as for `CALLF`.
+    LabelAndCount,
+}
+
+#[derive(Debug, Clone)]
 pub enum InstructionSet {
     LOAD(InnerData, u8),
     ADD,
@@ -22,6 +33,87 @@ pub enum InstructionSet {
     EQU,
     NEG,
     DEREF,
+    READ,
+    LT,
+    LE,
+    GT,
+    GE,
+    NE,
+    JNZ(InnerData),
+    JP(InnerData),
+    JT(InnerData),
+    JF(InnerData),
+    CMP,
+    JC(InnerData),
+    JO(InnerData),
+    JNC(InnerData),
+    JNO(InnerData),
+    PUSHF,
+    POPF,
+    AND,
+    OR,
+    XOR,
+    NOT,
+    SHL,
+    SHR,
+    USHR,
+    DUP,
+    DROP,
+    SWAP,
+    OVER,
+    ROT,
+    PICK(InnerData),
+    ROLL(InnerData),
+    ITOF,
+    FTOI(InnerData),
+    ADDP(InnerData),
+    SUBP(InnerData),
+    MULP(InnerData),
+    DIVP(InnerData),
+    MODP(InnerData),
+    NEGP(InnerData),
+    WIDEN(InnerData),
+    NARROW(InnerData),
+    NARROWC(InnerData),
+    SEXT(InnerData),
+    ZEXT(InnerData),
+    PARSE,
+    TOSTR,
+    LEN,
+    CHARAT,
+    SUBSTR,
+    FIND,
+    SPLIT,
+    UPPER,
+    LOWER,
+    TRIM,
+    ORD,
+    CHR,
+    FORMAT(InnerData),
+    PRINT,
+    NEWARRAY(InnerData),
+    ALOAD,
+    ASTORE,
+    ALEN,
+    APPEND,
+    NEW(InnerData),
+    GETFIELD(InnerData),
+    SETFIELD(InnerData),
+    MAPNEW,
+    MAPGET,
+    MAPSET,
+    MAPHAS,
+    MAPDEL,
+    MAPKEYS,
+    MLOAD(InnerData),
+    MSTORE(InnerData),
+    MSIZE,
+    CALLF(InnerData, InnerData),
+    RETF(InnerData),
+    LOADLOCAL(InnerData),
+    STORELOCAL(InnerData),
+    FUNCREF(InnerData),
+    CALLI(InnerData),
 }
 
 impl PartialEq for InstructionSet {
@@ -47,12 +139,105 @@ impl PartialEq for InstructionSet {
             (InstructionSet::EQU, InstructionSet::EQU) => true,
             (InstructionSet::NEG, InstructionSet::NEG) => true,
             (InstructionSet::DEREF, InstructionSet::DEREF) => true,
+            (InstructionSet::READ, InstructionSet::READ) => true,
+            (InstructionSet::LT, InstructionSet::LT) => true,
+            (InstructionSet::LE, InstructionSet::LE) => true,
+            (InstructionSet::GT, InstructionSet::GT) => true,
+            (InstructionSet::GE, InstructionSet::GE) => true,
+            (InstructionSet::NE, InstructionSet::NE) => true,
+            (InstructionSet::JNZ(a), InstructionSet::JNZ(b)) => a == b,
+            (InstructionSet::JP(a), InstructionSet::JP(b)) => a == b,
+            (InstructionSet::JT(a), InstructionSet::JT(b)) => a == b,
+            (InstructionSet::JF(a), InstructionSet::JF(b)) => a == b,
+            (InstructionSet::CMP, InstructionSet::CMP) => true,
+            (InstructionSet::JC(a), InstructionSet::JC(b)) => a == b,
+            (InstructionSet::JO(a), InstructionSet::JO(b)) => a == b,
+            (InstructionSet::JNC(a), InstructionSet::JNC(b)) => a == b,
+            (InstructionSet::JNO(a), InstructionSet::JNO(b)) => a == b,
+            (InstructionSet::PUSHF, InstructionSet::PUSHF) => true,
+            (InstructionSet::POPF, InstructionSet::POPF) => true,
+            (InstructionSet::AND, InstructionSet::AND) => true,
+            (InstructionSet::OR, InstructionSet::OR) => true,
+            (InstructionSet::XOR, InstructionSet::XOR) => true,
+            (InstructionSet::NOT, InstructionSet::NOT) => true,
+            (InstructionSet::SHL, InstructionSet::SHL) => true,
+            (InstructionSet::SHR, InstructionSet::SHR) => true,
+            (InstructionSet::USHR, InstructionSet::USHR) => true,
+            (InstructionSet::DUP, InstructionSet::DUP) => true,
+            (InstructionSet::DROP, InstructionSet::DROP) => true,
+            (InstructionSet::SWAP, InstructionSet::SWAP) => true,
+            (InstructionSet::OVER, InstructionSet::OVER) => true,
+            (InstructionSet::ROT, InstructionSet::ROT) => true,
+            (InstructionSet::PICK(a), InstructionSet::PICK(b)) => a == b,
+            (InstructionSet::ROLL(a), InstructionSet::ROLL(b)) => a == b,
+            (InstructionSet::ITOF, InstructionSet::ITOF) => true,
+            (InstructionSet::FTOI(a), InstructionSet::FTOI(b)) => a == b,
+            (InstructionSet::ADDP(a), InstructionSet::ADDP(b)) => a == b,
+            (InstructionSet::SUBP(a), InstructionSet::SUBP(b)) => a == b,
+            (InstructionSet::MULP(a), InstructionSet::MULP(b)) => a == b,
+            (InstructionSet::DIVP(a), InstructionSet::DIVP(b)) => a == b,
+            (InstructionSet::MODP(a), InstructionSet::MODP(b)) => a == b,
+            (InstructionSet::NEGP(a), InstructionSet::NEGP(b)) => a == b,
+            (InstructionSet::WIDEN(a), InstructionSet::WIDEN(b)) => a == b,
+            (InstructionSet::NARROW(a), InstructionSet::NARROW(b)) => a == b,
+            (InstructionSet::NARROWC(a), InstructionSet::NARROWC(b)) => a == b,
+            (InstructionSet::SEXT(a), InstructionSet::SEXT(b)) => a == b,
+            (InstructionSet::ZEXT(a), InstructionSet::ZEXT(b)) => a == b,
+            (InstructionSet::PARSE, InstructionSet::PARSE) => true,
+            (InstructionSet::TOSTR, InstructionSet::TOSTR) => true,
+            (InstructionSet::LEN, InstructionSet::LEN) => true,
+            (InstructionSet::CHARAT, InstructionSet::CHARAT) => true,
+            (InstructionSet::SUBSTR, InstructionSet::SUBSTR) => true,
+            (InstructionSet::FIND, InstructionSet::FIND) => true,
+            (InstructionSet::SPLIT, InstructionSet::SPLIT) => true,
+            (InstructionSet::UPPER, InstructionSet::UPPER) => true,
+            (InstructionSet::LOWER, InstructionSet::LOWER) => true,
+            (InstructionSet::TRIM, InstructionSet::TRIM) => true,
+            (InstructionSet::ORD, InstructionSet::ORD) => true,
+            (InstructionSet::CHR, InstructionSet::CHR) => true,
+            (InstructionSet::FORMAT(a), InstructionSet::FORMAT(b)) => a == b,
+            (InstructionSet::PRINT, InstructionSet::PRINT) => true,
+            (InstructionSet::NEWARRAY(a), InstructionSet::NEWARRAY(b)) => a == b,
+            (InstructionSet::ALOAD, InstructionSet::ALOAD) => true,
+            (InstructionSet::ASTORE, InstructionSet::ASTORE) => true,
+            (InstructionSet::ALEN, InstructionSet::ALEN) => true,
+            (InstructionSet::APPEND, InstructionSet::APPEND) => true,
+            (InstructionSet::NEW(a), InstructionSet::NEW(b)) => a == b,
+            (InstructionSet::GETFIELD(a), InstructionSet::GETFIELD(b)) => a == b,
+            (InstructionSet::SETFIELD(a), InstructionSet::SETFIELD(b)) => a == b,
+            (InstructionSet::MAPNEW, InstructionSet::MAPNEW) => true,
+            (InstructionSet::MAPGET, InstructionSet::MAPGET) => true,
+            (InstructionSet::MAPSET, InstructionSet::MAPSET) => true,
+            (InstructionSet::MAPHAS, InstructionSet::MAPHAS) => true,
+            (InstructionSet::MAPDEL, InstructionSet::MAPDEL) => true,
+            (InstructionSet::MAPKEYS, InstructionSet::MAPKEYS) => true,
+            (InstructionSet::MLOAD(a), InstructionSet::MLOAD(b)) => a == b,
+            (InstructionSet::MSTORE(a), InstructionSet::MSTORE(b)) => a == b,
+            (InstructionSet::MSIZE, InstructionSet::MSIZE) => true,
+            (InstructionSet::CALLF(a, b), InstructionSet::CALLF(c, d)) => a == c && b == d,
+            (InstructionSet::RETF(a), InstructionSet::RETF(b)) => a == b,
+            (InstructionSet::LOADLOCAL(a), InstructionSet::LOADLOCAL(b)) => a == b,
+            (InstructionSet::STORELOCAL(a), InstructionSet::STORELOCAL(b)) => a == b,
+            (InstructionSet::FUNCREF(a), InstructionSet::FUNCREF(b)) => a == b,
+            (InstructionSet::CALLI(a), InstructionSet::CALLI(b)) => a == b,
             _ => false,
         }
     }
 }
 
 impl InstructionSet {
+    /// The operands that follow `opcode`, or `None` if no instruction has that opcode.
+    pub fn operands(opcode: u8) -> Option<Operands> {
+        match opcode {
+            0 | 9 => Some(Operands::ValueAndOffset),
+            95 => Some(Operands::LabelAndCount),
+            8 | 10 | 11 | 16 | 26..=29 | 31..=34 | 49 | 50 | 52..=63 | 76 | 78 | 83..=85 | 92 | 93 |
+            96..=100 => Some(Operands::One),
+            0..=100 => Some(Operands::Zero),
+            _ => None,
+        }
+    }
+
     pub fn from_int(value: u8, arg: Option<InnerData>, arg1: Option<InnerData>) -> Self {
         match value {
             0 => {
@@ -119,7 +304,376 @@ impl InstructionSet {
             17 => InstructionSet::EQU,
             18 => InstructionSet::NEG,
             19 => InstructionSet::DEREF,
+            20 => InstructionSet::READ,
+            21 => InstructionSet::LT,
+            22 => InstructionSet::LE,
+            23 => InstructionSet::GT,
+            24 => InstructionSet::GE,
+            25 => InstructionSet::NE,
+            26 => {
+                match arg {
+                    Some(arg) => InstructionSet::JNZ(arg),
+                    None => panic!("InstructionSet::JNZ: arg is None"),
+                }
+            },
+            27 => {
+                match arg {
+                    Some(arg) => InstructionSet::JP(arg),
+                    None => panic!("InstructionSet::JP: arg is None"),
+                }
+            },
+            28 => {
+                match arg {
+                    Some(arg) => InstructionSet::JT(arg),
+                    None => panic!("InstructionSet::JT: arg is None"),
+                }
+            },
+            29 => {
+                match arg {
+                    Some(arg) => InstructionSet::JF(arg),
+                    None => panic!("InstructionSet::JF: arg is None"),
+                }
+            },
+            30 => InstructionSet::CMP,
+            31 => {
+                match arg {
+                    Some(arg) => InstructionSet::JC(arg),
+                    None => panic!("InstructionSet::JC: arg is None"),
+                }
+            },
+            32 => {
+                match arg {
+                    Some(arg) => InstructionSet::JO(arg),
+                    None => panic!("InstructionSet::JO: arg is None"),
+                }
+            },
+            33 => {
+                match arg {
+                    Some(arg) => InstructionSet::JNC(arg),
+                    None => panic!("InstructionSet::JNC: arg is None"),
+                }
+            },
+            34 => {
+                match arg {
+                    Some(arg) => InstructionSet::JNO(arg),
+                    None => panic!("InstructionSet::JNO: arg is None"),
+                }
+            },
+            35 => InstructionSet::PUSHF,
+            36 => InstructionSet::POPF,
+            37 => InstructionSet::AND,
+            38 => InstructionSet::OR,
+            39 => InstructionSet::XOR,
+            40 => InstructionSet::NOT,
+            41 => InstructionSet::SHL,
+            42 => InstructionSet::SHR,
+            43 => InstructionSet::USHR,
+            44 => InstructionSet::DUP,
+            45 => InstructionSet::DROP,
+            46 => InstructionSet::SWAP,
+            47 => InstructionSet::OVER,
+            48 => InstructionSet::ROT,
+            49 => {
+                match arg {
+                    Some(arg) => InstructionSet::PICK(arg),
+                    None => panic!("InstructionSet::PICK: arg is None"),
+                }
+            },
+            50 => {
+                match arg {
+                    Some(arg) => InstructionSet::ROLL(arg),
+                    None => panic!("InstructionSet::ROLL: arg is None"),
+                }
+            },
+            51 => InstructionSet::ITOF,
+            52 => {
+                match arg {
+                    Some(arg) => InstructionSet::FTOI(arg),
+                    None => panic!("InstructionSet::FTOI: arg is None"),
+                }
+            },
+            53 => {
+                match arg {
+                    Some(arg) => InstructionSet::ADDP(arg),
+                    None => panic!("InstructionSet::ADDP: arg is None"),
+                }
+            },
+            54 => {
+                match arg {
+                    Some(arg) => InstructionSet::SUBP(arg),
+                    None => panic!("InstructionSet::SUBP: arg is None"),
+                }
+            },
+            55 => {
+                match arg {
+                    Some(arg) => InstructionSet::MULP(arg),
+                    None => panic!("InstructionSet::MULP: arg is None"),
+                }
+            },
+            56 => {
+                match arg {
+                    Some(arg) => InstructionSet::DIVP(arg),
+                    None => panic!("InstructionSet::DIVP: arg is None"),
+                }
+            },
+            57 => {
+                match arg {
+                    Some(arg) => InstructionSet::MODP(arg),
+                    None => panic!("InstructionSet::MODP: arg is None"),
+                }
+            },
+            58 => {
+                match arg {
+                    Some(arg) => InstructionSet::NEGP(arg),
+                    None => panic!("InstructionSet::NEGP: arg is None"),
+                }
+            },
+            59 => {
+                match arg {
+                    Some(arg) => InstructionSet::WIDEN(arg),
+                    None => panic!("InstructionSet::WIDEN: arg is None"),
+                }
+            },
+            60 => {
+                match arg {
+                    Some(arg) => InstructionSet::NARROW(arg),
+                    None => panic!("InstructionSet::NARROW: arg is None"),
+                }
+            },
+            61 => {
+                match arg {
+                    Some(arg) => InstructionSet::NARROWC(arg),
+                    None => panic!("InstructionSet::NARROWC: arg is None"),
+                }
+            },
+            62 => {
+                match arg {
+                    Some(arg) => InstructionSet::SEXT(arg),
+                    None => panic!("InstructionSet::SEXT: arg is None"),
+                }
+            },
+            63 => {
+                match arg {
+                    Some(arg) => InstructionSet::ZEXT(arg),
+                    None => panic!("InstructionSet::ZEXT: arg is None"),
+                }
+            },
+            64 => InstructionSet::PARSE,
+            65 => InstructionSet::TOSTR,
+            66 => InstructionSet::LEN,
+            67 => InstructionSet::CHARAT,
+            68 => InstructionSet::SUBSTR,
+            69 => InstructionSet::FIND,
+            70 => InstructionSet::SPLIT,
+            71 => InstructionSet::UPPER,
+            72 => InstructionSet::LOWER,
+            73 => InstructionSet::TRIM,
+            74 => InstructionSet::ORD,
+            75 => InstructionSet::CHR,
+            76 => {
+                match arg {
+                    Some(arg) => InstructionSet::FORMAT(arg),
+                    None => panic!("InstructionSet::FORMAT: arg is None"),
+                }
+            },
+            77 => InstructionSet::PRINT,
+            78 => {
+                match arg {
+                    Some(arg) => InstructionSet::NEWARRAY(arg),
+                    None => panic!("InstructionSet::NEWARRAY: arg is None"),
+                }
+            },
+            79 => InstructionSet::ALOAD,
+            80 => InstructionSet::ASTORE,
+            81 => InstructionSet::ALEN,
+            82 => InstructionSet::APPEND,
+            83 => {
+                match arg {
+                    Some(arg) => InstructionSet::NEW(arg),
+                    None => panic!("InstructionSet::NEW: arg is None"),
+                }
+            },
+            84 => {
+                match arg {
+                    Some(arg) => InstructionSet::GETFIELD(arg),
+                    None => panic!("InstructionSet::GETFIELD: arg is None"),
+                }
+            },
+            85 => {
+                match arg {
+                    Some(arg) => InstructionSet::SETFIELD(arg),
+                    None => panic!("InstructionSet::SETFIELD: arg is None"),
+                }
+            },
+            86 => InstructionSet::MAPNEW,
+            87 => InstructionSet::MAPGET,
+            88 => InstructionSet::MAPSET,
+            89 => InstructionSet::MAPHAS,
+            90 => InstructionSet::MAPDEL,
+            91 => InstructionSet::MAPKEYS,
+            92 => {
+                match arg {
+                    Some(arg) => InstructionSet::MLOAD(arg),
+                    None => panic!("InstructionSet::MLOAD: arg is None"),
+                }
+            },
+            93 => {
+                match arg {
+                    Some(arg) => InstructionSet::MSTORE(arg),
+                    None => panic!("InstructionSet::MSTORE: arg is None"),
+                }
+            },
+            94 => InstructionSet::MSIZE,
+            95 => {
+                let first_arg = match arg {
+                    Some(arg) => arg,
+                    None => panic!("InstructionSet::CALLF: arg is None"),
+                };
+
+                let second_arg = match arg1 {
+                    Some(arg) => arg,
+                    None => panic!("InstructionSet::CALLF: arg1 is None"),
+                };
+
+                InstructionSet::CALLF(first_arg, second_arg)
+            },
+            96 => {
+                match arg {
+                    Some(arg) => InstructionSet::RETF(arg),
+                    None => panic!("InstructionSet::RETF: arg is None"),
+                }
+            },
+            97 => {
+                match arg {
+                    Some(arg) => InstructionSet::LOADLOCAL(arg),
+                    None => panic!("InstructionSet::LOADLOCAL: arg is None"),
+                }
+            },
+            98 => {
+                match arg {
+                    Some(arg) => InstructionSet::STORELOCAL(arg),
+                    None => panic!("InstructionSet::STORELOCAL: arg is None"),
+                }
+            },
+            99 => {
+                match arg {
+                    Some(arg) => InstructionSet::FUNCREF(arg),
+                    None => panic!("InstructionSet::FUNCREF: arg is None"),
+                }
+            },
+            100 => {
+                match arg {
+                    Some(arg) => InstructionSet::CALLI(arg),
+                    None => panic!("InstructionSet::CALLI: arg is None"),
+                }
+            },
             _ => panic!("Invalid instruction set value: {}", value),
         }
     }
+
+    pub fn opcode(&self) -> u8 {
+        match self {
+            InstructionSet::LOAD(_, _) => 0,
+            InstructionSet::ADD => 1,
+            InstructionSet::SUB => 2,
+            InstructionSet::MUL => 3,
+            InstructionSet::DIV => 4,
+            InstructionSet::HALT => 5,
+            InstructionSet::MOD => 6,
+            InstructionSet::LABEL => 7,
+            InstructionSet::JMP(_) => 8,
+            InstructionSet::POP(_, _) => 9,
+            InstructionSet::JZ(_) => 10,
+            InstructionSet::JN(_) => 11,
+            InstructionSet::STARTSTR => 12,
+            InstructionSet::ENDSTR => 13,
+            InstructionSet::SHOW => 14,
+            InstructionSet::RET => 15,
+            InstructionSet::CALL(_) => 16,
+            InstructionSet::EQU => 17,
+            InstructionSet::NEG => 18,
+            InstructionSet::DEREF => 19,
+            InstructionSet::READ => 20,
+            InstructionSet::LT => 21,
+            InstructionSet::LE => 22,
+            InstructionSet::GT => 23,
+            InstructionSet::GE => 24,
+            InstructionSet::NE => 25,
+            InstructionSet::JNZ(_) => 26,
+            InstructionSet::JP(_) => 27,
+            InstructionSet::JT(_) => 28,
+            InstructionSet::JF(_) => 29,
+            InstructionSet::CMP => 30,
+            InstructionSet::JC(_) => 31,
+            InstructionSet::JO(_) => 32,
+            InstructionSet::JNC(_) => 33,
+            InstructionSet::JNO(_) => 34,
+            InstructionSet::PUSHF => 35,
+            InstructionSet::POPF => 36,
+            InstructionSet::AND => 37,
+            InstructionSet::OR => 38,
+            InstructionSet::XOR => 39,
+            InstructionSet::NOT => 40,
+            InstructionSet::SHL => 41,
+            InstructionSet::SHR => 42,
+            InstructionSet::USHR => 43,
+            InstructionSet::DUP => 44,
+            InstructionSet::DROP => 45,
+            InstructionSet::SWAP => 46,
+            InstructionSet::OVER => 47,
+            InstructionSet::ROT => 48,
+            InstructionSet::PICK(_) => 49,
+            InstructionSet::ROLL(_) => 50,
+            InstructionSet::ITOF => 51,
+            InstructionSet::FTOI(_) => 52,
+            InstructionSet::ADDP(_) => 53,
+            InstructionSet::SUBP(_) => 54,
+            InstructionSet::MULP(_) => 55,
+            InstructionSet::DIVP(_) => 56,
+            InstructionSet::MODP(_) => 57,
+            InstructionSet::NEGP(_) => 58,
+            InstructionSet::WIDEN(_) => 59,
+            InstructionSet::NARROW(_) => 60,
+            InstructionSet::NARROWC(_) => 61,
+            InstructionSet::SEXT(_) => 62,
+            InstructionSet::ZEXT(_) => 63,
+            InstructionSet::PARSE => 64,
+            InstructionSet::TOSTR => 65,
+            InstructionSet::LEN => 66,
+            InstructionSet::CHARAT => 67,
+            InstructionSet::SUBSTR => 68,
+            InstructionSet::FIND => 69,
+            InstructionSet::SPLIT => 70,
+            InstructionSet::UPPER => 71,
+            InstructionSet::LOWER => 72,
+            InstructionSet::TRIM => 73,
+            InstructionSet::ORD => 74,
+            InstructionSet::CHR => 75,
+            InstructionSet::FORMAT(_) => 76,
+            InstructionSet::PRINT => 77,
+            InstructionSet::NEWARRAY(_) => 78,
+            InstructionSet::ALOAD => 79,
+            InstructionSet::ASTORE => 80,
+            InstructionSet::ALEN => 81,
+            InstructionSet::APPEND => 82,
+            InstructionSet::NEW(_) => 83,
+            InstructionSet::GETFIELD(_) => 84,
+            InstructionSet::SETFIELD(_) => 85,
+            InstructionSet::MAPNEW => 86,
+            InstructionSet::MAPGET => 87,
+            InstructionSet::MAPSET => 88,
+            InstructionSet::MAPHAS => 89,
+            InstructionSet::MAPDEL => 90,
+            InstructionSet::MAPKEYS => 91,
+            InstructionSet::MLOAD(_) => 92,
+            InstructionSet::MSTORE(_) => 93,
+            InstructionSet::MSIZE => 94,
+            InstructionSet::CALLF(_, _) => 95,
+            InstructionSet::RETF(_) => 96,
+            InstructionSet::LOADLOCAL(_) => 97,
+            InstructionSet::STORELOCAL(_) => 98,
+            InstructionSet::FUNCREF(_) => 99,
+            InstructionSet::CALLI(_) => 100,
+        }
+    }
 }
\ No newline at end of file
@@ -1,3 +1,3 @@
 pub mod instruction_set;
 
-pub use instruction_set::InstructionSet;
\ No newline at end of file
+pub use instruction_set::{InstructionSet, Operands};
\ No newline at end of file
@@ -1,4 +1,7 @@
 pub mod instructions;
 pub mod memory;
 pub mod processor;
-pub mod binread;
\ No newline at end of file
+pub mod binread;
+pub mod snapshot;
+pub mod replay;
+pub mod vm;
\ No newline at end of file
@@ -1,46 +1,116 @@
 extern crate sargparse;
 
-use std::io;
+use std::env;
+use std::process;
 use sargparse::{ArgumentParser, ArgumentType, InnerData};
 
-use yamini::memory::DataMemory;
-use yamini::memory::Stack;
-use yamini::memory::{ProgramMemory};
-use yamini::processor::Processor;
-use yamini::binread::read_from_file;
+use yamini::processor::VmError;
+use yamini::binread::read_binary;
+use yamini::snapshot::Snapshot;
+use yamini::vm::{Vm, DEFAULT_LINEAR_MEMORY};
 
 fn main() {
+    let resume = env::args().nth(1).as_deref() == Some("resume");
+
     let mut parser = ArgumentParser::new(Some("YamASM - Assembler for YaminiVM"));
 
-    parser.add_argument("f", "file_path", "File path to executable binary", 
-                        true, None, ArgumentType::STR);
-    parser.add_argument("-i", "--instructions", "Flag to print compiled instructions",
+    if resume {
+        parser.add_argument("c", "command", "Subcommand to run",
+                            true, None, ArgumentType::STR);
+        parser.add_argument("p", "snapshot_path", "File path to snapshot to resume",
+                            true, None, ArgumentType::STR);
+    } else {
+        parser.add_argument("f", "file_path", "File path to executable binary", 
+                            true, None, ArgumentType::STR);
+        parser.add_argument("-i", "--instructions", "Flag to print compiled instructions",
+                            false, Some(InnerData::BOOL(false)), ArgumentType::BOOL);
+    }
+    parser.add_argument("-m", "--max-steps", "Maximum number of instructions to execute (0 for no limit)",
+                        false, Some(InnerData::INT(0)), ArgumentType::INT);
+    parser.add_argument("-s", "--snapshot", "File path to write a snapshot to if execution runs out of steps",
+                        false, Some(InnerData::STR(String::new())), ArgumentType::STR);
+
+    parser.add_argument("-g", "--gc-stress", "Flag to collect the heap before every allocation",
+                        false, Some(InnerData::BOOL(false)), ArgumentType::BOOL);
+    parser.add_argument("-t", "--gc-stats", "Flag to print garbage collector statistics on exit",
+                        false, Some(InnerData::BOOL(false)), ArgumentType::BOOL);
+
+    parser.add_argument("-r", "--strict", "Flag to fail on reads of data slots that were never written",
                         false, Some(InnerData::BOOL(false)), ArgumentType::BOOL);
+    parser.add_argument("-l", "--memory-size", "Bytes of linear memory for MLOAD and MSTORE",
+                        false, Some(InnerData::INT(DEFAULT_LINEAR_MEMORY as i32)), ArgumentType::INT);
 
     let args = parser.parse_args().unwrap();
 
-    let filepath = &args.get("file_path").unwrap().get_str();
-    let instructions_flag = args.get("instructions").unwrap().get_bool();
+    let max_steps = args.get("maxsteps").unwrap().get_int();
+    let snapshot_path = args.get("snapshot").unwrap().get_str();
 
-    let program = read_from_file(filepath);
+    let gc_stats = args.get("gcstats").unwrap().get_bool();
 
-    if instructions_flag {
-        println!("--------------------------------------------");
-        println!("Instructions:");
-        for instruction in &program {
-            println!("{:?}", instruction);
-        }
-        println!("--------------------------------------------");
+    let memory_size = args.get("memorysize").unwrap().get_int();
+    if memory_size < 0 {
+        eprintln!("Error: Invalid memory size!");
+        process::exit(1);
     }
 
-    let mut stack = Stack::new();
-    let mut call_stack = Stack::new();
+    let mut builder = Vm::builder()
+        .gc_stress(args.get("gcstress").unwrap().get_bool())
+        .strict_memory(args.get("strict").unwrap().get_bool())
+        .linear_memory(memory_size as usize);
+    if max_steps > 0 {
+        builder = builder.fuel(max_steps as u64);
+    }
 
-    let mut program_memory = ProgramMemory::new();
-    program_memory.load_program(program);
+    let mut vm = if resume {
+        let snapshot_path = &args.get("snapshot_path").unwrap().get_str();
+
+        match Snapshot::load(snapshot_path) {
+            Ok(snapshot) => {
+                let mut vm = builder.build();
+                vm.restore(snapshot);
+                vm
+            },
+            Err(error) => {
+                eprintln!("Error: {}", error);
+                process::exit(1);
+            },
+        }
+    } else {
+        let filepath = &args.get("file_path").unwrap().get_str();
+        let instructions_flag = args.get("instructions").unwrap().get_bool();
 
-    let mut data_memory = DataMemory::new();
+        let binary = read_binary(filepath);
+        let program = binary.program;
 
-    let mut processor = Processor::new();
-    processor.execute_program(program_memory, &mut data_memory, &mut stack, &mut call_stack, &mut io::stdout());
+        if instructions_flag {
+            println!("--------------------------------------------");
+            println!("Instructions:");
+            for instruction in &program {
+                println!("{:?}", instruction);
+            }
+            println!("--------------------------------------------");
+        }
+
+        builder.program(program).types(binary.types).build()
+    };
+
+    let result = vm.run();
+
+    if gc_stats {
+        let stats = vm.data_memory().heap().stats();
+        eprintln!("GC: {} collections, {} objects and {} cells freed, {} objects live",
+                  stats.collections, stats.objects_freed, stats.cells_freed,
+                  vm.data_memory().heap().len());
+    }
+
+    if let Err(error) = result {
+        if error == VmError::OutOfFuel && !snapshot_path.is_empty() {
+            if let Err(error) = vm.snapshot().save(&snapshot_path) {
+                eprintln!("Error: {}", error);
+            }
+        }
+
+        eprintln!("Error: {}", error);
+        process::exit(1);
+    }
 }
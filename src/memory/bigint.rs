@@ -0,0 +1,362 @@
+use std::ops::{Add, Sub, Mul, Div, Rem, Neg};
+use std::fmt::{Display, self};
+use std::cmp::Ordering;
+
+/// Arbitrary-precision signed integer. The magnitude is stored as little-endian
+/// base 2^32 limbs without trailing zero limbs, so zero has no limbs and is
+/// never negative.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BigInt {
+    negative: bool,
+    limbs: Vec<u32>,
+}
+
+impl BigInt {
+    pub fn zero() -> BigInt {
+        BigInt {
+            negative: false,
+            limbs: Vec::new(),
+        }
+    }
+
+    pub(crate) fn from_parts(negative: bool, limbs: Vec<u32>) -> BigInt {
+        BigInt::normalized(negative, limbs)
+    }
+
+    pub(crate) fn parts(&self) -> (bool, &[u32]) {
+        (self.negative, &self.limbs)
+    }
+
+    fn normalized(negative: bool, mut limbs: Vec<u32>) -> BigInt {
+        while limbs.last() == Some(&0) {
+            limbs.pop();
+        }
+
+        BigInt {
+            negative: negative && !limbs.is_empty(),
+            limbs,
+        }
+    }
+
+    /// Parses an optionally signed decimal number.
+    pub fn parse(data: &str) -> Option<BigInt> {
+        let (negative, digits) = match data.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, data),
+        };
+
+        if digits.is_empty() {
+            return None;
+        }
+
+        let mut limbs = Vec::new();
+        for ch in digits.chars() {
+            let digit = ch.to_digit(10)?;
+            BigInt::mul_small(&mut limbs, 10, digit);
+        }
+
+        Some(BigInt::normalized(negative, limbs))
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.limbs.is_empty()
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.negative
+    }
+
+    /// Number of bits in the magnitude, so zero has none.
+    pub fn bits(&self) -> usize {
+        match self.limbs.last() {
+            Some(top) => self.limbs.len() * 32 - top.leading_zeros() as usize,
+            None => 0,
+        }
+    }
+
+    pub fn to_i128(&self) -> Option<i128> {
+        if self.limbs.len() > 4 {
+            return None;
+        }
+
+        let magnitude = self.limbs.iter().rev()
+            .fold(0u128, |acc, limb| (acc << 32) | *limb as u128);
+
+        if self.negative {
+            if magnitude > i128::MAX as u128 + 1 {
+                return None;
+            }
+
+            Some((magnitude as i128).wrapping_neg())
+        } else {
+            i128::try_from(magnitude).ok()
+        }
+    }
+
+    /// Digits in the given radix (2 to 36), with a leading `-` if negative.
+    pub fn to_str_radix(&self, radix: u32) -> String {
+        if self.is_zero() {
+            return String::from("0");
+        }
+
+        let mut limbs = self.limbs.clone();
+        let mut digits = Vec::new();
+
+        while !limbs.is_empty() {
+            let digit = BigInt::div_small(&mut limbs, radix);
+            digits.push(std::char::from_digit(digit, radix).unwrap());
+
+            while limbs.last() == Some(&0) {
+                limbs.pop();
+            }
+        }
+
+        if self.negative {
+            digits.push('-');
+        }
+
+        digits.iter().rev().collect()
+    }
+
+    /// `limbs = limbs * factor + addend`
+    fn mul_small(limbs: &mut Vec<u32>, factor: u32, addend: u32) {
+        let mut carry = addend as u64;
+
+        for limb in limbs.iter_mut() {
+            let value = *limb as u64 * factor as u64 + carry;
+            *limb = value as u32;
+            carry = value >> 32;
+        }
+
+        if carry > 0 {
+            limbs.push(carry as u32);
+        }
+    }
+
+    /// Divides in place and returns the remainder.
+    fn div_small(limbs: &mut [u32], divisor: u32) -> u32 {
+        let mut remainder = 0u64;
+
+        for limb in limbs.iter_mut().rev() {
+            let value = (remainder << 32) | *limb as u64;
+            *limb = (value / divisor as u64) as u32;
+            remainder = value % divisor as u64;
+        }
+
+        remainder as u32
+    }
+
+    fn cmp_magnitude(a: &[u32], b: &[u32]) -> Ordering {
+        a.len().cmp(&b.len()).then_with(|| a.iter().rev().cmp(b.iter().rev()))
+    }
+
+    fn add_magnitude(a: &[u32], b: &[u32]) -> Vec<u32> {
+        let mut result = Vec::with_capacity(a.len().max(b.len()) + 1);
+        let mut carry = 0u64;
+
+        for i in 0..a.len().max(b.len()) {
+            let value = *a.get(i).unwrap_or(&0) as u64 + *b.get(i).unwrap_or(&0) as u64 + carry;
+            result.push(value as u32);
+            carry = value >> 32;
+        }
+
+        if carry > 0 {
+            result.push(carry as u32);
+        }
+
+        result
+    }
+
+    /// Requires `a >= b` in magnitude.
+    fn sub_magnitude(a: &[u32], b: &[u32]) -> Vec<u32> {
+        let mut result = Vec::with_capacity(a.len());
+        let mut borrow = 0i64;
+
+        for (i, x) in a.iter().enumerate() {
+            let mut value = *x as i64 - *b.get(i).unwrap_or(&0) as i64 - borrow;
+            borrow = 0;
+
+            if value < 0 {
+                value += 1 << 32;
+                borrow = 1;
+            }
+
+            result.push(value as u32);
+        }
+
+        result
+    }
+
+    fn mul_magnitude(a: &[u32], b: &[u32]) -> Vec<u32> {
+        let mut result = vec![0u32; a.len() + b.len()];
+
+        for (i, x) in a.iter().enumerate() {
+            let mut carry = 0u64;
+
+            for (j, y) in b.iter().enumerate() {
+                let value = result[i + j] as u64 + *x as u64 * *y as u64 + carry;
+                result[i + j] = value as u32;
+                carry = value >> 32;
+            }
+
+            result[i + b.len()] = carry as u32;
+        }
+
+        result
+    }
+
+    /// Binary long division of magnitudes; `b` must be non-zero.
+    fn divrem_magnitude(a: &[u32], b: &[u32]) -> (Vec<u32>, Vec<u32>) {
+        let mut quotient = vec![0u32; a.len()];
+        let mut remainder: Vec<u32> = Vec::new();
+
+        for bit in (0..a.len() * 32).rev() {
+            BigInt::mul_small(&mut remainder, 2, (a[bit / 32] >> (bit % 32)) & 1);
+
+            if BigInt::cmp_magnitude(&remainder, b) != Ordering::Less {
+                remainder = BigInt::sub_magnitude(&remainder, b);
+                while remainder.last() == Some(&0) {
+                    remainder.pop();
+                }
+
+                quotient[bit / 32] |= 1 << (bit % 32);
+            }
+        }
+
+        (quotient, remainder)
+    }
+
+    fn divrem(self, other: BigInt) -> (BigInt, BigInt) {
+        if other.is_zero() {
+            panic!("Division by zero!");
+        }
+
+        let (quotient, remainder) = BigInt::divrem_magnitude(&self.limbs, &other.limbs);
+
+        // Truncating division, as for the fixed-width types: the remainder takes
+        // the sign of the dividend.
+        (BigInt::normalized(self.negative != other.negative, quotient),
+         BigInt::normalized(self.negative, remainder))
+    }
+}
+
+impl From<i128> for BigInt {
+    fn from(value: i128) -> BigInt {
+        let mut magnitude = value.unsigned_abs();
+        let mut limbs = Vec::new();
+
+        while magnitude > 0 {
+            limbs.push(magnitude as u32);
+            magnitude >>= 32;
+        }
+
+        BigInt::normalized(value < 0, limbs)
+    }
+}
+
+impl PartialOrd for BigInt {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for BigInt {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => BigInt::cmp_magnitude(&self.limbs, &other.limbs),
+            (true, true) => BigInt::cmp_magnitude(&other.limbs, &self.limbs),
+        }
+    }
+}
+
+impl Neg for BigInt {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        BigInt::normalized(!self.negative, self.limbs)
+    }
+}
+
+impl Add for BigInt {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        if self.negative == other.negative {
+            return BigInt::normalized(self.negative,
+                                      BigInt::add_magnitude(&self.limbs, &other.limbs));
+        }
+
+        match BigInt::cmp_magnitude(&self.limbs, &other.limbs) {
+            Ordering::Less => BigInt::normalized(other.negative,
+                                                 BigInt::sub_magnitude(&other.limbs, &self.limbs)),
+            _ => BigInt::normalized(self.negative,
+                                    BigInt::sub_magnitude(&self.limbs, &other.limbs)),
+        }
+    }
+}
+
+impl Sub for BigInt {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        self + -other
+    }
+}
+
+impl Mul for BigInt {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        BigInt::normalized(self.negative != other.negative,
+                           BigInt::mul_magnitude(&self.limbs, &other.limbs))
+    }
+}
+
+impl Div for BigInt {
+    type Output = Self;
+
+    fn div(self, other: Self) -> Self {
+        self.divrem(other).0
+    }
+}
+
+impl Rem for BigInt {
+    type Output = Self;
+
+    fn rem(self, other: Self) -> Self {
+        self.divrem(other).1
+    }
+}
+
+impl Display for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_zero() {
+            return write!(f, "0");
+        }
+
+        // Peel off nine decimal digits at a time, least significant first.
+        let mut limbs = self.limbs.clone();
+        let mut chunks = Vec::new();
+
+        while !limbs.is_empty() {
+            chunks.push(BigInt::div_small(&mut limbs, 1_000_000_000));
+
+            while limbs.last() == Some(&0) {
+                limbs.pop();
+            }
+        }
+
+        if self.negative {
+            write!(f, "-")?;
+        }
+
+        write!(f, "{}", chunks.pop().unwrap())?;
+        for chunk in chunks.iter().rev() {
+            write!(f, "{:09}", chunk)?;
+        }
+
+        Ok(())
+    }
+}
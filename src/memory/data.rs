@@ -1,12 +1,27 @@
-use std::ops::{Add, Sub, Mul, Div, Rem};
+use std::ops::{Add, Sub, Mul, Div, Rem, BitAnd, BitOr, BitXor, Not, Shl, Shr};
 use std::fmt::{Display, self};
+use std::cmp::Ordering;
 
-#[derive(Debug)]
+use super::bigint::BigInt;
+
+#[derive(Debug, Clone)]
 pub enum InnerData {
     INT(i8),
     INT16(i16),
     INT32(i32),
+    INT64(i64),
+    U8(u8),
+    U16(u16),
+    U32(u32),
+    U64(u64),
+    BIGINT(BigInt),
+    FLOAT(f64),
     STR(String),
+    BOOL(bool),
+    /// Handle of an object on the heap.
+    REF(usize),
+    /// Entry label of a function, as pushed by `FUNCREF`.
+    FUNC(usize),
 }
 
 impl PartialEq for InnerData {
@@ -15,7 +30,17 @@ impl PartialEq for InnerData {
             (InnerData::INT(a), InnerData::INT(b)) => a == b,
             (InnerData::INT16(a), InnerData::INT16(b)) => a == b,
             (InnerData::INT32(a), InnerData::INT32(b)) => a == b,
+            (InnerData::INT64(a), InnerData::INT64(b)) => a == b,
+            (InnerData::U8(a), InnerData::U8(b)) => a == b,
+            (InnerData::U16(a), InnerData::U16(b)) => a == b,
+            (InnerData::U32(a), InnerData::U32(b)) => a == b,
+            (InnerData::U64(a), InnerData::U64(b)) => a == b,
+            (InnerData::BIGINT(a), InnerData::BIGINT(b)) => a == b,
+            (InnerData::FLOAT(a), InnerData::FLOAT(b)) => a == b,
             (InnerData::STR(a), InnerData::STR(b)) => a == b,
+            (InnerData::BOOL(a), InnerData::BOOL(b)) => a == b,
+            (InnerData::REF(a), InnerData::REF(b)) => a == b,
+            (InnerData::FUNC(a), InnerData::FUNC(b)) => a == b,
             _ => false,
         }
     }
@@ -27,7 +52,15 @@ impl InnerData {
             "INT" => InnerData::INT(data.parse::<i8>().unwrap()),
             "INT16" => InnerData::INT16(data.parse::<i16>().unwrap()),
             "INT32" => InnerData::INT32(data.parse::<i32>().unwrap()),
+            "INT64" => InnerData::INT64(data.parse::<i64>().unwrap()),
+            "U8" => InnerData::U8(data.parse::<u8>().unwrap()),
+            "U16" => InnerData::U16(data.parse::<u16>().unwrap()),
+            "U32" => InnerData::U32(data.parse::<u32>().unwrap()),
+            "U64" => InnerData::U64(data.parse::<u64>().unwrap()),
+            "BIGINT" => InnerData::BIGINT(BigInt::parse(data).unwrap()),
+            "FLOAT" => InnerData::FLOAT(data.parse::<f64>().unwrap()),
             "STR" => InnerData::STR(data.to_string()),
+            "BOOL" => InnerData::BOOL(data.parse::<bool>().unwrap()),
             _ => panic!("Value out of bounds!"),
         }
     }
@@ -65,60 +98,269 @@ impl InnerData {
             InnerData::INT(_) => "i8",
             InnerData::INT16(_) => "i16",
             InnerData::INT32(_) => "i32",
+            InnerData::INT64(_) => "i64",
+            InnerData::U8(_) => "u8",
+            InnerData::U16(_) => "u16",
+            InnerData::U32(_) => "u32",
+            InnerData::U64(_) => "u64",
+            InnerData::BIGINT(_) => "bigint",
+            InnerData::FLOAT(_) => "f64",
             InnerData::STR(_) => "str",
+            InnerData::BOOL(_) => "bool",
+            InnerData::REF(_) => "ref",
+            InnerData::FUNC(_) => "func",
+        }
+    }
+
+    /// Promotes two integers of different types to a common type. Signed
+    /// values widen to the wider signed type and unsigned values to the wider
+    /// unsigned type. When signedness differs the result is signed and wide
+    /// enough to hold the unsigned operand; a U64 too large for INT64 makes
+    /// both BIGINT. Anything mixed with a BIGINT becomes a BIGINT.
+    fn promote_type(self, other: Self) -> (InnerData, InnerData) {
+        if let (InnerData::BIGINT(_), _) | (_, InnerData::BIGINT(_)) = (&self, &other) {
+            return match (self.to_bigint(), other.to_bigint()) {
+                (Some(a), Some(b)) => (InnerData::BIGINT(a), InnerData::BIGINT(b)),
+                _ => panic!("Illegal types for promotion"),
+            };
+        }
+
+        let (signed, bits) = match (self.int_kind(), other.int_kind()) {
+            (Some((a_signed, a_bits)), Some((b_signed, b_bits))) if a_signed == b_signed => {
+                (a_signed, a_bits.max(b_bits))
+            },
+            (Some((true, signed_bits)), Some((false, unsigned_bits))) |
+            (Some((false, unsigned_bits)), Some((true, signed_bits))) => {
+                (true, signed_bits.max(unsigned_bits * 2).min(64))
+            },
+            _ => panic!("Illegal types for promotion"),
+        };
+
+        match (self.cast_to(signed, bits), other.cast_to(signed, bits)) {
+            (Some(a), Some(b)) => (a, b),
+            // Only a U64 above i64::MAX mixed with a signed value gets here.
+            _ => (InnerData::BIGINT(self.to_bigint().unwrap()),
+                  InnerData::BIGINT(other.to_bigint().unwrap())),
         }
     }
 
-    pub fn clone(&self) -> InnerData {
+    fn int_kind(&self) -> Option<(bool, u32)> {
         match self {
-            InnerData::INT(a) => InnerData::INT(*a),
-            InnerData::INT16(a) => InnerData::INT16(*a),
-            InnerData::INT32(a) => InnerData::INT32(*a),
-            InnerData::STR(a) => InnerData::STR(a.clone()),
+            InnerData::INT(_) => Some((true, 8)),
+            InnerData::INT16(_) => Some((true, 16)),
+            InnerData::INT32(_) => Some((true, 32)),
+            InnerData::INT64(_) => Some((true, 64)),
+            InnerData::U8(_) => Some((false, 8)),
+            InnerData::U16(_) => Some((false, 16)),
+            InnerData::U32(_) => Some((false, 32)),
+            InnerData::U64(_) => Some((false, 64)),
+            _ => None,
         }
     }
 
-    fn promote_type(self, other: Self) -> (InnerData, InnerData) {
-        let mut promoted_self = self;
-        let mut promoted_other = other;
+    pub fn to_bigint(&self) -> Option<BigInt> {
+        match self {
+            InnerData::BIGINT(a) => Some(a.clone()),
+            _ => self.to_i128().map(BigInt::from),
+        }
+    }
 
-        match (promoted_self, promoted_other) {
-            (InnerData::INT(a), InnerData::INT16(b)) => {
-                promoted_self = InnerData::INT16(a as i16);
-                promoted_other = InnerData::INT16(b);
-            }
-            (InnerData::INT(a), InnerData::INT32(b)) => {
-                promoted_self = InnerData::INT32(a as i32);
-                promoted_other = InnerData::INT32(b);
-            }
-            (InnerData::INT16(a), InnerData::INT(b)) => {
-                promoted_self = InnerData::INT16(a);
-                promoted_other = InnerData::INT16(b as i16);
-            }
-            (InnerData::INT16(a), InnerData::INT32(b)) => {
-                promoted_self = InnerData::INT32(a as i32);
-                promoted_other = InnerData::INT32(b);
-            }
-            (InnerData::INT32(a), InnerData::INT(b)) => {
-                promoted_self = InnerData::INT32(a);
-                promoted_other = InnerData::INT32(b as i32);
-            }
-            (InnerData::INT32(a), InnerData::INT16(b)) => {
-                promoted_self = InnerData::INT32(a);
-                promoted_other = InnerData::INT32(b as i32);
-            }
-            _ => panic!("Illegal types for promotion"),
+    /// Whether the value is an integer of any width, including BIGINT.
+    pub fn is_integer(&self) -> bool {
+        self.int_kind().is_some() || matches!(self, InnerData::BIGINT(_))
+    }
+
+    /// Whether the value is an integer or a FLOAT.
+    pub fn is_numeric(&self) -> bool {
+        self.is_integer() || matches!(self, InnerData::FLOAT(_))
+    }
+
+    /// The value as a float, rounding integers too large to be exact.
+    pub fn to_f64(&self) -> Option<f64> {
+        match self {
+            InnerData::FLOAT(a) => Some(*a),
+            InnerData::BIGINT(a) => a.to_string().parse::<f64>().ok(),
+            _ => self.to_i128().map(|value| value as f64),
         }
+    }
 
-        (promoted_self, promoted_other)
+    /// The narrowest signed integer holding `value`, as literals are encoded.
+    pub fn from_integer(value: BigInt) -> InnerData {
+        match value.to_i128() {
+            Some(fixed) => InnerData::int(fixed),
+            None => InnerData::BIGINT(value),
+        }
+    }
+
+    /// Like `from_integer`, for values that are already known to be small.
+    pub fn int(value: i128) -> InnerData {
+        match [8, 16, 32, 64].iter().find_map(|bits| InnerData::from_i128(value, true, *bits)) {
+            Some(fixed) => fixed,
+            None => InnerData::BIGINT(BigInt::from(value)),
+        }
+    }
+
+    fn cast_to(&self, signed: bool, bits: u32) -> Option<InnerData> {
+        let value = match self.to_i128() {
+            Some(value) => value,
+            None => panic!("Illegal types for promotion"),
+        };
+
+        InnerData::from_i128(value, signed, bits)
+    }
+
+    /// Builds the integer of the given signedness and width holding `value`,
+    /// or `None` if it does not fit.
+    pub(crate) fn from_i128(value: i128, signed: bool, bits: u32) -> Option<InnerData> {
+        match (signed, bits) {
+            (true, 8) => i8::try_from(value).ok().map(InnerData::INT),
+            (true, 16) => i16::try_from(value).ok().map(InnerData::INT16),
+            (true, 32) => i32::try_from(value).ok().map(InnerData::INT32),
+            (true, 64) => i64::try_from(value).ok().map(InnerData::INT64),
+            (false, 8) => u8::try_from(value).ok().map(InnerData::U8),
+            (false, 16) => u16::try_from(value).ok().map(InnerData::U16),
+            (false, 32) => u32::try_from(value).ok().map(InnerData::U32),
+            (false, 64) => u64::try_from(value).ok().map(InnerData::U64),
+            _ => None,
+        }
+    }
+
+    /// Like `from_i128`, but keeps only the low `bits` bits of `value` as two's
+    /// complement hardware does.
+    pub(crate) fn wrapping_from_i128(value: i128, signed: bool, bits: u32) -> InnerData {
+        let modulus = 1i128 << bits;
+        let mut low_bits = value.rem_euclid(modulus);
+
+        if signed && low_bits >= modulus / 2 {
+            low_bits -= modulus;
+        }
+
+        InnerData::from_i128(low_bits, signed, bits).unwrap()
+    }
+
+    /// Reads a byte operand such as a label, register or slot index. The loader
+    /// stores bytes in INT, so its bits are reinterpreted; other integers must
+    /// hold a value in `0..=255`.
+    pub fn to_byte(&self) -> Option<u8> {
+        match self {
+            InnerData::INT(a) => Some(*a as u8),
+            _ => self.to_i128().and_then(|value| u8::try_from(value).ok()),
+        }
+    }
+
+    /// How many times an integer repeats a string: negative counts repeat it
+    /// zero times, and counts beyond `usize` saturate.
+    pub(crate) fn repeat_count(&self) -> usize {
+        match (self.to_i128(), self) {
+            (Some(count), _) => usize::try_from(count.max(0)).unwrap_or(usize::MAX),
+            (None, InnerData::BIGINT(count)) if count.is_negative() => 0,
+            (None, _) => usize::MAX,
+        }
+    }
+
+    /// Width in bits of an integer value, or `None` for non-integers.
+    pub fn bit_width(&self) -> Option<u32> {
+        match self {
+            InnerData::INT(_) => Some(8),
+            InnerData::INT16(_) => Some(16),
+            InnerData::INT32(_) => Some(32),
+            InnerData::INT64(_) => Some(64),
+            InnerData::U8(_) => Some(8),
+            InnerData::U16(_) => Some(16),
+            InnerData::U32(_) => Some(32),
+            InnerData::U64(_) => Some(64),
+            InnerData::BIGINT(_) | InnerData::FLOAT(_) |
+            InnerData::STR(_) | InnerData::BOOL(_) | InnerData::REF(_) | InnerData::FUNC(_) => None,
+        }
+    }
+
+    /// Whether the value is one of the unsigned integer types.
+    pub fn is_unsigned(&self) -> bool {
+        matches!(self.int_kind(), Some((false, _)))
+    }
+
+    pub(crate) fn to_i128(&self) -> Option<i128> {
+        match self {
+            InnerData::INT(a) => Some(*a as i128),
+            InnerData::INT16(a) => Some(*a as i128),
+            InnerData::INT32(a) => Some(*a as i128),
+            InnerData::INT64(a) => Some(*a as i128),
+            InnerData::U8(a) => Some(*a as i128),
+            InnerData::U16(a) => Some(*a as i128),
+            InnerData::U32(a) => Some(*a as i128),
+            InnerData::U64(a) => Some(*a as i128),
+            InnerData::BIGINT(a) => a.to_i128(),
+            InnerData::FLOAT(_) | InnerData::STR(_) | InnerData::BOOL(_) |
+            InnerData::REF(_) | InnerData::FUNC(_) => None,
+        }
+    }
+
+    /// Orders two values by what they represent rather than by variant, so
+    /// `INT(5)` and `INT16(5)` compare equal. Strings compare lexicographically
+    /// and never compare with integers. NaN is unordered, and references are only
+    /// equal to themselves.
+    pub fn compare(&self, other: &Self) -> Option<Ordering> {
+        match (self, other) {
+            (InnerData::FLOAT(_), _) | (_, InnerData::FLOAT(_)) => {
+                match (self.to_f64(), other.to_f64()) {
+                    (Some(a), Some(b)) => a.partial_cmp(&b),
+                    _ => None,
+                }
+            },
+            (InnerData::STR(a), InnerData::STR(b)) => Some(a.cmp(b)),
+            (InnerData::BOOL(a), InnerData::BOOL(b)) => Some(a.cmp(b)),
+            (InnerData::REF(a), InnerData::REF(b)) if a == b => Some(Ordering::Equal),
+            (InnerData::FUNC(a), InnerData::FUNC(b)) if a == b => Some(Ordering::Equal),
+            (InnerData::BIGINT(_), _) | (_, InnerData::BIGINT(_)) => {
+                match (self.to_bigint(), other.to_bigint()) {
+                    (Some(a), Some(b)) => Some(a.cmp(&b)),
+                    _ => None,
+                }
+            },
+            _ => match (self.to_i128(), other.to_i128()) {
+                (Some(a), Some(b)) => Some(a.cmp(&b)),
+                _ => None,
+            },
+        }
+    }
+
+    /// Shift amounts are taken modulo the width of the shifted value, as in Java.
+    fn shift_amount(&self) -> u32 {
+        match self.to_i128() {
+            Some(amount) => amount as u32,
+            None => panic!("Illegal shift amount"),
+        }
+    }
+
+    /// Logical right shift: the vacated high bits are filled with zeros.
+    pub fn ushr(self, other: Self) -> Self {
+        let amount = other.shift_amount();
+
+        match self {
+            InnerData::INT(a) => InnerData::INT((a as u8).wrapping_shr(amount) as i8),
+            InnerData::INT16(a) => InnerData::INT16((a as u16).wrapping_shr(amount) as i16),
+            InnerData::INT32(a) => InnerData::INT32((a as u32).wrapping_shr(amount) as i32),
+            InnerData::INT64(a) => InnerData::INT64((a as u64).wrapping_shr(amount) as i64),
+            InnerData::U8(_) | InnerData::U16(_) |
+            InnerData::U32(_) | InnerData::U64(_) => self >> other,
+            _ => panic!("Illegal ushr operation"),
+        }
     }
 
     pub fn variant_eq<T>(a: &T, b: &T) -> bool {
         std::mem::discriminant(a) == std::mem::discriminant(b)
     }
 
-    fn promote_or_not(self, other: Self) -> (InnerData, InnerData) {
-        if self.get_type() == "str" || other.get_type() == "str" {
+    pub(crate) fn promote_or_not(self, other: Self) -> (InnerData, InnerData) {
+        // Mixing an integer with a float gives a float.
+        if let (InnerData::FLOAT(_), _) | (_, InnerData::FLOAT(_)) = (&self, &other) {
+            return match (self.to_f64(), other.to_f64()) {
+                (Some(a), Some(b)) => (InnerData::FLOAT(a), InnerData::FLOAT(b)),
+                _ => (self, other),
+            };
+        }
+
+        if !self.is_integer() || !other.is_integer() {
             return (self, other);
         }
 
@@ -133,6 +375,21 @@ impl InnerData {
         (promoted_self, promoted_other)
     }
 
+    /// Like `promote_or_not`, for the bitwise operators. They work on bit
+    /// patterns, so a mixed-sign pair too wide for INT64 is combined as U64
+    /// rather than as BIGINT.
+    fn promote_bits(self, other: Self) -> (InnerData, InnerData) {
+        let wide = |value: &InnerData| matches!(value, InnerData::U64(a) if *a > i64::MAX as u64);
+        let signed = |value: &InnerData| matches!(value.int_kind(), Some((true, _)));
+
+        if (wide(&self) && signed(&other)) || (signed(&self) && wide(&other)) {
+            return (InnerData::wrapping_from_i128(self.to_i128().unwrap(), false, 64),
+                    InnerData::wrapping_from_i128(other.to_i128().unwrap(), false, 64));
+        }
+
+        self.promote_or_not(other)
+    }
+
     fn compute_or_promote_i8(a: i8, b: i8, checked_fn: &dyn Fn(i8, i8) -> Option<i8>,
                              op_fn: &dyn Fn(Self, Self) -> Self) -> InnerData {
         let res = checked_fn(a, b);
@@ -164,13 +421,94 @@ impl InnerData {
     }
 
     fn compute_or_promote_i32(a: i32, b: i32, checked_fn: &dyn Fn(i32, i32) -> Option<i32>,
-                             _op_fn: &dyn Fn(Self, Self) -> Self) -> InnerData {
+                             op_fn: &dyn Fn(Self, Self) -> Self) -> InnerData {
         let res = checked_fn(a, b);
 
         match res {
             Some(res) => InnerData::INT32(res),
             None => {
-                panic!("Value out of bounds!");
+                let promoted_self = InnerData::INT64(a as i64);
+                let promoted_other = InnerData::INT64(b as i64);
+
+                op_fn(promoted_self, promoted_other)
+            }
+        }
+    }
+
+    fn compute_or_promote_i64(a: i64, b: i64, checked_fn: &dyn Fn(i64, i64) -> Option<i64>,
+                             op_fn: &dyn Fn(Self, Self) -> Self) -> InnerData {
+        let res = checked_fn(a, b);
+
+        match res {
+            Some(res) => InnerData::INT64(res),
+            None => {
+                let promoted_self = InnerData::BIGINT(BigInt::from(a as i128));
+                let promoted_other = InnerData::BIGINT(BigInt::from(b as i128));
+
+                op_fn(promoted_self, promoted_other)
+            }
+        }
+    }
+
+    // Unsigned results that do not fit (including negative differences)
+    // continue in the signed chain at twice the width, and past 64 bits as BIGINT.
+
+    fn compute_or_promote_u8(a: u8, b: u8, checked_fn: &dyn Fn(u8, u8) -> Option<u8>,
+                             op_fn: &dyn Fn(Self, Self) -> Self) -> InnerData {
+        let res = checked_fn(a, b);
+
+        match res {
+            Some(res) => InnerData::U8(res),
+            None => {
+                let promoted_self = InnerData::INT16(a as i16);
+                let promoted_other = InnerData::INT16(b as i16);
+
+                op_fn(promoted_self, promoted_other)
+            }
+        }
+    }
+
+    fn compute_or_promote_u16(a: u16, b: u16, checked_fn: &dyn Fn(u16, u16) -> Option<u16>,
+                             op_fn: &dyn Fn(Self, Self) -> Self) -> InnerData {
+        let res = checked_fn(a, b);
+
+        match res {
+            Some(res) => InnerData::U16(res),
+            None => {
+                let promoted_self = InnerData::INT32(a as i32);
+                let promoted_other = InnerData::INT32(b as i32);
+
+                op_fn(promoted_self, promoted_other)
+            }
+        }
+    }
+
+    fn compute_or_promote_u32(a: u32, b: u32, checked_fn: &dyn Fn(u32, u32) -> Option<u32>,
+                             op_fn: &dyn Fn(Self, Self) -> Self) -> InnerData {
+        let res = checked_fn(a, b);
+
+        match res {
+            Some(res) => InnerData::U32(res),
+            None => {
+                let promoted_self = InnerData::INT64(a as i64);
+                let promoted_other = InnerData::INT64(b as i64);
+
+                op_fn(promoted_self, promoted_other)
+            }
+        }
+    }
+
+    fn compute_or_promote_u64(a: u64, b: u64, checked_fn: &dyn Fn(u64, u64) -> Option<u64>,
+                             op_fn: &dyn Fn(Self, Self) -> Self) -> InnerData {
+        let res = checked_fn(a, b);
+
+        match res {
+            Some(res) => InnerData::U64(res),
+            None => {
+                let promoted_self = InnerData::BIGINT(BigInt::from(a as i128));
+                let promoted_other = InnerData::BIGINT(BigInt::from(b as i128));
+
+                op_fn(promoted_self, promoted_other)
             }
         }
     }
@@ -192,6 +530,23 @@ impl Add for InnerData {
             (InnerData::INT32(a), InnerData::INT32(b)) => {
                 InnerData::compute_or_promote_i32(a, b, &i32::checked_add, &Add::add)
             },
+            (InnerData::INT64(a), InnerData::INT64(b)) => {
+                InnerData::compute_or_promote_i64(a, b, &i64::checked_add, &Add::add)
+            },
+            (InnerData::U8(a), InnerData::U8(b)) => {
+                InnerData::compute_or_promote_u8(a, b, &u8::checked_add, &Add::add)
+            },
+            (InnerData::U16(a), InnerData::U16(b)) => {
+                InnerData::compute_or_promote_u16(a, b, &u16::checked_add, &Add::add)
+            },
+            (InnerData::U32(a), InnerData::U32(b)) => {
+                InnerData::compute_or_promote_u32(a, b, &u32::checked_add, &Add::add)
+            },
+            (InnerData::U64(a), InnerData::U64(b)) => {
+                InnerData::compute_or_promote_u64(a, b, &u64::checked_add, &Add::add)
+            },
+            (InnerData::BIGINT(a), InnerData::BIGINT(b)) => InnerData::BIGINT(a + b),
+            (InnerData::FLOAT(a), InnerData::FLOAT(b)) => InnerData::FLOAT(a + b),
             (InnerData::STR(a), InnerData::STR(b)) => InnerData::STR(a + &b),
             _ => panic!("Illegal add operation"),
         }
@@ -214,6 +569,23 @@ impl Sub for InnerData {
             (InnerData::INT32(a), InnerData::INT32(b)) => {
                 InnerData::compute_or_promote_i32(a, b, &i32::checked_sub, &Sub::sub)
             },
+            (InnerData::INT64(a), InnerData::INT64(b)) => {
+                InnerData::compute_or_promote_i64(a, b, &i64::checked_sub, &Sub::sub)
+            },
+            (InnerData::U8(a), InnerData::U8(b)) => {
+                InnerData::compute_or_promote_u8(a, b, &u8::checked_sub, &Sub::sub)
+            },
+            (InnerData::U16(a), InnerData::U16(b)) => {
+                InnerData::compute_or_promote_u16(a, b, &u16::checked_sub, &Sub::sub)
+            },
+            (InnerData::U32(a), InnerData::U32(b)) => {
+                InnerData::compute_or_promote_u32(a, b, &u32::checked_sub, &Sub::sub)
+            },
+            (InnerData::U64(a), InnerData::U64(b)) => {
+                InnerData::compute_or_promote_u64(a, b, &u64::checked_sub, &Sub::sub)
+            },
+            (InnerData::BIGINT(a), InnerData::BIGINT(b)) => InnerData::BIGINT(a - b),
+            (InnerData::FLOAT(a), InnerData::FLOAT(b)) => InnerData::FLOAT(a - b),
             _ => panic!("Illegal add operation"),
         }
     }
@@ -235,19 +607,25 @@ impl Mul for InnerData {
             (InnerData::INT32(a), InnerData::INT32(b)) => {
                 InnerData::compute_or_promote_i32(a, b, &i32::checked_mul, &Mul::mul)
             },
-            (InnerData::STR(a), InnerData::INT(b)) => {
-                let mut result = String::new();
-                for _ in 0..b {
-                    result.push_str(&a);
-                }
-                InnerData::STR(result)
+            (InnerData::INT64(a), InnerData::INT64(b)) => {
+                InnerData::compute_or_promote_i64(a, b, &i64::checked_mul, &Mul::mul)
             },
-            (InnerData::INT(a), InnerData::STR(b)) => {
-                let mut result = String::new();
-                for _ in 0..a {
-                    result.push_str(&b);
-                }
-                InnerData::STR(result)
+            (InnerData::U8(a), InnerData::U8(b)) => {
+                InnerData::compute_or_promote_u8(a, b, &u8::checked_mul, &Mul::mul)
+            },
+            (InnerData::U16(a), InnerData::U16(b)) => {
+                InnerData::compute_or_promote_u16(a, b, &u16::checked_mul, &Mul::mul)
+            },
+            (InnerData::U32(a), InnerData::U32(b)) => {
+                InnerData::compute_or_promote_u32(a, b, &u32::checked_mul, &Mul::mul)
+            },
+            (InnerData::U64(a), InnerData::U64(b)) => {
+                InnerData::compute_or_promote_u64(a, b, &u64::checked_mul, &Mul::mul)
+            },
+            (InnerData::BIGINT(a), InnerData::BIGINT(b)) => InnerData::BIGINT(a * b),
+            (InnerData::FLOAT(a), InnerData::FLOAT(b)) => InnerData::FLOAT(a * b),
+            (InnerData::STR(a), count) | (count, InnerData::STR(a)) if count.is_integer() => {
+                InnerData::STR(a.repeat(count.repeat_count()))
             },
             _ => panic!("Illegal mul operation"),
         }
@@ -270,6 +648,23 @@ impl Div for InnerData {
             (InnerData::INT32(a), InnerData::INT32(b)) => {
                 InnerData::compute_or_promote_i32(a, b, &i32::checked_div, &Div::div)
             },
+            (InnerData::INT64(a), InnerData::INT64(b)) => {
+                InnerData::compute_or_promote_i64(a, b, &i64::checked_div, &Div::div)
+            },
+            (InnerData::U8(a), InnerData::U8(b)) => {
+                InnerData::compute_or_promote_u8(a, b, &u8::checked_div, &Div::div)
+            },
+            (InnerData::U16(a), InnerData::U16(b)) => {
+                InnerData::compute_or_promote_u16(a, b, &u16::checked_div, &Div::div)
+            },
+            (InnerData::U32(a), InnerData::U32(b)) => {
+                InnerData::compute_or_promote_u32(a, b, &u32::checked_div, &Div::div)
+            },
+            (InnerData::U64(a), InnerData::U64(b)) => {
+                InnerData::compute_or_promote_u64(a, b, &u64::checked_div, &Div::div)
+            },
+            (InnerData::BIGINT(a), InnerData::BIGINT(b)) => InnerData::BIGINT(a / b),
+            (InnerData::FLOAT(a), InnerData::FLOAT(b)) => InnerData::FLOAT(a / b),
             _ => panic!("Illegal div operation"),
         }
     }
@@ -291,18 +686,171 @@ impl Rem for InnerData {
             (InnerData::INT32(a), InnerData::INT32(b)) => {
                 InnerData::compute_or_promote_i32(a, b, &i32::checked_rem, &Rem::rem)
             },
+            (InnerData::INT64(a), InnerData::INT64(b)) => {
+                InnerData::compute_or_promote_i64(a, b, &i64::checked_rem, &Rem::rem)
+            },
+            (InnerData::U8(a), InnerData::U8(b)) => {
+                InnerData::compute_or_promote_u8(a, b, &u8::checked_rem, &Rem::rem)
+            },
+            (InnerData::U16(a), InnerData::U16(b)) => {
+                InnerData::compute_or_promote_u16(a, b, &u16::checked_rem, &Rem::rem)
+            },
+            (InnerData::U32(a), InnerData::U32(b)) => {
+                InnerData::compute_or_promote_u32(a, b, &u32::checked_rem, &Rem::rem)
+            },
+            (InnerData::U64(a), InnerData::U64(b)) => {
+                InnerData::compute_or_promote_u64(a, b, &u64::checked_rem, &Rem::rem)
+            },
+            (InnerData::BIGINT(a), InnerData::BIGINT(b)) => InnerData::BIGINT(a % b),
+            (InnerData::FLOAT(a), InnerData::FLOAT(b)) => InnerData::FLOAT(a % b),
             _ => panic!("Illegal rem operation"),
         }
     }
 }
 
+impl BitAnd for InnerData {
+    type Output = Self;
+
+    fn bitand(self, other: Self) -> Self {
+        let (promoted_self, promoted_other) = InnerData::promote_bits(self, other);
+
+        match (promoted_self, promoted_other) {
+            (InnerData::INT(a), InnerData::INT(b)) => InnerData::INT(a & b),
+            (InnerData::INT16(a), InnerData::INT16(b)) => InnerData::INT16(a & b),
+            (InnerData::INT32(a), InnerData::INT32(b)) => InnerData::INT32(a & b),
+            (InnerData::INT64(a), InnerData::INT64(b)) => InnerData::INT64(a & b),
+            (InnerData::U8(a), InnerData::U8(b)) => InnerData::U8(a & b),
+            (InnerData::U16(a), InnerData::U16(b)) => InnerData::U16(a & b),
+            (InnerData::U32(a), InnerData::U32(b)) => InnerData::U32(a & b),
+            (InnerData::U64(a), InnerData::U64(b)) => InnerData::U64(a & b),
+            (InnerData::BOOL(a), InnerData::BOOL(b)) => InnerData::BOOL(a && b),
+            _ => panic!("Illegal and operation"),
+        }
+    }
+}
+
+impl BitOr for InnerData {
+    type Output = Self;
+
+    fn bitor(self, other: Self) -> Self {
+        let (promoted_self, promoted_other) = InnerData::promote_bits(self, other);
+
+        match (promoted_self, promoted_other) {
+            (InnerData::INT(a), InnerData::INT(b)) => InnerData::INT(a | b),
+            (InnerData::INT16(a), InnerData::INT16(b)) => InnerData::INT16(a | b),
+            (InnerData::INT32(a), InnerData::INT32(b)) => InnerData::INT32(a | b),
+            (InnerData::INT64(a), InnerData::INT64(b)) => InnerData::INT64(a | b),
+            (InnerData::U8(a), InnerData::U8(b)) => InnerData::U8(a | b),
+            (InnerData::U16(a), InnerData::U16(b)) => InnerData::U16(a | b),
+            (InnerData::U32(a), InnerData::U32(b)) => InnerData::U32(a | b),
+            (InnerData::U64(a), InnerData::U64(b)) => InnerData::U64(a | b),
+            (InnerData::BOOL(a), InnerData::BOOL(b)) => InnerData::BOOL(a || b),
+            _ => panic!("Illegal or operation"),
+        }
+    }
+}
+
+impl BitXor for InnerData {
+    type Output = Self;
+
+    fn bitxor(self, other: Self) -> Self {
+        let (promoted_self, promoted_other) = InnerData::promote_bits(self, other);
+
+        match (promoted_self, promoted_other) {
+            (InnerData::INT(a), InnerData::INT(b)) => InnerData::INT(a ^ b),
+            (InnerData::INT16(a), InnerData::INT16(b)) => InnerData::INT16(a ^ b),
+            (InnerData::INT32(a), InnerData::INT32(b)) => InnerData::INT32(a ^ b),
+            (InnerData::INT64(a), InnerData::INT64(b)) => InnerData::INT64(a ^ b),
+            (InnerData::U8(a), InnerData::U8(b)) => InnerData::U8(a ^ b),
+            (InnerData::U16(a), InnerData::U16(b)) => InnerData::U16(a ^ b),
+            (InnerData::U32(a), InnerData::U32(b)) => InnerData::U32(a ^ b),
+            (InnerData::U64(a), InnerData::U64(b)) => InnerData::U64(a ^ b),
+            (InnerData::BOOL(a), InnerData::BOOL(b)) => InnerData::BOOL(a != b),
+            _ => panic!("Illegal xor operation"),
+        }
+    }
+}
+
+impl Not for InnerData {
+    type Output = Self;
+
+    fn not(self) -> Self {
+        match self {
+            InnerData::INT(a) => InnerData::INT(!a),
+            InnerData::INT16(a) => InnerData::INT16(!a),
+            InnerData::INT32(a) => InnerData::INT32(!a),
+            InnerData::INT64(a) => InnerData::INT64(!a),
+            InnerData::U8(a) => InnerData::U8(!a),
+            InnerData::U16(a) => InnerData::U16(!a),
+            InnerData::U32(a) => InnerData::U32(!a),
+            InnerData::U64(a) => InnerData::U64(!a),
+            InnerData::BOOL(a) => InnerData::BOOL(!a),
+            _ => panic!("Illegal not operation"),
+        }
+    }
+}
+
+/// Shifts keep the width of the shifted value; bits shifted out are lost.
+impl Shl for InnerData {
+    type Output = Self;
+
+    fn shl(self, other: Self) -> Self {
+        let amount = other.shift_amount();
+
+        match self {
+            InnerData::INT(a) => InnerData::INT(a.wrapping_shl(amount)),
+            InnerData::INT16(a) => InnerData::INT16(a.wrapping_shl(amount)),
+            InnerData::INT32(a) => InnerData::INT32(a.wrapping_shl(amount)),
+            InnerData::INT64(a) => InnerData::INT64(a.wrapping_shl(amount)),
+            InnerData::U8(a) => InnerData::U8(a.wrapping_shl(amount)),
+            InnerData::U16(a) => InnerData::U16(a.wrapping_shl(amount)),
+            InnerData::U32(a) => InnerData::U32(a.wrapping_shl(amount)),
+            InnerData::U64(a) => InnerData::U64(a.wrapping_shl(amount)),
+            _ => panic!("Illegal shl operation"),
+        }
+    }
+}
+
+/// Arithmetic right shift: the sign bit is copied into the vacated high bits.
+/// Unsigned values have no sign bit, so they shift in zeros.
+impl Shr for InnerData {
+    type Output = Self;
+
+    fn shr(self, other: Self) -> Self {
+        let amount = other.shift_amount();
+
+        match self {
+            InnerData::INT(a) => InnerData::INT(a.wrapping_shr(amount)),
+            InnerData::INT16(a) => InnerData::INT16(a.wrapping_shr(amount)),
+            InnerData::INT32(a) => InnerData::INT32(a.wrapping_shr(amount)),
+            InnerData::INT64(a) => InnerData::INT64(a.wrapping_shr(amount)),
+            InnerData::U8(a) => InnerData::U8(a.wrapping_shr(amount)),
+            InnerData::U16(a) => InnerData::U16(a.wrapping_shr(amount)),
+            InnerData::U32(a) => InnerData::U32(a.wrapping_shr(amount)),
+            InnerData::U64(a) => InnerData::U64(a.wrapping_shr(amount)),
+            _ => panic!("Illegal shr operation"),
+        }
+    }
+}
+
 impl Display for InnerData {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             InnerData::INT(a) => write!(f, "{}", a),
             InnerData::INT16(a) => write!(f, "{}", a),
             InnerData::INT32(a) => write!(f, "{}", a),
+            InnerData::INT64(a) => write!(f, "{}", a),
+            InnerData::U8(a) => write!(f, "{}", a),
+            InnerData::U16(a) => write!(f, "{}", a),
+            InnerData::U32(a) => write!(f, "{}", a),
+            InnerData::U64(a) => write!(f, "{}", a),
+            InnerData::BIGINT(a) => write!(f, "{}", a),
+            // Debug keeps the fraction of whole floats, so `3.0` is not shown as `3`.
+            InnerData::FLOAT(a) => write!(f, "{:?}", a),
             InnerData::STR(a) => write!(f, "{}", a),
+            InnerData::BOOL(a) => write!(f, "{}", a),
+            InnerData::REF(a) => write!(f, "<ref {}>", a),
+            InnerData::FUNC(a) => write!(f, "<func {}>", a),
         }
     }
 }
\ No newline at end of file
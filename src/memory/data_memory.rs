@@ -1,27 +1,167 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
-use crate::memory::InnerData;
+use crate::memory::{Heap, InnerData, LinearMemory};
 
+/// Slots below this index are stored densely. Higher ones go in a map, so a
+/// single write to a huge index cannot allocate every slot below it.
+const DENSE_SLOTS: u32 = 1 << 16;
+
+/// Global variable slots, stored densely by index up to `DENSE_SLOTS`.
 pub struct DataMemory {
-    pub data: HashMap<u8, InnerData>,
+    slots: Vec<Option<InnerData>>,
+    sparse: BTreeMap<u32, InnerData>,
+    used: usize,
+    journal: Option<Vec<(u32, Option<InnerData>)>>,
+    heap: Heap,
+    linear_memory: LinearMemory,
+}
+
+impl Default for DataMemory {
+    fn default() -> Self {
+        DataMemory::new()
+    }
 }
 
 impl DataMemory {
     pub fn new() -> DataMemory {
         DataMemory {
-            data: HashMap::new(),
+            slots: Vec::new(),
+            sparse: BTreeMap::new(),
+            used: 0,
+            journal: None,
+            heap: Heap::new(),
+            linear_memory: LinearMemory::default(),
+        }
+    }
+
+    /// Data memory with `size` bytes of zeroed linear memory.
+    pub fn with_linear_memory(size: usize) -> DataMemory {
+        DataMemory {
+            linear_memory: LinearMemory::new(size),
+            ..DataMemory::new()
+        }
+    }
+
+    /// Number of slots that have been written.
+    pub fn len(&self) -> usize {
+        self.used
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.used == 0
+    }
+
+    /// The written slots with their indices, in index order.
+    pub fn slots(&self) -> impl Iterator<Item = (u32, &InnerData)> {
+        self.slots.iter().enumerate()
+            .filter_map(|(idx, slot)| slot.as_ref().map(|value| (idx as u32, value)))
+            .chain(self.sparse.iter().map(|(idx, value)| (*idx, value)))
+    }
+
+    /// Replaces every slot, as when restoring a snapshot.
+    pub fn load(&mut self, slots: Vec<(u32, InnerData)>) {
+        self.slots.clear();
+        self.sparse.clear();
+        self.used = 0;
+
+        for (idx, value) in slots {
+            self.replace(idx, Some(value));
+        }
+    }
+
+    /// The value of a slot, or `None` if it was never written.
+    pub fn get(&self, idx: u32) -> Option<&InnerData> {
+        if idx >= DENSE_SLOTS {
+            return self.sparse.get(&idx);
+        }
+
+        self.slots.get(idx as usize).and_then(|slot| slot.as_ref())
+    }
+
+    /// The value of a slot, reading `INT(0)` from slots that were never written.
+    pub fn get_var_value(&self, idx: u32) -> &InnerData {
+        match self.get(idx) {
+            Some(value) => value,
+            None => &InnerData::INT(0),
+        }
+    }
+
+    pub fn set_var_value(&mut self, idx: u32, value: InnerData) {
+        let previous = self.replace(idx, Some(value));
+
+        if let Some(journal) = &mut self.journal {
+            journal.push((idx, previous));
         }
     }
 
-    pub fn get_var_value(&self, idx: u8) -> &InnerData {
-        if let Some(value) = self.data.get(&idx) {
-            value
+    fn replace(&mut self, idx: u32, value: Option<InnerData>) -> Option<InnerData> {
+        let is_some = value.is_some();
+
+        let previous = if idx >= DENSE_SLOTS {
+            match value {
+                Some(value) => self.sparse.insert(idx, value),
+                None => self.sparse.remove(&idx),
+            }
         } else {
-            &InnerData::INT(0)
+            let idx = idx as usize;
+            if idx >= self.slots.len() {
+                self.slots.resize(idx + 1, None);
+            }
+
+            std::mem::replace(&mut self.slots[idx], value)
+        };
+
+        match (previous.is_some(), is_some) {
+            (false, true) => self.used += 1,
+            (true, false) => self.used -= 1,
+            _ => {},
         }
+
+        previous
+    }
+
+    /// Puts back the value a recorded write replaced, or clears the slot if it
+    /// had none.
+    pub fn undo(&mut self, idx: u32, previous: Option<InnerData>) {
+        self.replace(idx, previous);
+    }
+
+    pub fn heap(&self) -> &Heap {
+        &self.heap
+    }
+
+    pub fn heap_mut(&mut self) -> &mut Heap {
+        &mut self.heap
+    }
+
+    pub fn linear_memory(&self) -> &LinearMemory {
+        &self.linear_memory
+    }
+
+    pub fn linear_memory_mut(&mut self) -> &mut LinearMemory {
+        &mut self.linear_memory
+    }
+
+    /// Frees every heap object that is not reachable from a data slot or from one
+    /// of `roots`.
+    pub fn collect_garbage(&mut self, roots: &[&[InnerData]]) {
+        let roots = self.slots.iter().flatten()
+            .chain(self.sparse.values())
+            .chain(roots.iter().flat_map(|values| values.iter()));
+        self.heap.collect(roots);
     }
 
-    pub fn set_var_value(&mut self, idx: u8, value: InnerData) {
-        self.data.insert(idx, value);
+    /// Starts or stops recording the previous value of every slot written through
+    /// `set_var_value`.
+    pub fn record_writes(&mut self, enabled: bool) {
+        self.journal = if enabled { Some(Vec::new()) } else { None };
+    }
+
+    /// Returns the writes recorded since the last call, oldest first.
+    pub fn take_writes(&mut self) -> Vec<(u32, Option<InnerData>)> {
+        match &mut self.journal {
+            Some(journal) => std::mem::take(journal),
+            None => Vec::new(),
+        }
     }
 }
\ No newline at end of file
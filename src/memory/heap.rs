@@ -0,0 +1,474 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::memory::{BigInt, InnerData};
+
+/// Layout of a struct: its name and the names of its fields, in order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StructType {
+    pub name: String,
+    pub fields: Vec<String>,
+}
+
+impl StructType {
+    pub fn new(name: &str, fields: &[&str]) -> StructType {
+        StructType {
+            name: name.to_string(),
+            fields: fields.iter().map(|field| field.to_string()).collect(),
+        }
+    }
+
+    pub fn field_index(&self, name: &str) -> Option<usize> {
+        self.fields.iter().position(|field| field == name)
+    }
+}
+
+/// A map key: an integer of any width or a string. Integers compare by value,
+/// so `INT(5)` and `U64(5)` are the same key, and sort before strings.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum MapKey {
+    Int(BigInt),
+    Str(String),
+}
+
+impl MapKey {
+    /// The key for `value`, or `None` if values of its type cannot be keys.
+    pub fn from_data(value: &InnerData) -> Option<MapKey> {
+        match value {
+            InnerData::STR(text) => Some(MapKey::Str(text.clone())),
+            _ => value.to_bigint().map(MapKey::Int),
+        }
+    }
+}
+
+/// An object on the heap, reached through an `InnerData::REF` handle.
+#[derive(Debug, Clone, PartialEq)]
+pub enum HeapObject {
+    Array(Vec<InnerData>),
+    /// An instance of the struct type with the given index, one value per field.
+    Struct(usize, Vec<InnerData>),
+    /// Entries in key order, each holding the key as first stored and its value.
+    Map(BTreeMap<MapKey, (InnerData, InnerData)>),
+}
+
+impl HeapObject {
+    /// Number of values the object holds, which is what heap limits count.
+    pub fn cells(&self) -> usize {
+        match self {
+            HeapObject::Array(elements) | HeapObject::Struct(_, elements) => elements.len(),
+            HeapObject::Map(entries) => entries.len() * 2,
+        }
+    }
+
+    /// Every value the object holds, which is where it can refer to others.
+    fn values(&self) -> Box<dyn Iterator<Item = &InnerData> + '_> {
+        match self {
+            HeapObject::Array(elements) | HeapObject::Struct(_, elements) => Box::new(elements.iter()),
+            HeapObject::Map(entries) => Box::new(entries.values().map(|(_, value)| value)),
+        }
+    }
+}
+
+/// Running totals kept by the garbage collector.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct GcStats {
+    pub collections: u64,
+    pub objects_freed: u64,
+    pub cells_freed: u64,
+}
+
+/// Heap size, in cells plus live objects, below which allocations never trigger
+/// a collection.
+const MIN_COLLECTION_THRESHOLD: usize = 1024;
+
+/// One change to the heap, recorded so it can be undone.
+#[derive(Debug, Clone, PartialEq)]
+pub enum HeapWrite {
+    Alloc(usize),
+    /// The collector freed the object with the given handle.
+    Free(usize, HeapObject),
+    Set(usize, usize, InnerData),
+    Push(usize),
+    /// A map entry was set or deleted; holds what the entry was before.
+    MapEntry(usize, MapKey, Option<(InnerData, InnerData)>),
+}
+
+pub struct Heap {
+    types: Vec<StructType>,
+    objects: Vec<Option<HeapObject>>,
+    /// Empty slots below `objects.len()`; the lowest is reused first, so handles
+    /// depend only on which objects are live, not on the order they died in.
+    free: BTreeSet<usize>,
+    cells: usize,
+    next_collection: usize,
+    stats: GcStats,
+    journal: Option<Vec<HeapWrite>>,
+}
+
+impl Default for Heap {
+    fn default() -> Self {
+        Heap::new()
+    }
+}
+
+impl Heap {
+    pub fn new() -> Heap {
+        Heap {
+            types: Vec::new(),
+            objects: Vec::new(),
+            free: BTreeSet::new(),
+            cells: 0,
+            next_collection: MIN_COLLECTION_THRESHOLD,
+            stats: GcStats::default(),
+            journal: None,
+        }
+    }
+
+    pub fn types(&self) -> &[StructType] {
+        &self.types
+    }
+
+    /// Replaces the struct types that `NEW` can instantiate.
+    pub fn define_types(&mut self, types: Vec<StructType>) {
+        self.types = types;
+    }
+
+    pub fn struct_type(&self, id: usize) -> Option<&StructType> {
+        self.types.get(id)
+    }
+
+    /// Every slot, indexed by handle. Freed slots are `None`.
+    pub fn objects(&self) -> &[Option<HeapObject>] {
+        &self.objects
+    }
+
+    /// Number of live objects.
+    pub fn len(&self) -> usize {
+        self.objects.len() - self.free.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Replaces every slot, as when restoring a snapshot.
+    pub fn load(&mut self, objects: Vec<Option<HeapObject>>) {
+        self.cells = objects.iter().flatten().map(HeapObject::cells).sum();
+        self.free = (0..objects.len()).filter(|handle| objects[*handle].is_none()).collect();
+        self.objects = objects;
+        self.trim();
+    }
+
+    /// Total number of values held by all objects.
+    pub fn cells(&self) -> usize {
+        self.cells
+    }
+
+    pub fn stats(&self) -> &GcStats {
+        &self.stats
+    }
+
+    /// Whether the heap has grown enough since the last collection that the next
+    /// allocation should collect first.
+    pub fn wants_collection(&self) -> bool {
+        self.size() >= self.next_collection
+    }
+
+    /// Cells plus live objects, so that objects holding no values, such as empty
+    /// arrays, still count towards the next collection.
+    fn size(&self) -> usize {
+        self.cells + self.len()
+    }
+
+    pub fn alloc(&mut self, object: HeapObject) -> InnerData {
+        self.cells += object.cells();
+
+        let handle = match self.free.pop_first() {
+            Some(handle) => {
+                self.objects[handle] = Some(object);
+                handle
+            },
+            None => {
+                self.objects.push(Some(object));
+                self.objects.len() - 1
+            },
+        };
+
+        self.record(HeapWrite::Alloc(handle));
+        InnerData::REF(handle)
+    }
+
+    pub fn get(&self, handle: usize) -> Option<&HeapObject> {
+        self.objects.get(handle).and_then(Option::as_ref)
+    }
+
+    fn free_slot(&mut self, handle: usize) -> Option<HeapObject> {
+        let object = self.objects[handle].take()?;
+
+        self.cells -= object.cells();
+        self.free.insert(handle);
+        Some(object)
+    }
+
+    /// Drops empty slots at the end so the slot list is as short as possible.
+    fn trim(&mut self) {
+        while let Some(None) = self.objects.last() {
+            self.objects.pop();
+            self.free.remove(&self.objects.len());
+        }
+    }
+
+    /// Frees every object that cannot be reached from `roots`.
+    pub fn collect<'a>(&mut self, roots: impl Iterator<Item = &'a InnerData>) {
+        let mut marked = vec![false; self.objects.len()];
+        let mut pending: Vec<usize> = Vec::new();
+
+        let mut mark = |value: &InnerData, pending: &mut Vec<usize>| {
+            if let InnerData::REF(handle) = value {
+                if *handle < marked.len() && !marked[*handle] {
+                    marked[*handle] = true;
+                    pending.push(*handle);
+                }
+            }
+        };
+
+        for root in roots {
+            mark(root, &mut pending);
+        }
+
+        while let Some(handle) = pending.pop() {
+            if let Some(object) = &self.objects[handle] {
+                for value in object.values() {
+                    mark(value, &mut pending);
+                }
+            }
+        }
+
+        for (handle, marked) in marked.into_iter().enumerate() {
+            if marked {
+                continue;
+            }
+
+            if let Some(object) = self.free_slot(handle) {
+                self.stats.objects_freed += 1;
+                self.stats.cells_freed += object.cells() as u64;
+                self.record(HeapWrite::Free(handle, object));
+            }
+        }
+
+        self.trim();
+        self.stats.collections += 1;
+        self.next_collection = (self.size() * 2).max(MIN_COLLECTION_THRESHOLD);
+    }
+
+    /// Replaces element `index` of array or struct `handle`, returning `false` if
+    /// there is no such element.
+    pub fn set_element(&mut self, handle: usize, index: usize, value: InnerData) -> bool {
+        let previous = match self.objects.get_mut(handle).and_then(Option::as_mut) {
+            Some(HeapObject::Array(elements)) |
+            Some(HeapObject::Struct(_, elements)) => match elements.get_mut(index) {
+                Some(element) => std::mem::replace(element, value),
+                None => return false,
+            },
+            _ => return false,
+        };
+
+        self.record(HeapWrite::Set(handle, index, previous));
+        true
+    }
+
+    /// Appends to array `handle`, returning `false` if there is no such array.
+    pub fn push_element(&mut self, handle: usize, value: InnerData) -> bool {
+        match self.objects.get_mut(handle).and_then(Option::as_mut) {
+            Some(HeapObject::Array(elements)) => elements.push(value),
+            _ => return false,
+        }
+
+        self.cells += 1;
+        self.record(HeapWrite::Push(handle));
+        true
+    }
+
+    /// Sets the value for `key` in map `handle`, keeping the key already stored
+    /// if there is an equal one. Returns `false` if there is no such map or the
+    /// key's type cannot be a map key.
+    pub fn map_insert(&mut self, handle: usize, key: InnerData, value: InnerData) -> bool {
+        let map_key = match MapKey::from_data(&key) {
+            Some(map_key) => map_key,
+            None => return false,
+        };
+
+        let entries = match self.objects.get_mut(handle).and_then(Option::as_mut) {
+            Some(HeapObject::Map(entries)) => entries,
+            _ => return false,
+        };
+
+        let previous = entries.get(&map_key).cloned();
+        let key = match &previous {
+            Some((stored, _)) => stored.clone(),
+            None => {
+                self.cells += 2;
+                key
+            },
+        };
+
+        entries.insert(map_key.clone(), (key, value));
+        self.record(HeapWrite::MapEntry(handle, map_key, previous));
+        true
+    }
+
+    /// Removes `key` from map `handle`, returning the removed value.
+    pub fn map_remove(&mut self, handle: usize, key: &InnerData) -> Option<InnerData> {
+        let map_key = MapKey::from_data(key)?;
+
+        let previous = match self.objects.get_mut(handle).and_then(Option::as_mut) {
+            Some(HeapObject::Map(entries)) => entries.remove(&map_key)?,
+            _ => return None,
+        };
+
+        self.cells -= 2;
+        self.record(HeapWrite::MapEntry(handle, map_key, Some(previous.clone())));
+        Some(previous.1)
+    }
+
+    fn record(&mut self, write: HeapWrite) {
+        if let Some(journal) = &mut self.journal {
+            journal.push(write);
+        }
+    }
+
+    /// Starts or stops recording every change made to the heap.
+    pub fn record_writes(&mut self, enabled: bool) {
+        self.journal = if enabled { Some(Vec::new()) } else { None };
+    }
+
+    /// Returns the changes recorded since the last call, oldest first.
+    pub fn take_writes(&mut self) -> Vec<HeapWrite> {
+        match &mut self.journal {
+            Some(journal) => std::mem::take(journal),
+            None => Vec::new(),
+        }
+    }
+
+    /// Reverts one recorded change. Changes must be undone newest first.
+    pub fn undo(&mut self, write: &HeapWrite) {
+        match write {
+            HeapWrite::Alloc(handle) => {
+                self.free_slot(*handle);
+                self.trim();
+            },
+            HeapWrite::Free(handle, object) => {
+                if *handle >= self.objects.len() {
+                    self.free.extend(self.objects.len()..*handle);
+                    self.objects.resize(*handle + 1, None);
+                }
+
+                self.free.remove(handle);
+                self.cells += object.cells();
+                self.objects[*handle] = Some(object.clone());
+            },
+            HeapWrite::Set(handle, index, previous) => {
+                if let Some(HeapObject::Array(elements) | HeapObject::Struct(_, elements)) =
+                    &mut self.objects[*handle] {
+                    elements[*index] = previous.clone();
+                }
+            },
+            HeapWrite::Push(handle) => {
+                if let Some(HeapObject::Array(elements)) = &mut self.objects[*handle] {
+                    elements.pop();
+                    self.cells -= 1;
+                }
+            },
+            HeapWrite::MapEntry(handle, key, previous) => {
+                if let Some(HeapObject::Map(entries)) = &mut self.objects[*handle] {
+                    let current = match previous {
+                        Some(entry) => entries.insert(key.clone(), entry.clone()),
+                        None => entries.remove(key),
+                    };
+
+                    match (current.is_some(), previous.is_some()) {
+                        (true, false) => self.cells -= 2,
+                        (false, true) => self.cells += 2,
+                        _ => {},
+                    }
+                }
+            },
+        }
+    }
+
+    /// Shows a value with the contents of any objects it refers to, naming the
+    /// fields of structs. A reference back to an object already being shown
+    /// prints as `[...]`, `Name {...}` or `{...}`.
+    pub fn render(&self, value: &InnerData) -> String {
+        let mut visiting = Vec::new();
+        self.render_nested(value, &mut visiting)
+    }
+
+    fn render_nested(&self, value: &InnerData, visiting: &mut Vec<usize>) -> String {
+        let handle = match value {
+            InnerData::REF(handle) => *handle,
+            _ => return value.to_string(),
+        };
+
+        let object = match self.get(handle) {
+            Some(object) => object,
+            None => return value.to_string(),
+        };
+
+        let type_name = |id: usize| match self.types.get(id) {
+            Some(struct_type) => struct_type.name.clone(),
+            None => format!("<type {}>", id),
+        };
+
+        if visiting.contains(&handle) {
+            return match object {
+                HeapObject::Array(_) => String::from("[...]"),
+                HeapObject::Struct(id, _) => format!("{} {{...}}", type_name(*id)),
+                HeapObject::Map(_) => String::from("{...}"),
+            };
+        }
+
+        visiting.push(handle);
+
+        let rendered = match object {
+            HeapObject::Array(elements) => {
+                let elements: Vec<String> = elements.iter()
+                    .map(|element| self.render_nested(element, visiting))
+                    .collect();
+
+                format!("[{}]", elements.join(", "))
+            },
+            HeapObject::Struct(id, values) => {
+                let names = self.types.get(*id).map(|struct_type| &struct_type.fields);
+
+                let fields: Vec<String> = values.iter().enumerate()
+                    .map(|(index, value)| {
+                        let name = match names.and_then(|names| names.get(index)) {
+                            Some(name) => name.clone(),
+                            None => index.to_string(),
+                        };
+
+                        format!("{}: {}", name, self.render_nested(value, visiting))
+                    })
+                    .collect();
+
+                match fields.is_empty() {
+                    true => format!("{} {{}}", type_name(*id)),
+                    false => format!("{} {{ {} }}", type_name(*id), fields.join(", ")),
+                }
+            },
+            HeapObject::Map(entries) => {
+                let entries: Vec<String> = entries.values()
+                    .map(|(key, value)| {
+                        format!("{}: {}", self.render_nested(key, visiting),
+                                self.render_nested(value, visiting))
+                    })
+                    .collect();
+
+                format!("{{{}}}", entries.join(", "))
+            },
+        };
+
+        visiting.pop();
+        rendered
+    }
+}
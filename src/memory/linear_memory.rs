@@ -0,0 +1,81 @@
+/// Flat, byte-addressed memory for programs that compute their own addresses.
+/// Multi-byte values are little-endian.
+pub struct LinearMemory {
+    bytes: Vec<u8>,
+    journal: Option<Vec<(usize, Vec<u8>)>>,
+}
+
+impl Default for LinearMemory {
+    fn default() -> Self {
+        LinearMemory::new(0)
+    }
+}
+
+impl LinearMemory {
+    pub fn new(size: usize) -> LinearMemory {
+        LinearMemory {
+            bytes: vec![0; size],
+            journal: None,
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.bytes.len()
+    }
+
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Replaces the contents and size, as when restoring a snapshot.
+    pub fn load(&mut self, bytes: Vec<u8>) {
+        self.bytes = bytes;
+    }
+
+    /// Sets every byte back to zero, keeping the size.
+    pub fn clear(&mut self) {
+        self.bytes.fill(0);
+    }
+
+    /// The `len` bytes at `address`, or `None` if any of them is out of bounds.
+    pub fn read(&self, address: usize, len: usize) -> Option<&[u8]> {
+        let end = address.checked_add(len)?;
+        self.bytes.get(address..end)
+    }
+
+    /// Copies `bytes` to `address`, returning `false` without writing anything if
+    /// any of them would be out of bounds.
+    pub fn write(&mut self, address: usize, bytes: &[u8]) -> bool {
+        let end = match address.checked_add(bytes.len()) {
+            Some(end) if end <= self.bytes.len() => end,
+            _ => return false,
+        };
+
+        let previous = self.bytes[address..end].to_vec();
+        self.bytes[address..end].copy_from_slice(bytes);
+
+        if let Some(journal) = &mut self.journal {
+            journal.push((address, previous));
+        }
+
+        true
+    }
+
+    /// Starts or stops recording the previous contents of every write.
+    pub fn record_writes(&mut self, enabled: bool) {
+        self.journal = if enabled { Some(Vec::new()) } else { None };
+    }
+
+    /// Returns the writes recorded since the last call, oldest first.
+    pub fn take_writes(&mut self) -> Vec<(usize, Vec<u8>)> {
+        match &mut self.journal {
+            Some(journal) => std::mem::take(journal),
+            None => Vec::new(),
+        }
+    }
+
+    /// Puts back the bytes a recorded write replaced.
+    pub fn undo(&mut self, address: usize, previous: &[u8]) {
+        self.bytes[address..address + previous.len()].copy_from_slice(previous);
+    }
+}
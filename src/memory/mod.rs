@@ -2,8 +2,14 @@ pub mod stack;
 pub mod program_memory;
 pub mod data_memory;
 pub mod data;
+pub mod bigint;
+pub mod heap;
+pub mod linear_memory;
 
 pub use stack::Stack;
 pub use program_memory::ProgramMemory;
 pub use data_memory::DataMemory;
-pub use data::InnerData;
\ No newline at end of file
+pub use data::InnerData;
+pub use bigint::BigInt;
+pub use heap::{Heap, HeapObject, HeapWrite, MapKey, StructType};
+pub use linear_memory::LinearMemory;
@@ -4,6 +4,12 @@ pub struct ProgramMemory {
     pub program: Vec<InstructionSet>,
 }
 
+impl Default for ProgramMemory {
+    fn default() -> Self {
+        ProgramMemory::new()
+    }
+}
+
 impl ProgramMemory {
     pub fn new() -> ProgramMemory {
         ProgramMemory {
@@ -3,6 +3,15 @@ use crate::memory::InnerData;
 pub struct Stack {
     pub data: Vec<InnerData>,
     pub head: usize,
+    /// While recording, the length of the prefix no change has reached yet and
+    /// the values that sat above it when recording started.
+    journal: Option<(usize, Vec<InnerData>)>,
+}
+
+impl Default for Stack {
+    fn default() -> Self {
+        Stack::new()
+    }
 }
 
 impl Stack {
@@ -10,6 +19,7 @@ impl Stack {
         Stack {
             data: Vec::new(),
             head: 0,
+            journal: None,
         }
     }
 
@@ -19,6 +29,7 @@ impl Stack {
     }
 
     pub fn pop(&mut self) -> Option<InnerData> {
+        self.touch(self.data.len().saturating_sub(1));
         match self.data.pop() {
             Some(value) => {
                 self.head -= 1;
@@ -39,4 +50,75 @@ impl Stack {
     pub fn top(&self) -> &InnerData {
         self.data.get(self.head - 1).unwrap()
     }
+
+    /// Returns the value `depth` places below the top, where 0 is the top.
+    pub fn peek(&self, depth: usize) -> Option<&InnerData> {
+        self.data.iter().rev().nth(depth)
+    }
+
+    /// Moves the value `depth` places below the top onto the top. Returns `false`
+    /// if the stack is not that deep.
+    pub fn roll(&mut self, depth: usize) -> bool {
+        if depth >= self.data.len() {
+            return false;
+        }
+
+        let idx = self.data.len() - 1 - depth;
+        self.touch(idx);
+        let value = self.data.remove(idx);
+        self.data.push(value);
+
+        true
+    }
+
+    pub fn truncate(&mut self, len: usize) {
+        self.touch(len);
+        self.data.truncate(len);
+        self.head = self.data.len();
+    }
+
+    /// Removes and returns the values from `at` to the top.
+    pub fn split_off(&mut self, at: usize) -> Vec<InnerData> {
+        self.touch(at);
+        let values = self.data.split_off(at);
+        self.head = self.data.len();
+        values
+    }
+
+    pub fn set(&mut self, idx: usize, value: InnerData) {
+        self.touch(idx);
+        self.data[idx] = value;
+    }
+
+    /// Inserts `values` at `idx`, shifting everything above it up.
+    pub fn insert(&mut self, idx: usize, values: impl IntoIterator<Item = InnerData>) {
+        self.touch(idx);
+        self.data.splice(idx..idx, values);
+        self.head = self.data.len();
+    }
+
+    pub fn record_writes(&mut self, enabled: bool) {
+        self.journal = if enabled { Some((self.data.len(), Vec::new())) } else { None };
+    }
+
+    /// Returns the length of the prefix left untouched since the last call, and the
+    /// values that sat above it then. Truncating to that length and pushing the
+    /// values back undoes every change in between.
+    pub fn take_writes(&mut self) -> (usize, Vec<InnerData>) {
+        let len = self.data.len();
+        match &mut self.journal {
+            Some(journal) => std::mem::replace(journal, (len, Vec::new())),
+            None => (len, Vec::new()),
+        }
+    }
+
+    /// Saves the values from `idx` up to the untouched prefix before they change.
+    fn touch(&mut self, idx: usize) {
+        if let Some((untouched, tail)) = &mut self.journal {
+            if idx < *untouched {
+                tail.splice(0..0, self.data[idx..*untouched].iter().cloned());
+                *untouched = idx;
+            }
+        }
+    }
 }
\ No newline at end of file
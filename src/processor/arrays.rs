@@ -0,0 +1,69 @@
+use crate::memory::{Heap, HeapObject, InnerData};
+
+use super::error::VmError;
+use super::strings::index;
+
+pub(super) fn handle(value: &InnerData) -> Result<usize, VmError> {
+    match value {
+        InnerData::REF(handle) => Ok(*handle),
+        _ => Err(VmError::TypeMismatch),
+    }
+}
+
+fn elements<'a>(heap: &'a Heap, array: &InnerData) -> Result<&'a [InnerData], VmError> {
+    match heap.get(handle(array)?) {
+        Some(HeapObject::Array(elements)) => Ok(elements),
+        Some(_) => Err(VmError::TypeMismatch),
+        None => Err(VmError::InvalidReference),
+    }
+}
+
+/// Allocates an array of `length` elements, all `INT(0)` like unset data slots.
+pub(super) fn new_array(heap: &mut Heap, length: &InnerData,
+                        max_cells: usize) -> Result<InnerData, VmError> {
+    let length = index(length)?;
+
+    if heap.cells().saturating_add(length) > max_cells {
+        return Err(VmError::HeapExhausted);
+    }
+
+    Ok(heap.alloc(HeapObject::Array(vec![InnerData::INT(0); length])))
+}
+
+pub(super) fn load(heap: &Heap, array: &InnerData, at: &InnerData) -> Result<InnerData, VmError> {
+    let elements = elements(heap, array)?;
+
+    match elements.get(index(at)?) {
+        Some(element) => Ok(element.clone()),
+        None => Err(VmError::IndexOutOfBounds),
+    }
+}
+
+pub(super) fn store(heap: &mut Heap, array: &InnerData, at: &InnerData,
+                    value: InnerData) -> Result<(), VmError> {
+    let len = elements(heap, array)?.len();
+    let at = index(at)?;
+
+    if at >= len {
+        return Err(VmError::IndexOutOfBounds);
+    }
+
+    heap.set_element(handle(array)?, at, value);
+    Ok(())
+}
+
+pub(super) fn len(heap: &Heap, array: &InnerData) -> Result<InnerData, VmError> {
+    Ok(InnerData::int(elements(heap, array)?.len() as i128))
+}
+
+pub(super) fn append(heap: &mut Heap, array: &InnerData, value: InnerData,
+                     max_cells: usize) -> Result<(), VmError> {
+    elements(heap, array)?;
+
+    if heap.cells() >= max_cells {
+        return Err(VmError::HeapExhausted);
+    }
+
+    heap.push_element(handle(array)?, value);
+    Ok(())
+}
@@ -3,4 +3,15 @@ pub(super) const STACK_OFFSET: u8 = 2;
 pub(super) const STACK_OFFSET_STR: u8 = 3;
 pub(super) const DATA_MEMORY_OFFSET: u8 = 4;
 pub(super) const ADDR_OFFSET: u8 = 5;
-pub(super) const PTR_OFFSET: u8 = 6;
\ No newline at end of file
+pub(super) const PTR_OFFSET: u8 = 6;
+pub(super) const STACK_OFFSET_BOOL: u8 = 7;
+
+// Bytes between the addresses of neighbouring data slots, as handed out by ADDR
+pub(super) const SLOT_SIZE: u32 = 8;
+
+// Rounding modes for FTOI
+pub(super) const ROUND_TRUNC: u8 = 0;
+pub(super) const ROUND_NEAREST: u8 = 1;
+pub(super) const ROUND_FLOOR: u8 = 2;
+pub(super) const ROUND_CEIL: u8 = 3;
+pub(super) const ROUND_HALF_EVEN: u8 = 4;
\ No newline at end of file
@@ -0,0 +1,167 @@
+use crate::memory::{BigInt, InnerData};
+
+use super::error::VmError;
+
+/// Integer types named by the operand of `WIDEN`, `NARROW`, `NARROWC`, `MLOAD` and
+/// `MSTORE`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IntType {
+    I8,
+    I16,
+    I32,
+    I64,
+    U8,
+    U16,
+    U32,
+    U64,
+    BigInt,
+}
+
+impl IntType {
+    pub fn from_code(code: u8) -> Option<IntType> {
+        match code {
+            0 => Some(IntType::I8),
+            1 => Some(IntType::I16),
+            2 => Some(IntType::I32),
+            3 => Some(IntType::I64),
+            4 => Some(IntType::U8),
+            5 => Some(IntType::U16),
+            6 => Some(IntType::U32),
+            7 => Some(IntType::U64),
+            8 => Some(IntType::BigInt),
+            _ => None,
+        }
+    }
+
+    /// Signedness and width of the fixed-width types.
+    pub(super) fn kind(self) -> Option<(bool, u32)> {
+        match self {
+            IntType::I8 => Some((true, 8)),
+            IntType::I16 => Some((true, 16)),
+            IntType::I32 => Some((true, 32)),
+            IntType::I64 => Some((true, 64)),
+            IntType::U8 => Some((false, 8)),
+            IntType::U16 => Some((false, 16)),
+            IntType::U32 => Some((false, 32)),
+            IntType::U64 => Some((false, 64)),
+            IntType::BigInt => None,
+        }
+    }
+
+    /// Whether every value of `value`'s type is also a value of this type.
+    fn holds_all_of(self, value: &InnerData) -> bool {
+        let (signed, bits) = match self.kind() {
+            Some(kind) => kind,
+            None => return true,
+        };
+
+        match value.bit_width() {
+            Some(value_bits) if value.is_unsigned() => {
+                if signed { bits > value_bits } else { bits >= value_bits }
+            },
+            Some(value_bits) => signed && bits >= value_bits,
+            None => false,
+        }
+    }
+}
+
+fn integer(value: InnerData) -> Result<InnerData, VmError> {
+    if value.is_integer() {
+        Ok(value)
+    } else {
+        Err(VmError::TypeMismatch)
+    }
+}
+
+/// Converts to a type that can represent every value of the current one.
+pub(super) fn widen(value: InnerData, target: IntType) -> Result<InnerData, VmError> {
+    let value = integer(value)?;
+
+    if !target.holds_all_of(&value) {
+        return Err(VmError::InvalidConversion);
+    }
+
+    narrow_checked(value, target)
+}
+
+/// Converts keeping the value, failing if the target type cannot hold it.
+pub(super) fn narrow_checked(value: InnerData, target: IntType) -> Result<InnerData, VmError> {
+    let value = integer(value)?;
+
+    match target.kind() {
+        Some((signed, bits)) => match value.to_i128() {
+            Some(value) => InnerData::from_i128(value, signed, bits).ok_or(VmError::InvalidConversion),
+            None => Err(VmError::InvalidConversion),
+        },
+        None => Ok(InnerData::BIGINT(value.to_bigint().unwrap())),
+    }
+}
+
+/// Converts keeping only the low bits of the value, like Rust's `as`.
+pub(super) fn narrow_wrapping(value: InnerData, target: IntType) -> Result<InnerData, VmError> {
+    let value = integer(value)?;
+
+    let (signed, bits) = match target.kind() {
+        Some(kind) => kind,
+        None => return Ok(InnerData::BIGINT(value.to_bigint().unwrap())),
+    };
+
+    let low_bits = match value.to_i128() {
+        Some(value) => value,
+        None => {
+            let modulus = BigInt::from(1i128 << bits);
+            let mut low_bits = value.to_bigint().unwrap() % modulus.clone();
+
+            if low_bits.is_negative() {
+                low_bits = low_bits + modulus;
+            }
+
+            low_bits.to_i128().unwrap()
+        },
+    };
+
+    Ok(InnerData::wrapping_from_i128(low_bits, signed, bits))
+}
+
+/// Replaces the bits above the low `bits` bits with copies of bit `bits - 1`
+/// (sign extension) or with zeros, keeping the type of the value.
+pub(super) fn extend(value: InnerData, bits: u8, signed: bool) -> Result<InnerData, VmError> {
+    let width = match value.bit_width() {
+        Some(width) => width,
+        None => return Err(VmError::TypeMismatch),
+    };
+
+    let bits = bits as u32;
+    if bits == 0 || bits > width {
+        return Err(VmError::InvalidConversion);
+    }
+
+    let modulus = 1i128 << bits;
+    let mut low_bits = value.to_i128().unwrap().rem_euclid(modulus);
+
+    if signed && low_bits >= modulus / 2 {
+        low_bits -= modulus;
+    }
+
+    Ok(InnerData::wrapping_from_i128(low_bits, !value.is_unsigned(), width))
+}
+
+/// Parses a decimal integer, optionally signed, into the narrowest signed type
+/// that holds it.
+pub(super) fn parse(value: InnerData) -> Result<InnerData, VmError> {
+    let text = match value {
+        InnerData::STR(text) => text,
+        _ => return Err(VmError::TypeMismatch),
+    };
+
+    let digits = match text.strip_prefix('+') {
+        Some(digits) if digits.starts_with('-') => return Err(VmError::InvalidConversion),
+        Some(digits) => digits,
+        None => &text,
+    };
+
+    match BigInt::parse(digits) {
+        Some(integer) => Ok(InnerData::from_integer(integer)),
+        None => Err(VmError::InvalidConversion),
+    }
+}
@@ -0,0 +1,58 @@
+use std::fmt::{Display, self};
+
+#[derive(Debug, PartialEq)]
+pub enum VmError {
+    OutOfFuel,
+    StackOverflow,
+    CallStackOverflow,
+    DataMemoryExhausted,
+    StringTooLong,
+    TypeMismatch,
+    StackUnderflow,
+    InvalidConversion,
+    IntegerOverflow,
+    DivisionByZero,
+    IndexOutOfBounds,
+    InvalidFormat,
+    InvalidReference,
+    HeapExhausted,
+    KeyNotFound,
+    MemoryFault,
+    UninitializedRead,
+    InvalidCallTarget,
+    InvalidRegister,
+    InvalidOffset,
+    InvalidReturn,
+    BigIntTooLarge,
+}
+
+impl Display for VmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VmError::OutOfFuel => write!(f, "Out of fuel!"),
+            VmError::StackOverflow => write!(f, "Stack overflow!"),
+            VmError::CallStackOverflow => write!(f, "Call stack overflow!"),
+            VmError::DataMemoryExhausted => write!(f, "Data memory exhausted!"),
+            VmError::StringTooLong => write!(f, "String too long!"),
+            VmError::TypeMismatch => write!(f, "Invalid type!"),
+            VmError::StackUnderflow => write!(f, "Stack is empty!"),
+            VmError::InvalidConversion => write!(f, "Invalid conversion!"),
+            VmError::IntegerOverflow => write!(f, "Integer overflow!"),
+            VmError::DivisionByZero => write!(f, "Division by zero!"),
+            VmError::IndexOutOfBounds => write!(f, "Index out of bounds!"),
+            VmError::InvalidFormat => write!(f, "Invalid format string!"),
+            VmError::InvalidReference => write!(f, "Invalid reference!"),
+            VmError::HeapExhausted => write!(f, "Heap exhausted!"),
+            VmError::KeyNotFound => write!(f, "Key not found!"),
+            VmError::MemoryFault => write!(f, "Memory access out of bounds!"),
+            VmError::UninitializedRead => write!(f, "Read of an unwritten data slot!"),
+            VmError::InvalidCallTarget => write!(f, "Invalid call target!"),
+            VmError::InvalidRegister => write!(f, "Register index out of bounds!"),
+            VmError::InvalidOffset => write!(f, "Invalid offset!"),
+            VmError::InvalidReturn => write!(f, "Return without a matching call!"),
+            VmError::BigIntTooLarge => write!(f, "Integer too large!"),
+        }
+    }
+}
+
+impl std::error::Error for VmError {}
@@ -0,0 +1,183 @@
+use crate::memory::{Heap, InnerData};
+
+use super::error::VmError;
+
+/// A parsed `{:...}` placeholder: `[[fill]align][0][width][type]`, where align
+/// is one of `<`, `>`, `^` and type one of `d`, `x`, `X`, `o`, `b`.
+struct Spec {
+    fill: char,
+    align: Option<char>,
+    zero: bool,
+    width: usize,
+    radix: Option<char>,
+}
+
+impl Spec {
+    fn parse(spec: &str) -> Result<Spec, VmError> {
+        let spec = match spec.strip_prefix(':') {
+            Some(spec) => spec,
+            None if spec.is_empty() => spec,
+            None => return Err(VmError::InvalidFormat),
+        };
+
+        let chars: Vec<char> = spec.chars().collect();
+        let mut i = 0;
+
+        let (fill, align) = match chars.as_slice() {
+            [fill, align, ..] if "<>^".contains(*align) => {
+                i = 2;
+                (*fill, Some(*align))
+            },
+            [align, ..] if "<>^".contains(*align) => {
+                i = 1;
+                (' ', Some(*align))
+            },
+            _ => (' ', None),
+        };
+
+        let zero = chars.get(i) == Some(&'0');
+        if zero {
+            i += 1;
+        }
+
+        let digits: String = chars[i..].iter().take_while(|ch| ch.is_ascii_digit()).collect();
+        i += digits.len();
+
+        let width = match digits.is_empty() {
+            true => 0,
+            false => digits.parse().map_err(|_| VmError::InvalidFormat)?,
+        };
+
+        let radix = match &chars[i..] {
+            [] => None,
+            [radix] if "dxXob".contains(*radix) => Some(*radix),
+            _ => return Err(VmError::InvalidFormat),
+        };
+
+        Ok(Spec {
+            fill,
+            align,
+            zero,
+            width,
+            radix,
+        })
+    }
+}
+
+/// Digits of an integer in the radix named by `kind`. Fixed-width negatives are
+/// shown in two's complement at their width, as Rust does; BIGINT keeps its sign.
+fn radix_digits(value: &InnerData, kind: char) -> Result<String, VmError> {
+    let radix = match kind {
+        'x' | 'X' => 16,
+        'o' => 8,
+        'b' => 2,
+        _ => 10,
+    };
+
+    let digits = match (value, value.bit_width(), value.to_i128()) {
+        (_, Some(bits), Some(integer)) if radix != 10 => {
+            let bits = integer.rem_euclid(1i128 << bits) as u128;
+
+            match radix {
+                16 => format!("{:x}", bits),
+                8 => format!("{:o}", bits),
+                _ => format!("{:b}", bits),
+            }
+        },
+        (InnerData::BIGINT(integer), _, _) => integer.to_str_radix(radix),
+        _ if value.is_integer() => value.to_string(),
+        _ => return Err(VmError::InvalidFormat),
+    };
+
+    match kind {
+        'X' => Ok(digits.to_uppercase()),
+        _ => Ok(digits),
+    }
+}
+
+fn format_one(spec: &str, value: &InnerData, heap: &Heap, max_len: usize) -> Result<String, VmError> {
+    let spec = Spec::parse(spec)?;
+
+    if spec.width > max_len {
+        return Err(VmError::StringTooLong);
+    }
+
+    let text = match spec.radix {
+        Some(kind) => radix_digits(value, kind)?,
+        None => heap.render(value),
+    };
+
+    let (sign, body) = match text.strip_prefix('-') {
+        Some(body) if value.is_numeric() => ("-", body),
+        _ => ("", text.as_str()),
+    };
+
+    let len = sign.chars().count() + body.chars().count();
+    if len >= spec.width {
+        return Ok(text);
+    }
+
+    let padding = spec.width - len;
+
+    // Zero padding goes between the sign and the digits, and only for numbers.
+    if spec.zero && spec.align.is_none() && value.is_numeric() {
+        return Ok(format!("{}{}{}", sign, "0".repeat(padding), body));
+    }
+
+    let fill = |count: usize| spec.fill.to_string().repeat(count);
+    let default_align = if value.is_numeric() { '>' } else { '<' };
+
+    Ok(match spec.align.unwrap_or(default_align) {
+        '<' => format!("{}{}", text, fill(padding)),
+        '^' => format!("{}{}{}", fill(padding / 2), text, fill(padding - padding / 2)),
+        _ => format!("{}{}", fill(padding), text),
+    })
+}
+
+/// Replaces each `{}` or `{:spec}` in `template` with the next value; `{{` and
+/// `}}` stand for literal braces. The number of placeholders must match the
+/// number of values. References are rendered through `heap`, as `SHOW` does.
+pub(super) fn format(template: &str, values: &[InnerData], heap: &Heap, max_len: usize) -> Result<String, VmError> {
+    let mut result = String::new();
+    let mut values = values.iter();
+    let mut chars = template.chars().peekable();
+
+    while let Some(ch) = chars.next() {
+        match ch {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                result.push('{');
+            },
+            '{' => {
+                let mut spec = String::new();
+
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(ch) => spec.push(ch),
+                        None => return Err(VmError::InvalidFormat),
+                    }
+                }
+
+                let value = values.next().ok_or(VmError::InvalidFormat)?;
+                result.push_str(&format_one(&spec, value, heap, max_len)?);
+
+                if result.len() > max_len {
+                    return Err(VmError::StringTooLong);
+                }
+            },
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                result.push('}');
+            },
+            '}' => return Err(VmError::InvalidFormat),
+            _ => result.push(ch),
+        }
+    }
+
+    if values.next().is_some() {
+        return Err(VmError::InvalidFormat);
+    }
+
+    Ok(result)
+}
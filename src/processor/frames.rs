@@ -0,0 +1,157 @@
+use crate::memory::{InnerData, Stack};
+
+use super::error::VmError;
+
+/// Values `CALLF` pushes onto the call stack below a frame's locals: the return
+/// pc, the caller's frame pointer, the operand stack height to return to and the
+/// number of locals in the caller's frame.
+const HEADER_LEN: usize = 4;
+
+/// A function activation as seen by a debugger. Locals start with the arguments
+/// in the order they were pushed.
+#[derive(Debug, PartialEq)]
+pub struct Frame<'a> {
+    /// Pc of the `CALLF` that made the frame, or `None` for the outermost frame.
+    pub return_pc: Option<usize>,
+    pub locals: &'a [InnerData],
+}
+
+fn header_value(value: &InnerData) -> Result<usize, VmError> {
+    match value.to_i128().map(usize::try_from) {
+        Some(Ok(value)) => Ok(value),
+        Some(Err(_)) => Err(VmError::InvalidConversion),
+        None => Err(VmError::TypeMismatch),
+    }
+}
+
+/// Opens a frame whose first `argc` locals are moved off the operand stack, and
+/// points `fp` at it. Fails without touching either stack if the frame would
+/// take the call stack past `max_call_depth`.
+pub(super) fn call(fp: &mut usize, locals: &mut usize, pc: usize, argc: usize, stack: &mut Stack,
+                   call_stack: &mut Stack, max_call_depth: usize) -> Result<(), VmError> {
+    if argc > stack.data.len() {
+        return Err(VmError::StackUnderflow);
+    }
+
+    if call_stack.data.len().saturating_add(HEADER_LEN + argc) > max_call_depth {
+        return Err(VmError::CallStackOverflow);
+    }
+
+    let base = stack.data.len() - argc;
+    let args = stack.split_off(base);
+
+    call_stack.push(InnerData::int(pc as i128));
+    call_stack.push(InnerData::int(*fp as i128));
+    call_stack.push(InnerData::int(base as i128));
+    call_stack.push(InnerData::int(*locals as i128));
+
+    *fp = call_stack.data.len();
+    *locals = argc;
+    for arg in args {
+        call_stack.push(arg);
+    }
+
+    Ok(())
+}
+
+/// Closes the current frame, keeping the top `count` operands and dropping
+/// everything else the callee left on the operand stack. Returns the pc to
+/// resume from.
+pub(super) fn ret(fp: &mut usize, locals: &mut usize, count: usize, stack: &mut Stack,
+                  call_stack: &mut Stack) -> Result<usize, VmError> {
+    if *fp < HEADER_LEN || *fp > call_stack.data.len() {
+        return Err(VmError::StackUnderflow);
+    }
+
+    let header = &call_stack.data[*fp - HEADER_LEN..*fp];
+    let return_pc = header_value(&header[0])?;
+    let caller_fp = header_value(&header[1])?;
+    let base = header_value(&header[2])?;
+    let caller_locals = header_value(&header[3])?;
+
+    if count > stack.data.len() || stack.data.len() - count < base {
+        return Err(VmError::StackUnderflow);
+    }
+
+    let results = stack.split_off(stack.data.len() - count);
+    stack.truncate(base);
+    for result in results {
+        stack.push(result);
+    }
+
+    call_stack.truncate(*fp - HEADER_LEN);
+    *fp = caller_fp;
+    *locals = caller_locals;
+
+    Ok(return_pc)
+}
+
+/// Number of return pcs that `CALL` pushed above the current frame's locals.
+pub(super) fn pending_returns(fp: usize, locals: usize, call_stack: &Stack) -> usize {
+    call_stack.data.len().saturating_sub(fp + locals)
+}
+
+/// The local `n` of the current frame, or `None` if it was never stored.
+pub(super) fn load_local(fp: usize, locals: usize, n: usize, call_stack: &Stack) -> Option<&InnerData> {
+    match n < locals {
+        true => call_stack.data.get(fp + n),
+        false => None,
+    }
+}
+
+/// Stores local `n` of the current frame, growing the frame with `INT(0)` locals
+/// if it is not that large yet. New locals go below any return pcs that `CALL`
+/// pushed in this frame, so a subroutine cannot overwrite its own return address.
+pub(super) fn store_local(fp: usize, locals: &mut usize, n: usize, value: InnerData,
+                          call_stack: &mut Stack, max_call_depth: usize) -> Result<(), VmError> {
+    if n < *locals {
+        call_stack.set(fp + n, value);
+        return Ok(());
+    }
+
+    let growth = n - *locals + 1;
+    if call_stack.data.len().saturating_add(growth) > max_call_depth {
+        return Err(VmError::CallStackOverflow);
+    }
+
+    let end = fp + *locals;
+    let fill = std::iter::repeat_n(InnerData::INT(0), growth - 1).chain(std::iter::once(value));
+    call_stack.insert(end, fill);
+    *locals = n + 1;
+
+    Ok(())
+}
+
+/// The frames on the call stack, innermost first.
+pub(super) fn frames(fp: usize, locals: usize, call_stack: &Stack) -> Vec<Frame<'_>> {
+    let mut frames = Vec::new();
+    let len = call_stack.data.len();
+    let mut fp = fp.min(len);
+    let mut locals = locals.min(len - fp);
+
+    while fp >= HEADER_LEN {
+        let header = &call_stack.data[fp - HEADER_LEN..fp];
+
+        let (return_pc, caller_fp, caller_locals) =
+            match (header_value(&header[0]), header_value(&header[1]), header_value(&header[3])) {
+                (Ok(return_pc), Ok(caller_fp), Ok(caller_locals))
+                    if caller_fp + caller_locals <= fp - HEADER_LEN => (return_pc, caller_fp, caller_locals),
+                _ => break,
+            };
+
+        frames.push(Frame {
+            return_pc: Some(return_pc),
+            locals: &call_stack.data[fp..fp + locals],
+        });
+
+        fp = caller_fp;
+        locals = caller_locals;
+    }
+
+    frames.push(Frame {
+        return_pc: None,
+        locals: &call_stack.data[fp..fp + locals],
+    });
+
+    frames
+}
@@ -0,0 +1,39 @@
+/// Default for `Limits::max_data_slots`.
+pub const DEFAULT_DATA_SLOTS: usize = 1 << 20;
+
+/// Default for `Limits::max_bigint_bits`.
+pub const DEFAULT_BIGINT_BITS: usize = 1 << 20;
+
+pub struct Limits {
+    pub max_stack_depth: usize,
+    pub max_call_depth: usize,
+    /// Data slot indices must be below this, which also bounds the size of data
+    /// memory. Defaults to `DEFAULT_DATA_SLOTS` rather than unlimited.
+    pub max_data_slots: usize,
+    /// Maximum length of a string value, in bytes.
+    pub max_string_len: usize,
+    /// Maximum number of values held by all heap objects together.
+    pub max_heap_cells: usize,
+    /// Maximum magnitude of a BIGINT that arithmetic may produce, in bits.
+    /// Defaults to `DEFAULT_BIGINT_BITS` rather than unlimited.
+    pub max_bigint_bits: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits::new()
+    }
+}
+
+impl Limits {
+    pub fn new() -> Limits {
+        Limits {
+            max_stack_depth: usize::MAX,
+            max_call_depth: usize::MAX,
+            max_data_slots: DEFAULT_DATA_SLOTS,
+            max_string_len: usize::MAX,
+            max_heap_cells: usize::MAX,
+            max_bigint_bits: DEFAULT_BIGINT_BITS,
+        }
+    }
+}
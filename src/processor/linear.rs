@@ -0,0 +1,40 @@
+use crate::memory::{InnerData, LinearMemory};
+
+use super::error::VmError;
+
+/// Reads a byte address. Addresses are plain integers of any type, so pointer
+/// arithmetic is ordinary integer arithmetic.
+fn address(value: &InnerData) -> Result<usize, VmError> {
+    if !value.is_integer() {
+        return Err(VmError::TypeMismatch);
+    }
+
+    match value.to_i128().map(usize::try_from) {
+        Some(Ok(address)) => Ok(address),
+        _ => Err(VmError::MemoryFault),
+    }
+}
+
+/// Reads a little-endian integer of `bits` bits at `at`.
+pub(super) fn load(memory: &LinearMemory, at: &InnerData, signed: bool,
+                   bits: u32) -> Result<InnerData, VmError> {
+    let bytes = memory.read(address(at)?, bits as usize / 8).ok_or(VmError::MemoryFault)?;
+
+    let mut raw = [0; 16];
+    raw[..bytes.len()].copy_from_slice(bytes);
+
+    Ok(InnerData::wrapping_from_i128(i128::from_le_bytes(raw), signed, bits))
+}
+
+/// Writes the low `bits` bits of `value` at `at`, little-endian. `value` must
+/// already hold no more than those bits, as `NARROW` leaves it.
+pub(super) fn store(memory: &mut LinearMemory, at: &InnerData, value: &InnerData,
+                    bits: u32) -> Result<(), VmError> {
+    let at = address(at)?;
+    let raw = value.to_i128().unwrap().to_le_bytes();
+
+    match memory.write(at, &raw[..bits as usize / 8]) {
+        true => Ok(()),
+        false => Err(VmError::MemoryFault),
+    }
+}
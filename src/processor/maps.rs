@@ -0,0 +1,89 @@
+use crate::memory::{Heap, HeapObject, InnerData, MapKey};
+
+use super::arrays::handle;
+use super::error::VmError;
+
+fn key(value: &InnerData) -> Result<MapKey, VmError> {
+    MapKey::from_data(value).ok_or(VmError::TypeMismatch)
+}
+
+fn check_map(heap: &Heap, map: &InnerData) -> Result<usize, VmError> {
+    let handle = handle(map)?;
+
+    match heap.get(handle) {
+        Some(HeapObject::Map(_)) => Ok(handle),
+        Some(_) => Err(VmError::TypeMismatch),
+        None => Err(VmError::InvalidReference),
+    }
+}
+
+/// An empty map holds no cells, so like an empty array it only fails on a heap
+/// that is already over its limit, as after the host lowers it.
+pub(super) fn new_map(heap: &mut Heap, max_cells: usize) -> Result<InnerData, VmError> {
+    if heap.cells() > max_cells {
+        return Err(VmError::HeapExhausted);
+    }
+
+    Ok(heap.alloc(HeapObject::Map(Default::default())))
+}
+
+fn entry<'a>(heap: &'a Heap, map: &InnerData,
+             at: &InnerData) -> Result<Option<&'a InnerData>, VmError> {
+    let handle = check_map(heap, map)?;
+    let at = key(at)?;
+
+    match heap.get(handle) {
+        Some(HeapObject::Map(entries)) => Ok(entries.get(&at).map(|(_, value)| value)),
+        _ => unreachable!(),
+    }
+}
+
+pub(super) fn get(heap: &Heap, map: &InnerData, at: &InnerData) -> Result<InnerData, VmError> {
+    match entry(heap, map, at)? {
+        Some(value) => Ok(value.clone()),
+        None => Err(VmError::KeyNotFound),
+    }
+}
+
+pub(super) fn has(heap: &Heap, map: &InnerData, at: &InnerData) -> Result<InnerData, VmError> {
+    Ok(InnerData::BOOL(entry(heap, map, at)?.is_some()))
+}
+
+pub(super) fn set(heap: &mut Heap, map: &InnerData, at: InnerData, value: InnerData,
+                  max_cells: usize) -> Result<(), VmError> {
+    let handle = check_map(heap, map)?;
+    key(&at)?;
+
+    if entry(heap, map, &at)?.is_none() && heap.cells().saturating_add(2) > max_cells {
+        return Err(VmError::HeapExhausted);
+    }
+
+    heap.map_insert(handle, at, value);
+    Ok(())
+}
+
+/// Removes the entry for `at`; a missing key is not an error.
+pub(super) fn delete(heap: &mut Heap, map: &InnerData, at: &InnerData) -> Result<(), VmError> {
+    let handle = check_map(heap, map)?;
+    key(at)?;
+
+    heap.map_remove(handle, at);
+    Ok(())
+}
+
+/// A new array holding the keys in iteration order.
+pub(super) fn keys(heap: &mut Heap, map: &InnerData,
+                   max_cells: usize) -> Result<InnerData, VmError> {
+    let handle = check_map(heap, map)?;
+
+    let keys: Vec<InnerData> = match heap.get(handle) {
+        Some(HeapObject::Map(entries)) => entries.values().map(|(key, _)| key.clone()).collect(),
+        _ => unreachable!(),
+    };
+
+    if heap.cells().saturating_add(keys.len()) > max_cells {
+        return Err(VmError::HeapExhausted);
+    }
+
+    Ok(heap.alloc(HeapObject::Array(keys)))
+}
@@ -1,4 +1,21 @@
+#[allow(clippy::module_inception)]
 pub mod processor;
+pub mod error;
+pub mod limits;
+pub mod overflow;
+pub mod convert;
+pub mod frames;
+mod strings;
+mod format;
+mod arrays;
+mod structs;
+mod maps;
+mod linear;
 mod constants;
 
-pub use processor::Processor;
\ No newline at end of file
+pub use processor::Processor;
+pub use error::VmError;
+pub use limits::Limits;
+pub use overflow::OverflowPolicy;
+pub use convert::IntType;
+pub use frames::Frame;
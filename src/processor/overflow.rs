@@ -0,0 +1,130 @@
+use crate::memory::InnerData;
+
+use super::error::VmError;
+
+/// What integer arithmetic does when a result does not fit the width of its
+/// operands. Only fixed-width integers are affected; BIGINT and FLOAT results
+/// never overflow.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum OverflowPolicy {
+    /// Move to the next wider type, ending at BIGINT.
+    #[default]
+    Promote,
+    /// Keep the low bits, as two's complement hardware does.
+    Wrap,
+    /// Clamp to the smallest or largest value of the type.
+    Saturate,
+    /// Stop with `VmError::IntegerOverflow`.
+    Trap,
+}
+
+impl OverflowPolicy {
+    /// Decodes the operand of the `ADDP`-style instructions.
+    pub fn from_code(code: u8) -> Option<OverflowPolicy> {
+        match code {
+            0 => Some(OverflowPolicy::Promote),
+            1 => Some(OverflowPolicy::Wrap),
+            2 => Some(OverflowPolicy::Saturate),
+            3 => Some(OverflowPolicy::Trap),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) enum ArithOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+}
+
+/// Computes `a op b`, moving to wider types as needed. Operands are already held
+/// to `max_bigint_bits`, so the result is at most twice that before it is checked.
+fn promoting(op: ArithOp, a: InnerData, b: InnerData, max_bigint_bits: usize) -> Result<InnerData, VmError> {
+    let result = match op {
+        ArithOp::Add => a + b,
+        ArithOp::Sub => a - b,
+        ArithOp::Mul => a * b,
+        ArithOp::Div => a / b,
+        ArithOp::Rem => a % b,
+    };
+
+    match &result {
+        InnerData::BIGINT(value) if value.bits() > max_bigint_bits => Err(VmError::BigIntTooLarge),
+        _ => Ok(result),
+    }
+}
+
+/// Whether `a op b` is defined at all: numbers mix freely, strings can be
+/// concatenated, and a string can be repeated an integer number of times.
+fn defined(op: ArithOp, a: &InnerData, b: &InnerData) -> bool {
+    match (a, b) {
+        _ if a.is_numeric() && b.is_numeric() => true,
+        (InnerData::STR(_), InnerData::STR(_)) => op == ArithOp::Add,
+        (InnerData::STR(_), count) | (count, InnerData::STR(_)) => op == ArithOp::Mul && count.is_integer(),
+        _ => false,
+    }
+}
+
+/// Computes `a op b` under `policy`. Operands of different widths are first
+/// brought to a common type as usual; the policy decides what happens when
+/// the result does not fit that type.
+pub(super) fn apply(policy: OverflowPolicy, op: ArithOp, a: InnerData, b: InnerData,
+                    max_bigint_bits: usize) -> Result<InnerData, VmError> {
+    // The operators panic on operands they do not define, and both come from the guest.
+    if !defined(op, &a, &b) {
+        return Err(VmError::TypeMismatch);
+    }
+
+    // Checked before any policy runs, as even promotion cannot divide by zero.
+    if matches!(op, ArithOp::Div | ArithOp::Rem) && a.is_integer() && b.to_i128() == Some(0) {
+        return Err(VmError::DivisionByZero);
+    }
+
+    if policy == OverflowPolicy::Promote || a.bit_width().is_none() || b.bit_width().is_none() {
+        return promoting(op, a, b, max_bigint_bits);
+    }
+
+    let (a, b) = a.promote_or_not(b);
+    let bits = match a.bit_width() {
+        Some(bits) => bits,
+        // A mixed-sign pair too wide for INT64 meets in BIGINT, which never overflows.
+        None => return promoting(op, a, b, max_bigint_bits),
+    };
+    let signed = !a.is_unsigned();
+    let (x, y) = (a.to_i128().unwrap(), b.to_i128().unwrap());
+
+    // Operands are at most 64 bits wide, so only an unsigned 64-bit product can
+    // leave i128; its wrapped value is still right modulo 2^64.
+    let (exact, wrapped) = match op {
+        ArithOp::Add => (Some(x + y), x + y),
+        ArithOp::Sub => (Some(x - y), x - y),
+        ArithOp::Mul => (x.checked_mul(y), x.wrapping_mul(y)),
+        ArithOp::Div => (Some(x / y), x / y),
+        ArithOp::Rem => (Some(x % y), x % y),
+    };
+
+    if let Some(result) = exact.and_then(|exact| InnerData::from_i128(exact, signed, bits)) {
+        return Ok(result);
+    }
+
+    let (min, max) = if signed {
+        (-(1i128 << (bits - 1)), (1i128 << (bits - 1)) - 1)
+    } else {
+        (0, (1i128 << bits) - 1)
+    };
+
+    let result = match policy {
+        OverflowPolicy::Wrap => return Ok(InnerData::wrapping_from_i128(wrapped, signed, bits)),
+        OverflowPolicy::Saturate => match exact {
+            Some(exact) => exact.clamp(min, max),
+            None if (x < 0) != (y < 0) => min,
+            None => max,
+        },
+        _ => return Err(VmError::IntegerOverflow),
+    };
+
+    Ok(InnerData::from_i128(result, signed, bits).unwrap())
+}
@@ -1,18 +1,116 @@
-use std::io;
+use std::cmp::{max, Ordering};
+use std::collections::HashMap;
+use std::io::{self, BufRead, Read};
 use crate::instructions::InstructionSet;
 use crate::memory::stack::Stack;
-use crate::memory::{ProgramMemory, DataMemory, InnerData};
+use crate::memory::{ProgramMemory, DataMemory, InnerData, BigInt};
 
 use super::constants::{REGISTER_OFFSET, STACK_OFFSET, STACK_OFFSET_STR, DATA_MEMORY_OFFSET};
-use super::constants::{ADDR_OFFSET, PTR_OFFSET};
+use super::constants::{ADDR_OFFSET, PTR_OFFSET, STACK_OFFSET_BOOL, SLOT_SIZE};
+use super::constants::{ROUND_TRUNC, ROUND_NEAREST, ROUND_FLOOR, ROUND_CEIL, ROUND_HALF_EVEN};
+use super::error::VmError;
+use super::limits::Limits;
+use super::overflow::{self, OverflowPolicy, ArithOp};
+use super::convert::{self, IntType};
+use super::strings;
+use super::format;
+use super::arrays;
+use super::structs;
+use super::maps;
+use super::linear;
+use super::frames::{self, Frame};
 
+fn pop(stack: &mut Stack) -> Result<InnerData, VmError> {
+    stack.pop().ok_or(VmError::StackUnderflow)
+}
+
+/// Byte operands (labels, register indices, counts, modes) are converted
+/// with a check, so a wide or non-integer value is an error rather than a panic.
+fn byte_operand(value: &InnerData) -> Result<u8, VmError> {
+    match value.to_byte() {
+        Some(byte) => Ok(byte),
+        None if value.is_integer() => Err(VmError::InvalidConversion),
+        None => Err(VmError::TypeMismatch),
+    }
+}
+
+/// Integer type codes come from bytecode, so an unknown one is an error.
+fn int_type_operand(value: &InnerData) -> Result<IntType, VmError> {
+    IntType::from_code(byte_operand(value)?).ok_or(VmError::InvalidConversion)
+}
+
+/// Data slot indices are bytes when the loader reads them and wider integers
+/// otherwise; like byte operands, an INT is reinterpreted as unsigned.
+fn slot_operand(value: &InnerData) -> Result<u32, VmError> {
+    match value {
+        InnerData::INT(a) => Ok(*a as u8 as u32),
+        _ if value.is_integer() => match value.to_i128().map(u32::try_from) {
+            Some(Ok(idx)) => Ok(idx),
+            _ => Err(VmError::InvalidConversion),
+        },
+        _ => Err(VmError::TypeMismatch),
+    }
+}
+
+/// The slot an address handed out by `ADDR` points into.
+fn slot_address(value: &InnerData) -> Result<u32, VmError> {
+    if !value.is_integer() {
+        return Err(VmError::TypeMismatch);
+    }
+
+    match value.to_i128().map(|address| u32::try_from(address.div_euclid(SLOT_SIZE as i128))) {
+        Some(Ok(idx)) if value.to_i128() >= Some(0) => Ok(idx),
+        _ => Err(VmError::MemoryFault),
+    }
+}
+
+/// Function values may only name a `LABEL`, which is where calls and jumps
+/// land. This needs the program, so it is checked in `step` rather than in
+/// `execute`.
+fn check_call_target(instruction: &InstructionSet, program_memory: &ProgramMemory,
+                     stack: &Stack) -> Result<(), VmError> {
+    let label = match instruction {
+        InstructionSet::FUNCREF(label) => byte_operand(label)? as usize,
+        InstructionSet::CALLI(_) => match stack.data().last() {
+            Some(InnerData::FUNC(label)) => *label,
+            _ => return Ok(()),
+        },
+        _ => return Ok(()),
+    };
+
+    match program_memory.program().get(label) {
+        Some(InstructionSet::LABEL) => Ok(()),
+        _ => Err(VmError::InvalidCallTarget),
+    }
+}
+
+/// How many values `instruction` adds to the operand stack without popping any
+/// first, so the depth limit can be checked before it runs. `SPLIT` pushes a
+/// number of pieces known only once it runs, so it checks for itself.
+fn stack_growth(instruction: &InstructionSet) -> usize {
+    match instruction {
+        InstructionSet::LOAD(_, _) | InstructionSet::DUP | InstructionSet::OVER |
+        InstructionSet::PICK(_) | InstructionSet::PUSHF | InstructionSet::FUNCREF(_) |
+        InstructionSet::LOADLOCAL(_) | InstructionSet::NEWARRAY(_) | InstructionSet::NEW(_) |
+        InstructionSet::MAPNEW | InstructionSet::MSIZE | InstructionSet::READ => 1,
+        _ => 0,
+    }
+}
 
+fn allocates(instruction: &InstructionSet) -> bool {
+    matches!(instruction, InstructionSet::NEWARRAY(_) | InstructionSet::NEW(_) |
+                          InstructionSet::MAPNEW | InstructionSet::MAPKEYS)
+}
+
+/// `zero` and `negative` describe the last value an instruction produced (for `CMP`,
+/// the difference of its operands); `carry` and `overflow` are set by arithmetic and
+/// `CMP` at the width of their operands. Other instructions leave the flags alone.
 #[allow(dead_code)]
-struct FlagRegister {
+pub(crate) struct FlagRegister {
     zero: bool,
     negative: bool,
-    unused_3: bool,
-    unused_4: bool,
+    carry: bool,
+    overflow: bool,
     unused_5: bool,
     unused_6: bool,
     unused_7: bool,
@@ -24,22 +122,58 @@ impl FlagRegister {
         FlagRegister {
             zero: false,
             negative: false,
-            unused_3: false,
-            unused_4: false,
+            carry: false,
+            overflow: false,
             unused_5: false,
             unused_6: false,
             unused_7: false,
             unused_8: false,
         }
     }
+
+    pub(crate) fn to_bits(&self) -> u8 {
+        [self.zero, self.negative, self.carry, self.overflow,
+         self.unused_5, self.unused_6, self.unused_7, self.unused_8]
+            .iter()
+            .enumerate()
+            .fold(0, |bits, (i, flag)| bits | ((*flag as u8) << i))
+    }
+
+    pub(crate) fn from_bits(bits: u8) -> FlagRegister {
+        FlagRegister {
+            zero: bits & 1 != 0,
+            negative: bits & (1 << 1) != 0,
+            carry: bits & (1 << 2) != 0,
+            overflow: bits & (1 << 3) != 0,
+            unused_5: bits & (1 << 4) != 0,
+            unused_6: bits & (1 << 5) != 0,
+            unused_7: bits & (1 << 6) != 0,
+            unused_8: bits & (1 << 7) != 0,
+        }
+    }
 }
 
 
 #[allow(dead_code)]
 pub struct Processor {
-    pc: usize,
-    registers: [i8; 10],
-    flag_register: FlagRegister,
+    pub(crate) pc: usize,
+    pub(crate) registers: [i8; 10],
+    pub(crate) flag_register: FlagRegister,
+    pub(crate) fp: usize,
+    /// Number of locals in the current frame; `CALL` return pcs sit above them.
+    pub(crate) locals: usize,
+    fuel: Option<u64>,
+    instruction_costs: HashMap<u8, u64>,
+    limits: Limits,
+    overflow_policy: OverflowPolicy,
+    gc_stress: bool,
+    strict_memory: bool,
+}
+
+impl Default for Processor {
+    fn default() -> Self {
+        Processor::new()
+    }
 }
 
 impl Processor {
@@ -48,231 +182,939 @@ impl Processor {
             pc: 0,
             registers: [0; 10],
             flag_register: FlagRegister::new(),
+            fp: 0,
+            locals: 0,
+            fuel: None,
+            instruction_costs: HashMap::new(),
+            limits: Limits::new(),
+            overflow_policy: OverflowPolicy::Promote,
+            gc_stress: false,
+            strict_memory: false,
+        }
+    }
+
+    pub fn limits(&self) -> &Limits {
+        &self.limits
+    }
+
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.limits = limits;
+    }
+
+    pub fn overflow_policy(&self) -> OverflowPolicy {
+        self.overflow_policy
+    }
+
+    /// Sets the policy used by ADD, SUB, MUL, DIV, MOD and NEG. The `ADDP`-style
+    /// instructions carry their own policy and ignore this one.
+    pub fn set_overflow_policy(&mut self, policy: OverflowPolicy) {
+        self.overflow_policy = policy;
+    }
+
+    pub fn gc_stress(&self) -> bool {
+        self.gc_stress
+    }
+
+    /// With stress enabled the heap is collected before every allocation, so a
+    /// missing root shows up as an invalid reference right away.
+    pub fn set_gc_stress(&mut self, enabled: bool) {
+        self.gc_stress = enabled;
+    }
+
+    pub fn strict_memory(&self) -> bool {
+        self.strict_memory
+    }
+
+    /// With strict memory enabled, reading a data slot that was never written is
+    /// an error instead of reading `INT(0)`.
+    pub fn set_strict_memory(&mut self, enabled: bool) {
+        self.strict_memory = enabled;
+    }
+
+    fn read_slot(&self, data_memory: &DataMemory, idx: u32) -> Result<InnerData, VmError> {
+        match data_memory.get(idx) {
+            Some(value) => Ok(value.clone()),
+            None if self.strict_memory => Err(VmError::UninitializedRead),
+            None => Ok(data_memory.get_var_value(idx).clone()),
+        }
+    }
+
+    /// The slot limit bounds indices rather than counting the slots in use.
+    fn write_slot(&self, data_memory: &mut DataMemory, idx: u32,
+                  value: InnerData) -> Result<(), VmError> {
+        if idx as usize >= self.limits.max_data_slots {
+            return Err(VmError::DataMemoryExhausted);
+        }
+
+        data_memory.set_var_value(idx, value);
+        Ok(())
+    }
+
+    /// Collects the heap before an allocation when stress mode is on, when it
+    /// has doubled since the last collection, or when half of its limit is used.
+    /// Registers only hold `i8` values, so the roots are the two stacks and data
+    /// memory.
+    fn collect_before_allocation(&self, data_memory: &mut DataMemory, stack: &Stack,
+                                 call_stack: &Stack) {
+        let heap = data_memory.heap();
+
+        if self.gc_stress || heap.wants_collection() ||
+           heap.cells() >= self.limits.max_heap_cells / 2 {
+            data_memory.collect_garbage(&[stack.data(), call_stack.data()]);
         }
     }
 
+    /// Clears the pc, registers and flags. Fuel, instruction costs, limits and the
+    /// overflow policy are host configuration and are kept.
+    pub fn reset(&mut self) {
+        self.pc = 0;
+        self.registers = [0; 10];
+        self.flag_register = FlagRegister::new();
+        self.fp = 0;
+        self.locals = 0;
+    }
+
+    pub fn pc(&self) -> usize {
+        self.pc
+    }
+
+    /// Index on the call stack of the current frame's first local.
+    pub fn fp(&self) -> usize {
+        self.fp
+    }
+
+    /// The frames made by `CALLF`, innermost first, followed by the outermost
+    /// frame whose locals sit at the bottom of `call_stack`.
+    pub fn frames<'a>(&self, call_stack: &'a Stack) -> Vec<Frame<'a>> {
+        frames::frames(self.fp, self.locals, call_stack)
+    }
+
+    pub fn fuel(&self) -> Option<u64> {
+        self.fuel
+    }
+
+    pub fn set_fuel(&mut self, fuel: Option<u64>) {
+        self.fuel = fuel;
+    }
+
+    pub fn add_fuel(&mut self, fuel: u64) {
+        self.fuel = Some(self.fuel.unwrap_or(0).saturating_add(fuel));
+    }
+
+    pub fn set_instruction_cost(&mut self, opcode: u8, cost: u64) {
+        self.instruction_costs.insert(opcode, cost);
+    }
+
+    pub fn instruction_cost(&self, instruction: &InstructionSet) -> u64 {
+        match self.instruction_costs.get(&instruction.opcode()) {
+            Some(cost) => *cost,
+            None => 1,
+        }
+    }
+
+    fn consume_fuel(&mut self, instruction: &InstructionSet) -> Result<(), VmError> {
+        if let Some(fuel) = self.fuel {
+            let cost = self.instruction_cost(instruction);
+
+            if cost > fuel {
+                return Err(VmError::OutOfFuel);
+            }
+
+            self.fuel = Some(fuel - cost);
+        }
+
+        Ok(())
+    }
+
+    fn set_value_flags(&mut self, value: &InnerData) {
+        // `false` counts as zero so that `EQU` followed by `JZ` keeps branching on
+        // inequality now that comparisons produce booleans.
+        if let InnerData::BOOL(value) = value {
+            self.flag_register.zero = !value;
+            self.flag_register.negative = false;
+            return;
+        }
+
+        let ordering = value.compare(&InnerData::INT(0));
+
+        self.flag_register.zero = ordering == Some(Ordering::Equal);
+        self.flag_register.negative = ordering == Some(Ordering::Less);
+    }
+
+    /// Sets carry and overflow as if `instruction` had been computed at the wider of
+    /// the operand widths without promotion. Carry is the unsigned carry out (or
+    /// borrow, for subtraction); overflow is signed overflow.
+    fn set_carry_overflow(&mut self, instruction: &InstructionSet, a: &InnerData, b: &InnerData) {
+        let (carry, overflow) = match (a.to_i128(), b.to_i128(), a.bit_width(), b.bit_width()) {
+            (Some(x), Some(y), Some(x_bits), Some(y_bits)) => {
+                let modulus = 1i128 << max(x_bits, y_bits);
+                let (lowest, highest) = (-modulus / 2, modulus / 2 - 1);
+                let (unsigned_x, unsigned_y) = (x.rem_euclid(modulus), y.rem_euclid(modulus));
+
+                match instruction {
+                    InstructionSet::ADD | InstructionSet::ADDP(_) => {
+                        (unsigned_x + unsigned_y >= modulus, !(lowest..=highest).contains(&(x + y)))
+                    },
+                    InstructionSet::SUB | InstructionSet::CMP | InstructionSet::NEG |
+                    InstructionSet::SUBP(_) | InstructionSet::NEGP(_) => {
+                        (unsigned_x < unsigned_y, !(lowest..=highest).contains(&(x - y)))
+                    },
+                    InstructionSet::MUL | InstructionSet::MULP(_) => {
+                        let overflow = match x.checked_mul(y) {
+                            Some(product) => !(lowest..=highest).contains(&product),
+                            None => true,
+                        };
+                        (overflow, overflow)
+                    },
+                    InstructionSet::DIV | InstructionSet::MOD |
+                    InstructionSet::DIVP(_) | InstructionSet::MODP(_) => (false, x == lowest && y == -1),
+                    _ => (false, false),
+                }
+            },
+            _ => (false, false),
+        };
+
+        self.flag_register.carry = carry;
+        self.flag_register.overflow = overflow;
+    }
+
+    fn arithmetic(&self, instruction: &InstructionSet, a: InnerData,
+                  b: InnerData) -> Result<InnerData, VmError> {
+        let (op, policy) = match instruction {
+            InstructionSet::ADD => (ArithOp::Add, None),
+            InstructionSet::SUB | InstructionSet::NEG => (ArithOp::Sub, None),
+            InstructionSet::MUL => (ArithOp::Mul, None),
+            InstructionSet::DIV => (ArithOp::Div, None),
+            InstructionSet::MOD => (ArithOp::Rem, None),
+            InstructionSet::ADDP(policy) => (ArithOp::Add, Some(policy)),
+            InstructionSet::SUBP(policy) | InstructionSet::NEGP(policy) => (ArithOp::Sub, Some(policy)),
+            InstructionSet::MULP(policy) => (ArithOp::Mul, Some(policy)),
+            InstructionSet::DIVP(policy) => (ArithOp::Div, Some(policy)),
+            InstructionSet::MODP(policy) => (ArithOp::Rem, Some(policy)),
+            _ => panic!("Invalid arithmetic instruction!"),
+        };
+
+        let policy = match policy {
+            Some(policy) => match OverflowPolicy::from_code(byte_operand(policy)?) {
+                Some(policy) => policy,
+                None => return Err(VmError::InvalidConversion),
+            },
+            None => self.overflow_policy,
+        };
+
+        overflow::apply(policy, op, a, b, self.limits.max_bigint_bits)
+    }
+
+    /// Stack depths and string repetitions are checked before an instruction
+    /// runs; other strings are short enough to check once they are built.
+    fn check_limits(&self, stack: &Stack) -> Result<(), VmError> {
+        // Strings only reach data memory through the stack, so checking the value
+        // an instruction leaves on top is enough to bound every string.
+        if let Some(InnerData::STR(value)) = stack.data.last() {
+            if value.len() > self.limits.max_string_len {
+                return Err(VmError::StringTooLong);
+            }
+        }
+
+        Ok(())
+    }
+
     pub fn execute(&mut self, instruction: &InstructionSet, data_memory: &mut DataMemory,
                    stack: &mut Stack, call_stack: &mut Stack,
-                   stdout: &mut dyn io::Write) {
+                   stdin: &mut dyn io::BufRead, stdout: &mut dyn io::Write) -> Result<(), VmError> {
+        if stack.data.len().saturating_add(stack_growth(instruction)) > self.limits.max_stack_depth {
+            return Err(VmError::StackOverflow);
+        }
+
+        // Collect while the operands are still on the stack, so they stay rooted.
+        if allocates(instruction) {
+            self.collect_before_allocation(data_memory, stack, call_stack);
+        }
+
         match instruction {
             InstructionSet::LOAD(value, offset) => {
                 if offset == &REGISTER_OFFSET {
-                    if value.get_i8() < 0 || value.get_i8() as usize > self.registers.len() {
-                        panic!("Register index out of bounds!");
+                    let index = byte_operand(value)? as usize;
+                    if index >= self.registers.len() {
+                        return Err(VmError::InvalidRegister);
                     }
 
-                    stack.push(InnerData::INT(self.registers[value.get_u8() as usize]));
-                } else if offset == &STACK_OFFSET || offset == &STACK_OFFSET_STR {
+                    stack.push(InnerData::INT(self.registers[index]));
+                } else if offset == &STACK_OFFSET || offset == &STACK_OFFSET_STR ||
+                          offset == &STACK_OFFSET_BOOL {
                     stack.push(value.clone());
                 } else if offset == &DATA_MEMORY_OFFSET {
-                    stack.push(data_memory.get_var_value(value.get_u8()).clone());
+                    stack.push(self.read_slot(data_memory, slot_operand(value)?)?);
                 } else if offset == &ADDR_OFFSET {
-                    stack.push(InnerData::INT(value.get_u8() as i8 * 8));
+                    stack.push(InnerData::int(slot_operand(value)? as i128 * SLOT_SIZE as i128));
                 } else {
-                    panic!("Invalid offset!");
+                    return Err(VmError::InvalidOffset);
                 }
             },
-            InstructionSet::ADD => {
-                let b = match stack.pop() {
-                    Some(value) => value,
-                    None => panic!("Stack is empty!"),
-                };
-                let a = match stack.pop() {
-                    Some(value) => value,
-                    None => panic!("Stack is empty!"),
-                };
+            InstructionSet::ADD | InstructionSet::ADDP(_) => {
+                let b = pop(stack)?;
+                let a = pop(stack)?;
 
-                stack.push(a + b);
+                self.set_carry_overflow(instruction, &a, &b);
+                stack.push(self.arithmetic(instruction, a, b)?);
             },
-            InstructionSet::SUB => {
-                let b = match stack.pop() {
-                    Some(value) => value,
-                    None => panic!("Stack is empty!"),
-                };
-                let a = match stack.pop() {
-                    Some(value) => value,
-                    None => panic!("Stack is empty!"),
-                };
+            InstructionSet::SUB | InstructionSet::SUBP(_) => {
+                let b = pop(stack)?;
+                let a = pop(stack)?;
 
-                stack.push(a - b);
+                self.set_carry_overflow(instruction, &a, &b);
+                stack.push(self.arithmetic(instruction, a, b)?);
             },
-            InstructionSet::MUL => {
-                let b = match stack.pop() {
-                    Some(value) => value,
-                    None => panic!("Stack is empty!"),
-                };
-                let a = match stack.pop() {
-                    Some(value) => value,
-                    None => panic!("Stack is empty!"),
+            InstructionSet::MUL | InstructionSet::MULP(_) => {
+                let b = pop(stack)?;
+                let a = pop(stack)?;
+
+                // Repetition is checked up front so a huge string is never allocated.
+                let repeated_len = match (&a, &b) {
+                    (InnerData::STR(value), count) | (count, InnerData::STR(value)) if count.is_integer() => {
+                        Some(value.len().saturating_mul(count.repeat_count()))
+                    },
+                    _ => None,
                 };
 
-                stack.push(a * b);
+                if let Some(len) = repeated_len {
+                    if len > self.limits.max_string_len {
+                        return Err(VmError::StringTooLong);
+                    }
+                }
+
+                self.set_carry_overflow(instruction, &a, &b);
+                stack.push(self.arithmetic(instruction, a, b)?);
             },
-            InstructionSet::DIV => {
-                let b = match stack.pop() {
-                    Some(value) => value,
-                    None => panic!("Stack is empty!"),
-                };
-                let a = match stack.pop() {
-                    Some(value) => value,
-                    None => panic!("Stack is empty!"),
-                };
+            InstructionSet::DIV | InstructionSet::DIVP(_) => {
+                let b = pop(stack)?;
+                let a = pop(stack)?;
 
-                stack.push(a / b);
+                self.set_carry_overflow(instruction, &a, &b);
+                stack.push(self.arithmetic(instruction, a, b)?);
             },
             InstructionSet::HALT  => {},
             InstructionSet::SHOW => {
                 if let Some(value) = stack.pop() {
-                    match writeln!(stdout, "{}", value) {
+                    match writeln!(stdout, "{}", data_memory.heap().render(&value)) {
                         Ok(_) => (),
                         Err(error) => panic!("{}", error),
                     }
                 }
             },
-            InstructionSet::MOD => {
-                let b = match stack.pop() {
-                    Some(value) => value,
-                    None => panic!("Stack is empty!"),
-                };
-                let a = match stack.pop() {
-                    Some(value) => value,
-                    None => panic!("Stack is empty!"),
-                };
+            InstructionSet::PRINT => {
+                if let Some(value) = stack.pop() {
+                    match write!(stdout, "{}", data_memory.heap().render(&value)) {
+                        Ok(_) => (),
+                        Err(error) => panic!("{}", error),
+                    }
+                }
+            },
+            InstructionSet::MOD | InstructionSet::MODP(_) => {
+                let b = pop(stack)?;
+                let a = pop(stack)?;
 
-                stack.push(a % b);
+                self.set_carry_overflow(instruction, &a, &b);
+                stack.push(self.arithmetic(instruction, a, b)?);
             }
             InstructionSet::LABEL => {},
             InstructionSet::JMP(label) => {
-                self.pc = label.get_u8() as usize;
+                self.pc = byte_operand(label)? as usize;
             },
             InstructionSet::POP(value, offset) => {
                 if offset == &REGISTER_OFFSET {
-                    if value.get_i8() < 0 || value.get_i8() as usize > self.registers.len() {
-                        panic!("Register index out of bounds!");
+                    let index = byte_operand(value)? as usize;
+                    if index >= self.registers.len() {
+                        return Err(VmError::InvalidRegister);
                     }
 
-                    self.registers[value.get_u8() as usize] = match stack.pop() {
-                        Some(value) => value.get_i8(),
-                        None => panic!("Stack is empty!"),
+                    let data_val = pop(stack)?;
+
+                    // Registers are bytes, so only values that fit an i8 can be stored.
+                    self.registers[index] = match data_val.to_i128().map(i8::try_from) {
+                        Some(Ok(value)) => value,
+                        Some(Err(_)) => return Err(VmError::InvalidConversion),
+                        None => return Err(VmError::TypeMismatch),
                     };
                 } else if offset == &DATA_MEMORY_OFFSET {
-                    let data_val = match stack.pop() {
-                        Some(value) => value,
-                        None => panic!("Stack is empty!"),
-                    };
+                    let data_val = pop(stack)?;
 
-                    data_memory.set_var_value(value.get_u8(), data_val);
+                    self.write_slot(data_memory, slot_operand(value)?, data_val)?;
                 } else if offset == &PTR_OFFSET {
-                    let address = data_memory.get_var_value(value.get_u8()).clone();
+                    let address = self.read_slot(data_memory, slot_operand(value)?)?;
 
-                    let data_val = match stack.pop() {
-                        Some(value) => value,
-                        None => panic!("Stack is empty!"),
-                    };
+                    let data_val = pop(stack)?;
 
-                    data_memory.set_var_value(address.get_u8() / 8, data_val);
+                    self.write_slot(data_memory, slot_address(&address)?, data_val)?;
                 } else {
-                    panic!("Invalid offset!");
+                    return Err(VmError::InvalidOffset);
                 }
             },
             InstructionSet::JZ(label) => {
                 if self.flag_register.zero {
-                    self.pc = label.get_i8() as usize;
+                    self.pc = byte_operand(label)? as usize;
                 }
             },
             InstructionSet::JN(label) => {
                 if self.flag_register.negative {
-                    self.pc = label.get_i8() as usize;
+                    self.pc = byte_operand(label)? as usize;
+                }
+            },
+            InstructionSet::JC(label) => {
+                if self.flag_register.carry {
+                    self.pc = byte_operand(label)? as usize;
+                }
+            },
+            InstructionSet::JO(label) => {
+                if self.flag_register.overflow {
+                    self.pc = byte_operand(label)? as usize;
+                }
+            },
+            InstructionSet::JNC(label) => {
+                if !self.flag_register.carry {
+                    self.pc = byte_operand(label)? as usize;
+                }
+            },
+            InstructionSet::JNO(label) => {
+                if !self.flag_register.overflow {
+                    self.pc = byte_operand(label)? as usize;
+                }
+            },
+            InstructionSet::JNZ(label) => {
+                if !self.flag_register.zero {
+                    self.pc = byte_operand(label)? as usize;
+                }
+            },
+            InstructionSet::JP(label) => {
+                if !self.flag_register.zero && !self.flag_register.negative {
+                    self.pc = byte_operand(label)? as usize;
+                }
+            },
+            InstructionSet::JT(label) | InstructionSet::JF(label) => {
+                let condition = pop(stack)?;
+
+                let truth = match condition {
+                    InnerData::BOOL(value) => value,
+                    _ => return Err(VmError::TypeMismatch),
+                };
+
+                if truth == matches!(instruction, InstructionSet::JT(_)) {
+                    self.pc = byte_operand(label)? as usize;
                 }
             },
             InstructionSet::STARTSTR => {},
             InstructionSet::ENDSTR => {},
             InstructionSet::RET => {
-                if let Some(value) = call_stack.pop() {
-                    self.pc = value.get_u8() as usize;
+                // Only a pc pushed by `CALL` in this frame can be returned to. With
+                // none, `RET` ends the outermost frame as before but is an error
+                // inside a `CALLF` frame.
+                if frames::pending_returns(self.fp, self.locals, call_stack) == 0 {
+                    if self.fp > 0 {
+                        return Err(VmError::InvalidReturn);
+                    }
+                } else if let Some(value) = call_stack.pop() {
+                    self.pc = byte_operand(&value)? as usize;
                 }
             },
             InstructionSet::CALL(label) => {
+                if call_stack.data.len() >= self.limits.max_call_depth {
+                    return Err(VmError::CallStackOverflow);
+                }
+
                 call_stack.push(InnerData::INT(self.pc as i8));
-                self.pc = label.get_u8() as usize;
+                self.pc = byte_operand(label)? as usize;
             },
-            InstructionSet::EQU => {
-                let b = match stack.pop() {
-                    Some(value) => value,
-                    None => panic!("Stack is empty!"),
+            InstructionSet::CALLF(label, argc) => {
+                frames::call(&mut self.fp, &mut self.locals, self.pc, byte_operand(argc)? as usize, stack,
+                             call_stack, self.limits.max_call_depth)?;
+                self.pc = byte_operand(label)? as usize;
+            },
+            InstructionSet::FUNCREF(label) => {
+                stack.push(InnerData::FUNC(byte_operand(label)? as usize));
+            },
+            InstructionSet::CALLI(argc) => {
+                let argc = byte_operand(argc)? as usize;
+                if argc >= stack.data.len() {
+                    return Err(VmError::StackUnderflow);
+                }
+
+                let label = match pop(stack)? {
+                    InnerData::FUNC(label) => label,
+                    value => {
+                        stack.push(value);
+                        return Err(VmError::TypeMismatch);
+                    },
                 };
-                let a = match stack.pop() {
-                    Some(value) => value,
-                    None => panic!("Stack is empty!"),
+
+                frames::call(&mut self.fp, &mut self.locals, self.pc, argc, stack, call_stack,
+                             self.limits.max_call_depth)?;
+                self.pc = label;
+            },
+            InstructionSet::RETF(count) => {
+                self.pc = frames::ret(&mut self.fp, &mut self.locals, byte_operand(count)? as usize, stack,
+                                      call_stack)?;
+            },
+            InstructionSet::LOADLOCAL(n) => {
+                let value = match frames::load_local(self.fp, self.locals, slot_operand(n)? as usize,
+                                                     call_stack) {
+                    Some(value) => value.clone(),
+                    None if self.strict_memory => return Err(VmError::UninitializedRead),
+                    None => InnerData::INT(0),
                 };
 
-                match (a, b) {
-                    (InnerData::INT(a), InnerData::INT(b)) => {
-                        stack.push(InnerData::INT(if a == b { 1 } else { 0 }));
+                stack.push(value);
+            },
+            InstructionSet::STORELOCAL(n) => {
+                let value = pop(stack)?;
+
+                frames::store_local(self.fp, &mut self.locals, slot_operand(n)? as usize, value,
+                                    call_stack, self.limits.max_call_depth)?;
+            },
+            InstructionSet::EQU => {
+                let b = pop(stack)?;
+                let a = pop(stack)?;
+
+                stack.push(InnerData::BOOL(a.compare(&b) == Some(Ordering::Equal)));
+            },
+            InstructionSet::NE => {
+                let b = pop(stack)?;
+                let a = pop(stack)?;
+
+                stack.push(InnerData::BOOL(a.compare(&b) != Some(Ordering::Equal)));
+            },
+            InstructionSet::LT | InstructionSet::LE | InstructionSet::GT | InstructionSet::GE => {
+                let b = pop(stack)?;
+                let a = pop(stack)?;
+
+                let result = match a.compare(&b) {
+                    Some(ordering) => match instruction {
+                        InstructionSet::LT => ordering == Ordering::Less,
+                        InstructionSet::LE => ordering != Ordering::Greater,
+                        InstructionSet::GT => ordering == Ordering::Greater,
+                        _ => ordering != Ordering::Less,
                     },
-                    (InnerData::STR(a), InnerData::STR(b)) => {
-                        stack.push(InnerData::INT(if a == b { 1 } else { 0 }));
+                    // Numbers only fail to compare when one is NaN, which is
+                    // neither less than nor greater than anything.
+                    None if a.is_numeric() && b.is_numeric() => false,
+                    None => return Err(VmError::TypeMismatch),
+                };
+
+                stack.push(InnerData::BOOL(result));
+            },
+            InstructionSet::AND | InstructionSet::OR | InstructionSet::XOR |
+            InstructionSet::SHL | InstructionSet::SHR | InstructionSet::USHR => {
+                let b = pop(stack)?;
+                let a = pop(stack)?;
+
+                let logical = matches!((&a, &b), (InnerData::BOOL(_), InnerData::BOOL(_))) &&
+                    matches!(instruction, InstructionSet::AND | InstructionSet::OR | InstructionSet::XOR);
+
+                if !logical && (a.bit_width().is_none() || b.bit_width().is_none()) {
+                    return Err(VmError::TypeMismatch);
+                }
+
+                self.set_carry_overflow(instruction, &a, &b);
+
+                stack.push(match instruction {
+                    InstructionSet::AND => a & b,
+                    InstructionSet::OR => a | b,
+                    InstructionSet::XOR => a ^ b,
+                    InstructionSet::SHL => a << b,
+                    InstructionSet::SHR => a >> b,
+                    _ => a.ushr(b),
+                });
+            },
+            InstructionSet::NOT => {
+                let value = pop(stack)?;
+
+                if value.bit_width().is_none() && !matches!(value, InnerData::BOOL(_)) {
+                    return Err(VmError::TypeMismatch);
+                }
+
+                self.set_carry_overflow(instruction, &value, &value);
+                stack.push(!value);
+            },
+            InstructionSet::DUP | InstructionSet::OVER | InstructionSet::PICK(_) => {
+                let depth = match instruction {
+                    InstructionSet::DUP => 0,
+                    InstructionSet::OVER => 1,
+                    InstructionSet::PICK(depth) => byte_operand(depth)? as usize,
+                    _ => unreachable!(),
+                };
+
+                let value = match stack.peek(depth) {
+                    Some(value) => value.clone(),
+                    None => return Err(VmError::StackUnderflow),
+                };
+
+                stack.push(value);
+            },
+            InstructionSet::SWAP | InstructionSet::ROT | InstructionSet::ROLL(_) => {
+                let depth = match instruction {
+                    InstructionSet::SWAP => 1,
+                    InstructionSet::ROT => 2,
+                    InstructionSet::ROLL(depth) => byte_operand(depth)? as usize,
+                    _ => unreachable!(),
+                };
+
+                if !stack.roll(depth) {
+                    return Err(VmError::StackUnderflow);
+                }
+            },
+            InstructionSet::DROP => {
+                if stack.pop().is_none() {
+                    return Err(VmError::StackUnderflow);
+                }
+            },
+            InstructionSet::CMP => {
+                let b = pop(stack)?;
+                let a = pop(stack)?;
+
+                let ordering = match a.compare(&b) {
+                    Some(ordering) => Some(ordering),
+                    None if a.is_numeric() && b.is_numeric() => None,
+                    None => return Err(VmError::TypeMismatch),
+                };
+
+                self.flag_register.zero = ordering == Some(Ordering::Equal);
+                self.flag_register.negative = ordering == Some(Ordering::Less);
+                self.set_carry_overflow(instruction, &a, &b);
+            },
+            InstructionSet::PUSHF => {
+                stack.push(InnerData::INT(self.flag_register.to_bits() as i8));
+            },
+            InstructionSet::POPF => {
+                let value = pop(stack)?;
+
+                match value.to_i128() {
+                    Some(bits) => self.flag_register = FlagRegister::from_bits(bits as u8),
+                    None => return Err(VmError::TypeMismatch),
+                }
+            },
+            InstructionSet::NEG | InstructionSet::NEGP(_) => {
+                let value = pop(stack)?;
+
+                if !value.is_numeric() {
+                    return Err(VmError::TypeMismatch);
+                }
+
+                self.set_carry_overflow(instruction, &InnerData::INT(0), &value);
+
+                match value {
+                    // Floats negate directly so that the sign of zero flips too.
+                    InnerData::FLOAT(value) => stack.push(InnerData::FLOAT(-value)),
+                    // Negating is subtracting from a zero of the same type, so -INT(-128)
+                    // overflows and follows the overflow policy.
+                    value => {
+                        let zero = match value.bit_width() {
+                            Some(bits) => InnerData::from_i128(0, !value.is_unsigned(), bits).unwrap(),
+                            None => InnerData::INT(0),
+                        };
+
+                        stack.push(self.arithmetic(instruction, zero, value)?);
                     },
+                }
+            },
+            InstructionSet::ITOF => {
+                let value = pop(stack)?;
+
+                if !value.is_integer() {
+                    return Err(VmError::TypeMismatch);
+                }
+
+                stack.push(InnerData::FLOAT(value.to_f64().unwrap()));
+            },
+            InstructionSet::FTOI(mode) => {
+                let value = match pop(stack)? {
+                    InnerData::FLOAT(value) => value,
+                    _ => return Err(VmError::TypeMismatch),
+                };
+
+                let rounded = match byte_operand(mode)? {
+                    ROUND_TRUNC => value.trunc(),
+                    ROUND_NEAREST => value.round(),
+                    ROUND_FLOOR => value.floor(),
+                    ROUND_CEIL => value.ceil(),
+                    ROUND_HALF_EVEN => value.round_ties_even(),
+                    _ => return Err(VmError::InvalidConversion),
+                };
+
+                if !rounded.is_finite() {
+                    return Err(VmError::InvalidConversion);
+                }
+
+                // A whole float prints exactly with no decimals, however large.
+                let integer = BigInt::parse(&format!("{:.0}", rounded)).unwrap();
+                stack.push(InnerData::from_integer(integer));
+            },
+            InstructionSet::WIDEN(target) | InstructionSet::NARROW(target) |
+            InstructionSet::NARROWC(target) => {
+                let value = pop(stack)?;
+
+                let target = int_type_operand(target)?;
+
+                stack.push(match instruction {
+                    InstructionSet::WIDEN(_) => convert::widen(value, target)?,
+                    InstructionSet::NARROW(_) => convert::narrow_wrapping(value, target)?,
+                    _ => convert::narrow_checked(value, target)?,
+                });
+            },
+            InstructionSet::SEXT(bits) | InstructionSet::ZEXT(bits) => {
+                let value = pop(stack)?;
+
+                let signed = matches!(instruction, InstructionSet::SEXT(_));
+                stack.push(convert::extend(value, byte_operand(bits)?, signed)?);
+            },
+            InstructionSet::PARSE => {
+                let value = pop(stack)?;
+
+                stack.push(convert::parse(value)?);
+            },
+            InstructionSet::TOSTR => {
+                let value = pop(stack)?;
+
+                stack.push(InnerData::STR(data_memory.heap().render(&value)));
+            },
+            InstructionSet::LEN | InstructionSet::UPPER | InstructionSet::LOWER |
+            InstructionSet::TRIM | InstructionSet::ORD | InstructionSet::CHR => {
+                let value = pop(stack)?;
+
+                stack.push(match instruction {
+                    InstructionSet::LEN => strings::len(value)?,
+                    InstructionSet::UPPER => strings::upper(value)?,
+                    InstructionSet::LOWER => strings::lower(value)?,
+                    InstructionSet::TRIM => strings::trim(value)?,
+                    InstructionSet::ORD => strings::ord(value)?,
+                    _ => strings::chr(value)?,
+                });
+            },
+            InstructionSet::CHARAT | InstructionSet::FIND | InstructionSet::SPLIT => {
+                let b = pop(stack)?;
+                let a = pop(stack)?;
+
+                match instruction {
+                    InstructionSet::CHARAT => stack.push(strings::char_at(a, b)?),
+                    InstructionSet::FIND => stack.push(strings::find(a, b)?),
                     _ => {
-                        stack.push(InnerData::INT(0));
-                    }
+                        // The pieces go on the stack in order, followed by their count.
+                        let pieces = strings::split(a, b)?;
+                        let count = pieces.len();
+
+                        if stack.data.len().saturating_add(count + 1) > self.limits.max_stack_depth {
+                            return Err(VmError::StackOverflow);
+                        }
+
+                        for piece in pieces {
+                            stack.push(piece);
+                        }
+                        stack.push(InnerData::int(count as i128));
+                    },
                 }
             },
-            InstructionSet::NEG => {
-                let value = match stack.pop() {
-                    Some(value) => value,
-                    None => panic!("Stack is empty!"),
+            InstructionSet::SUBSTR => {
+                let count = pop(stack)?;
+                let start = pop(stack)?;
+                let value = pop(stack)?;
+
+                stack.push(strings::substr(value, start, count)?);
+            },
+            InstructionSet::FORMAT(count) => {
+                let count = byte_operand(count)? as usize;
+                if stack.data().len() <= count {
+                    return Err(VmError::StackUnderflow);
+                }
+
+                // The format string lies below its values, which were pushed in order.
+                let mut values = Vec::with_capacity(count);
+                for _ in 0..count {
+                    values.push(stack.pop().unwrap());
+                }
+                values.reverse();
+
+                let template = match stack.pop().unwrap() {
+                    InnerData::STR(template) => template,
+                    _ => return Err(VmError::TypeMismatch),
                 };
 
-                match value {
-                    InnerData::INT(value) => stack.push(InnerData::INT(-value)),
-                    _ => panic!("Invalid type!"),
+                stack.push(InnerData::STR(format::format(&template, &values, data_memory.heap(),
+                                                         self.limits.max_string_len)?));
+            },
+            InstructionSet::NEWARRAY(length) => {
+                let array = arrays::new_array(data_memory.heap_mut(), length,
+                                              self.limits.max_heap_cells)?;
+                stack.push(array);
+            },
+            InstructionSet::ALOAD => {
+                let index = pop(stack)?;
+                let array = pop(stack)?;
+
+                stack.push(arrays::load(data_memory.heap(), &array, &index)?);
+            },
+            InstructionSet::ASTORE => {
+                let value = pop(stack)?;
+                let index = pop(stack)?;
+                let array = pop(stack)?;
+
+                arrays::store(data_memory.heap_mut(), &array, &index, value)?;
+            },
+            InstructionSet::ALEN => {
+                let array = pop(stack)?;
+
+                stack.push(arrays::len(data_memory.heap(), &array)?);
+            },
+            InstructionSet::APPEND => {
+                let value = pop(stack)?;
+                let array = pop(stack)?;
+
+                arrays::append(data_memory.heap_mut(), &array, value, self.limits.max_heap_cells)?;
+            },
+            InstructionSet::NEW(id) => {
+                let object = structs::new_struct(data_memory.heap_mut(), id,
+                                                 self.limits.max_heap_cells)?;
+                stack.push(object);
+            },
+            InstructionSet::GETFIELD(field) => {
+                let object = pop(stack)?;
+
+                stack.push(structs::get_field(data_memory.heap(), &object, field)?);
+            },
+            InstructionSet::SETFIELD(field) => {
+                let value = pop(stack)?;
+                let object = pop(stack)?;
+
+                structs::set_field(data_memory.heap_mut(), &object, field, value)?;
+            },
+            InstructionSet::MAPNEW => {
+                stack.push(maps::new_map(data_memory.heap_mut(), self.limits.max_heap_cells)?);
+            },
+            InstructionSet::MAPGET | InstructionSet::MAPHAS | InstructionSet::MAPDEL => {
+                let key = pop(stack)?;
+                let map = pop(stack)?;
+
+                match instruction {
+                    InstructionSet::MAPGET => stack.push(maps::get(data_memory.heap(), &map, &key)?),
+                    InstructionSet::MAPHAS => stack.push(maps::has(data_memory.heap(), &map, &key)?),
+                    _ => maps::delete(data_memory.heap_mut(), &map, &key)?,
                 }
             },
-            InstructionSet::DEREF => {
-                let value = match stack.pop() {
-                    Some(value) => value,
-                    None => panic!("Stack is empty!"),
+            InstructionSet::MAPSET => {
+                let value = pop(stack)?;
+                let key = pop(stack)?;
+                let map = pop(stack)?;
+
+                maps::set(data_memory.heap_mut(), &map, key, value, self.limits.max_heap_cells)?;
+            },
+            InstructionSet::MAPKEYS => {
+                let map = pop(stack)?;
+
+                stack.push(maps::keys(data_memory.heap_mut(), &map, self.limits.max_heap_cells)?);
+            },
+            InstructionSet::MLOAD(width) | InstructionSet::MSTORE(width) => {
+                // BIGINT has no fixed width to load or store.
+                let target = int_type_operand(width)?;
+                let (signed, bits) = match target.kind() {
+                    Some(kind) => kind,
+                    None => return Err(VmError::InvalidConversion),
                 };
 
-                match value {
-                    InnerData::INT(value) => stack.push(
-                        InnerData::INT(data_memory.get_var_value(value as u8 / 8).get_i8())
-                    ),
-                    _ => panic!("Invalid type!"),
+                if let InstructionSet::MSTORE(_) = instruction {
+                    let value = pop(stack)?;
+                    let address = pop(stack)?;
+
+                    let value = convert::narrow_wrapping(value, target)?;
+                    linear::store(data_memory.linear_memory_mut(), &address, &value, bits)?;
+                } else {
+                    let address = pop(stack)?;
+
+                    stack.push(linear::load(data_memory.linear_memory(), &address, signed, bits)?);
                 }
             },
-        }
+            InstructionSet::MSIZE => {
+                stack.push(InnerData::int(data_memory.linear_memory().size() as i128));
+            },
+            InstructionSet::READ => {
+                // Read at most one byte past the limit so an oversized line is
+                // rejected by the limit check instead of being buffered in full.
+                let max_len = (self.limits.max_string_len as u64).saturating_add(1);
+                let mut line = Vec::new();
+
+                if let Err(error) = stdin.take(max_len).read_until(b'\n', &mut line) {
+                    panic!("{}", error);
+                }
+
+                if line.ends_with(b"\n") {
+                    line.pop();
 
-        if stack.data.len() > 0 {
-            match stack.top() {
-                InnerData::INT(top_val) => {
-                    if stack.data().len() > 0 && *top_val == 0 {
-                        self.flag_register.zero = true;
-                    } else if stack.data.len() > 0 && *top_val < 0 {
-                        self.flag_register.negative = true;
-                    } else {
-                        self.flag_register.zero = false;
-                        self.flag_register.negative = false;
+                    if line.ends_with(b"\r") {
+                        line.pop();
                     }
-                },
-                _ => {},
-            }
+                }
+
+                // Checked before decoding, as the cut may fall inside a character.
+                if line.len() > self.limits.max_string_len {
+                    return Err(VmError::StringTooLong);
+                }
+
+                match String::from_utf8(line) {
+                    Ok(line) => stack.push(InnerData::STR(line)),
+                    Err(_) => return Err(VmError::InvalidConversion),
+                }
+            },
+            InstructionSet::DEREF => {
+                let value = pop(stack)?;
+
+                stack.push(self.read_slot(data_memory, slot_address(&value)?)?);
+            },
+        }
+
+        match instruction {
+            InstructionSet::LOAD(_, _) | InstructionSet::ADD | InstructionSet::SUB |
+            InstructionSet::MUL | InstructionSet::DIV | InstructionSet::MOD |
+            InstructionSet::EQU | InstructionSet::NEG | InstructionSet::DEREF |
+            InstructionSet::READ | InstructionSet::LT | InstructionSet::LE |
+            InstructionSet::GT | InstructionSet::GE | InstructionSet::NE |
+            InstructionSet::AND | InstructionSet::OR | InstructionSet::XOR |
+            InstructionSet::NOT | InstructionSet::SHL | InstructionSet::SHR |
+            InstructionSet::USHR | InstructionSet::ITOF | InstructionSet::FTOI(_) |
+            InstructionSet::ADDP(_) | InstructionSet::SUBP(_) | InstructionSet::MULP(_) |
+            InstructionSet::DIVP(_) | InstructionSet::MODP(_) | InstructionSet::NEGP(_) |
+            InstructionSet::WIDEN(_) | InstructionSet::NARROW(_) | InstructionSet::NARROWC(_) |
+            InstructionSet::SEXT(_) | InstructionSet::ZEXT(_) | InstructionSet::PARSE |
+            InstructionSet::LEN | InstructionSet::FIND | InstructionSet::SPLIT |
+            InstructionSet::ORD | InstructionSet::ALOAD | InstructionSet::ALEN |
+            InstructionSet::GETFIELD(_) | InstructionSet::MAPGET | InstructionSet::MAPHAS |
+            InstructionSet::MLOAD(_) | InstructionSet::LOADLOCAL(_) => {
+                self.set_value_flags(stack.top());
+            },
+            _ => {},
         }
+
+        self.check_limits(stack)
     }
 
-    pub fn execute_program(&mut self, program_memory: ProgramMemory, data_memory: &mut DataMemory,
-                           stack: &mut Stack, call_stack: &mut Stack,
-                           stdout: &mut dyn io::Write) {
-        loop {
-            let instruction = program_memory.get_instruction(self.pc);
-            self.execute(instruction, data_memory, stack, call_stack, stdout);
+    /// Executes the instruction at `pc` and advances past it. Returns whether the
+    /// instruction was `HALT`.
+    pub fn step(&mut self, program_memory: &ProgramMemory, data_memory: &mut DataMemory,
+                stack: &mut Stack, call_stack: &mut Stack,
+                stdin: &mut dyn io::BufRead, stdout: &mut dyn io::Write) -> Result<bool, VmError> {
+        let instruction = program_memory.get_instruction(self.pc);
 
-            self.pc += 1;
+        // Fuel is checked before executing so that the processor stops on the
+        // instruction it could not afford and resumes from it after a refill.
+        self.consume_fuel(instruction)?;
+        check_call_target(instruction, program_memory, stack)?;
+        self.execute(instruction, data_memory, stack, call_stack, stdin, stdout)?;
 
-            if *instruction == InstructionSet::HALT {
-                break;
-            }
-        }   
+        self.pc += 1;
+
+        Ok(*instruction == InstructionSet::HALT)
+    }
+
+    pub fn execute_program(&mut self, program_memory: &ProgramMemory, data_memory: &mut DataMemory,
+                           stack: &mut Stack, call_stack: &mut Stack,
+                           stdin: &mut dyn io::BufRead, stdout: &mut dyn io::Write) -> Result<(), VmError> {
+        while !self.step(program_memory, data_memory, stack, call_stack, stdin, stdout)? {}
+
+        Ok(())
     }
 }
\ No newline at end of file
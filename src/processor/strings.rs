@@ -0,0 +1,104 @@
+use crate::memory::InnerData;
+
+use super::error::VmError;
+
+// Strings are indexed and measured in chars (Unicode scalar values), never in
+// bytes, so no operation can split a multi-byte character.
+
+fn text(value: InnerData) -> Result<String, VmError> {
+    match value {
+        InnerData::STR(text) => Ok(text),
+        _ => Err(VmError::TypeMismatch),
+    }
+}
+
+pub(super) fn index(value: &InnerData) -> Result<usize, VmError> {
+    match value.to_i128() {
+        Some(index) => usize::try_from(index).map_err(|_| VmError::IndexOutOfBounds),
+        None if value.is_integer() => Err(VmError::IndexOutOfBounds),
+        None => Err(VmError::TypeMismatch),
+    }
+}
+
+pub(super) fn len(value: InnerData) -> Result<InnerData, VmError> {
+    Ok(InnerData::int(text(value)?.chars().count() as i128))
+}
+
+pub(super) fn char_at(value: InnerData, at: InnerData) -> Result<InnerData, VmError> {
+    let at = index(&at)?;
+
+    match text(value)?.chars().nth(at) {
+        Some(ch) => Ok(InnerData::STR(ch.to_string())),
+        None => Err(VmError::IndexOutOfBounds),
+    }
+}
+
+/// The `count` chars starting at char `start`; both ends must lie in the string.
+pub(super) fn substr(value: InnerData, start: InnerData,
+                     count: InnerData) -> Result<InnerData, VmError> {
+    let (start, count) = (index(&start)?, index(&count)?);
+    let text = text(value)?;
+
+    match start.checked_add(count) {
+        Some(end) if end <= text.chars().count() => {
+            Ok(InnerData::STR(text.chars().skip(start).take(count).collect()))
+        },
+        _ => Err(VmError::IndexOutOfBounds),
+    }
+}
+
+/// Char index of the first occurrence of `needle`, or -1.
+pub(super) fn find(value: InnerData, needle: InnerData) -> Result<InnerData, VmError> {
+    let (text, needle) = (text(value)?, text(needle)?);
+
+    match text.find(&needle) {
+        Some(byte_index) => Ok(InnerData::int(text[..byte_index].chars().count() as i128)),
+        None => Ok(InnerData::INT(-1)),
+    }
+}
+
+/// The pieces between separators, in order. An empty separator splits into chars.
+pub(super) fn split(value: InnerData, separator: InnerData) -> Result<Vec<InnerData>, VmError> {
+    let (text, separator) = (text(value)?, text(separator)?);
+
+    if separator.is_empty() {
+        return Ok(text.chars().map(|ch| InnerData::STR(ch.to_string())).collect());
+    }
+
+    Ok(text.split(&separator).map(|piece| InnerData::STR(piece.to_string())).collect())
+}
+
+pub(super) fn upper(value: InnerData) -> Result<InnerData, VmError> {
+    Ok(InnerData::STR(text(value)?.to_uppercase()))
+}
+
+pub(super) fn lower(value: InnerData) -> Result<InnerData, VmError> {
+    Ok(InnerData::STR(text(value)?.to_lowercase()))
+}
+
+pub(super) fn trim(value: InnerData) -> Result<InnerData, VmError> {
+    Ok(InnerData::STR(text(value)?.trim().to_string()))
+}
+
+/// Code point of a one-char string.
+pub(super) fn ord(value: InnerData) -> Result<InnerData, VmError> {
+    let text = text(value)?;
+    let mut chars = text.chars();
+
+    match (chars.next(), chars.next()) {
+        (Some(ch), None) => Ok(InnerData::int(ch as i128)),
+        _ => Err(VmError::InvalidConversion),
+    }
+}
+
+pub(super) fn chr(value: InnerData) -> Result<InnerData, VmError> {
+    let code = match value.to_i128() {
+        Some(code) => code,
+        None => return Err(VmError::TypeMismatch),
+    };
+
+    match u32::try_from(code).ok().and_then(char::from_u32) {
+        Some(ch) => Ok(InnerData::STR(ch.to_string())),
+        None => Err(VmError::InvalidConversion),
+    }
+}
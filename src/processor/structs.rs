@@ -0,0 +1,60 @@
+use crate::memory::{Heap, HeapObject, InnerData};
+
+use super::arrays::handle;
+use super::error::VmError;
+use super::strings::index;
+
+/// Allocates an instance of struct type `id` with every field `INT(0)`. An id
+/// missing from the type section is an invalid reference.
+pub(super) fn new_struct(heap: &mut Heap, id: &InnerData,
+                         max_cells: usize) -> Result<InnerData, VmError> {
+    let id = index(id)?;
+    let count = match heap.struct_type(id) {
+        Some(struct_type) => struct_type.fields.len(),
+        None => return Err(VmError::InvalidReference),
+    };
+
+    if heap.cells().saturating_add(count) > max_cells {
+        return Err(VmError::HeapExhausted);
+    }
+
+    Ok(heap.alloc(HeapObject::Struct(id, vec![InnerData::INT(0); count])))
+}
+
+/// Index of the field named by `field` in struct `object`: either its position
+/// or, as a string, its name.
+fn field_index(heap: &Heap, object: &InnerData, field: &InnerData) -> Result<usize, VmError> {
+    let (id, count) = match heap.get(handle(object)?) {
+        Some(HeapObject::Struct(id, values)) => (*id, values.len()),
+        Some(_) => return Err(VmError::TypeMismatch),
+        None => return Err(VmError::InvalidReference),
+    };
+
+    let field = match field {
+        InnerData::STR(name) => heap.struct_type(id).and_then(|struct_type| struct_type.field_index(name)),
+        _ => Some(index(field)?),
+    };
+
+    match field {
+        Some(field) if field < count => Ok(field),
+        _ => Err(VmError::IndexOutOfBounds),
+    }
+}
+
+pub(super) fn get_field(heap: &Heap, object: &InnerData,
+                        field: &InnerData) -> Result<InnerData, VmError> {
+    let field = field_index(heap, object, field)?;
+
+    match heap.get(handle(object)?) {
+        Some(HeapObject::Struct(_, values)) => Ok(values[field].clone()),
+        _ => unreachable!(),
+    }
+}
+
+pub(super) fn set_field(heap: &mut Heap, object: &InnerData, field: &InnerData,
+                        value: InnerData) -> Result<(), VmError> {
+    let field = field_index(heap, object, field)?;
+
+    heap.set_element(handle(object)?, field, value);
+    Ok(())
+}
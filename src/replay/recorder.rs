@@ -0,0 +1,522 @@
+use std::fs;
+use std::io;
+
+use crate::memory::{HeapWrite, InnerData, MapKey, Stack};
+use crate::processor::VmError;
+use crate::processor::processor::FlagRegister;
+use crate::snapshot::SnapshotError;
+use crate::snapshot::codec::{Reader, write_data, write_object};
+use crate::vm::Vm;
+
+const MAGIC: &[u8; 4] = b"YREC";
+const VERSION: u16 = 1;
+
+const WRITE_ALLOC: u8 = 0;
+const WRITE_FREE: u8 = 1;
+const WRITE_SET: u8 = 2;
+const WRITE_PUSH: u8 = 3;
+const WRITE_MAP_ENTRY: u8 = 4;
+
+/// Everything needed to undo one executed instruction, plus the input it consumed
+/// and the output it produced.
+#[derive(Debug, PartialEq)]
+pub struct StepDelta {
+    pub pc: usize,
+    registers: [i8; 10],
+    flags: u8,
+    fp: usize,
+    locals: usize,
+    fuel: Option<u64>,
+    stack_len: usize,
+    stack_tail: Vec<InnerData>,
+    call_stack_len: usize,
+    call_stack_tail: Vec<InnerData>,
+    pub writes: Vec<(u32, Option<InnerData>)>,
+    heap_writes: Vec<HeapWrite>,
+    memory_writes: Vec<(usize, Vec<u8>)>,
+    pub input: Vec<u8>,
+    pub output: Vec<u8>,
+}
+
+impl StepDelta {
+    pub fn wrote(&self, idx: u32) -> bool {
+        self.writes.iter().any(|(written, _)| *written == idx)
+    }
+
+    fn write(&self, buffer: &mut Vec<u8>) {
+        buffer.extend_from_slice(&(self.pc as u64).to_le_bytes());
+        for register in self.registers {
+            buffer.push(register as u8);
+        }
+        buffer.push(self.flags);
+        buffer.extend_from_slice(&(self.fp as u64).to_le_bytes());
+        buffer.extend_from_slice(&(self.locals as u64).to_le_bytes());
+        write_option(buffer, &self.fuel, |buffer, fuel| buffer.extend_from_slice(&fuel.to_le_bytes()));
+
+        buffer.extend_from_slice(&(self.stack_len as u64).to_le_bytes());
+        write_values(buffer, &self.stack_tail);
+        buffer.extend_from_slice(&(self.call_stack_len as u64).to_le_bytes());
+        write_values(buffer, &self.call_stack_tail);
+
+        buffer.extend_from_slice(&(self.writes.len() as u32).to_le_bytes());
+        for (idx, previous) in &self.writes {
+            buffer.extend_from_slice(&idx.to_le_bytes());
+            write_option(buffer, previous, write_data);
+        }
+
+        buffer.extend_from_slice(&(self.heap_writes.len() as u32).to_le_bytes());
+        for write in &self.heap_writes {
+            write_heap_write(buffer, write);
+        }
+
+        buffer.extend_from_slice(&(self.memory_writes.len() as u32).to_le_bytes());
+        for (address, previous) in &self.memory_writes {
+            buffer.extend_from_slice(&(*address as u64).to_le_bytes());
+            write_bytes(buffer, previous);
+        }
+
+        write_bytes(buffer, &self.input);
+        write_bytes(buffer, &self.output);
+    }
+
+    fn read(reader: &mut Reader) -> Result<StepDelta, SnapshotError> {
+        let pc = reader.read_u64()? as usize;
+
+        let mut registers = [0; 10];
+        for register in registers.iter_mut() {
+            *register = reader.read_u8()? as i8;
+        }
+
+        let flags = reader.read_u8()?;
+        let fp = reader.read_u64()? as usize;
+        let locals = reader.read_u64()? as usize;
+        let fuel = read_option(reader, Reader::read_u64)?;
+
+        let stack_len = reader.read_u64()? as usize;
+        let stack_tail = read_values(reader)?;
+        let call_stack_len = reader.read_u64()? as usize;
+        let call_stack_tail = read_values(reader)?;
+
+        let mut writes = Vec::new();
+        for _ in 0..reader.read_u32()? {
+            let idx = reader.read_u32()?;
+            writes.push((idx, read_option(reader, Reader::read_data)?));
+        }
+
+        let mut heap_writes = Vec::new();
+        for _ in 0..reader.read_u32()? {
+            heap_writes.push(read_heap_write(reader)?);
+        }
+
+        let mut memory_writes = Vec::new();
+        for _ in 0..reader.read_u32()? {
+            let address = reader.read_u64()? as usize;
+            memory_writes.push((address, read_bytes(reader)?));
+        }
+
+        let input = read_bytes(reader)?;
+        let output = read_bytes(reader)?;
+
+        Ok(StepDelta {
+            pc,
+            registers,
+            flags,
+            fp,
+            locals,
+            fuel,
+            stack_len,
+            stack_tail,
+            call_stack_len,
+            call_stack_tail,
+            writes,
+            heap_writes,
+            memory_writes,
+            input,
+            output,
+        })
+    }
+}
+
+fn write_option<T>(buffer: &mut Vec<u8>, value: &Option<T>, write: impl Fn(&mut Vec<u8>, &T)) {
+    match value {
+        Some(value) => {
+            buffer.push(1);
+            write(buffer, value);
+        },
+        None => buffer.push(0),
+    }
+}
+
+fn read_option<'a, T>(reader: &mut Reader<'a>,
+                      read: impl Fn(&mut Reader<'a>) -> Result<T, SnapshotError>)
+                      -> Result<Option<T>, SnapshotError> {
+    match reader.read_u8()? {
+        0 => Ok(None),
+        1 => read(reader).map(Some),
+        tag => Err(SnapshotError::InvalidTag(tag)),
+    }
+}
+
+fn write_values(buffer: &mut Vec<u8>, values: &[InnerData]) {
+    buffer.extend_from_slice(&(values.len() as u32).to_le_bytes());
+    for value in values {
+        write_data(buffer, value);
+    }
+}
+
+fn read_values(reader: &mut Reader) -> Result<Vec<InnerData>, SnapshotError> {
+    let mut values = Vec::new();
+    for _ in 0..reader.read_u32()? {
+        values.push(reader.read_data()?);
+    }
+
+    Ok(values)
+}
+
+fn write_bytes(buffer: &mut Vec<u8>, bytes: &[u8]) {
+    buffer.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+    buffer.extend_from_slice(bytes);
+}
+
+fn read_bytes(reader: &mut Reader) -> Result<Vec<u8>, SnapshotError> {
+    let len = reader.read_u32()? as usize;
+    Ok(reader.read_bytes(len)?.to_vec())
+}
+
+fn write_heap_write(buffer: &mut Vec<u8>, write: &HeapWrite) {
+    match write {
+        HeapWrite::Alloc(handle) => {
+            buffer.push(WRITE_ALLOC);
+            buffer.extend_from_slice(&(*handle as u64).to_le_bytes());
+        },
+        HeapWrite::Free(handle, object) => {
+            buffer.push(WRITE_FREE);
+            buffer.extend_from_slice(&(*handle as u64).to_le_bytes());
+            write_object(buffer, object);
+        },
+        HeapWrite::Set(handle, index, previous) => {
+            buffer.push(WRITE_SET);
+            buffer.extend_from_slice(&(*handle as u64).to_le_bytes());
+            buffer.extend_from_slice(&(*index as u64).to_le_bytes());
+            write_data(buffer, previous);
+        },
+        HeapWrite::Push(handle) => {
+            buffer.push(WRITE_PUSH);
+            buffer.extend_from_slice(&(*handle as u64).to_le_bytes());
+        },
+        HeapWrite::MapEntry(handle, key, previous) => {
+            buffer.push(WRITE_MAP_ENTRY);
+            buffer.extend_from_slice(&(*handle as u64).to_le_bytes());
+            match key {
+                MapKey::Int(value) => write_data(buffer, &InnerData::BIGINT(value.clone())),
+                MapKey::Str(value) => write_data(buffer, &InnerData::STR(value.clone())),
+            }
+            write_option(buffer, previous, |buffer, (key, value)| {
+                write_data(buffer, key);
+                write_data(buffer, value);
+            });
+        },
+    }
+}
+
+fn read_heap_write(reader: &mut Reader) -> Result<HeapWrite, SnapshotError> {
+    let tag = reader.read_u8()?;
+    let handle = reader.read_u64()? as usize;
+
+    match tag {
+        WRITE_ALLOC => Ok(HeapWrite::Alloc(handle)),
+        WRITE_FREE => Ok(HeapWrite::Free(handle, reader.read_heap_object()?)),
+        WRITE_SET => {
+            let index = reader.read_u64()? as usize;
+            Ok(HeapWrite::Set(handle, index, reader.read_data()?))
+        },
+        WRITE_PUSH => Ok(HeapWrite::Push(handle)),
+        WRITE_MAP_ENTRY => {
+            let key = MapKey::from_data(&reader.read_data()?)
+                .ok_or(SnapshotError::InvalidTag(WRITE_MAP_ENTRY))?;
+            let previous = read_option(reader, |reader| Ok((reader.read_data()?, reader.read_data()?)))?;
+
+            Ok(HeapWrite::MapEntry(handle, key, previous))
+        },
+        tag => Err(SnapshotError::InvalidTag(tag)),
+    }
+}
+
+/// Passes reads through to the host's stdin while keeping a copy of every byte
+/// the processor consumes.
+struct RecordingReader<'a> {
+    inner: &'a mut dyn io::BufRead,
+    consumed: Vec<u8>,
+}
+
+impl io::Read for RecordingReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = self.inner.read(buf)?;
+        self.consumed.extend_from_slice(&buf[..len]);
+        Ok(len)
+    }
+}
+
+impl io::BufRead for RecordingReader<'_> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        if let Ok(buf) = self.inner.fill_buf() {
+            self.consumed.extend_from_slice(&buf[..amt]);
+        }
+        self.inner.consume(amt);
+    }
+}
+
+fn restore_stack(stack: &mut Stack, len: usize, tail: &[InnerData]) {
+    stack.truncate(len);
+    for value in tail {
+        stack.push(value.clone());
+    }
+}
+
+/// Drives a `Vm` one instruction at a time and keeps a log of state deltas, input
+/// and output, so execution can be stepped backwards and replayed.
+///
+/// Steps that are executed again after stepping back, or that were loaded from a
+/// saved log, are replayed from the log: they read the recorded input instead of
+/// stdin and write the recorded output, so the host sees exactly what the original
+/// run consumed and emitted.
+///
+/// Stack and memory writes are only journaled while the recorder executes a step,
+/// so a `Vm` that is run directly afterwards pays nothing for it. Resetting or
+/// restoring the `Vm` discards the log, as its deltas no longer lead back from the
+/// new state.
+pub struct Recorder {
+    steps: Vec<StepDelta>,
+    position: usize,
+    epoch: u64,
+}
+
+impl Recorder {
+    pub fn new<R, W>(vm: &mut Vm<R, W>) -> Recorder {
+        Recorder {
+            steps: Vec::new(),
+            position: 0,
+            epoch: vm.epoch,
+        }
+    }
+
+    fn discard_if_replaced<R, W>(&mut self, vm: &Vm<R, W>) {
+        if self.epoch != vm.epoch {
+            self.steps.clear();
+            self.position = 0;
+            self.epoch = vm.epoch;
+        }
+    }
+
+    /// Loads a log written by `to_bytes`. Its steps are replayed from the start, so
+    /// `vm` should be in the state the original recording began in.
+    pub fn from_bytes<R, W>(bytes: &[u8], vm: &mut Vm<R, W>) -> Result<Recorder, SnapshotError> {
+        let mut reader = Reader::new(bytes);
+
+        if reader.read_bytes(MAGIC.len())? != MAGIC {
+            return Err(SnapshotError::InvalidMagic);
+        }
+
+        let version = reader.read_u16()?;
+        if version != VERSION {
+            return Err(SnapshotError::UnsupportedVersion(version));
+        }
+
+        let mut steps = Vec::new();
+        for _ in 0..reader.read_u32()? {
+            steps.push(StepDelta::read(&mut reader)?);
+        }
+
+        let mut recorder = Recorder::new(vm);
+        recorder.steps = steps;
+
+        Ok(recorder)
+    }
+
+    pub fn load<R, W>(filepath: &str, vm: &mut Vm<R, W>) -> Result<Recorder, SnapshotError> {
+        Recorder::from_bytes(&fs::read(filepath)?, vm)
+    }
+
+    /// Encodes every recorded step, including those undone by stepping back.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buffer = Vec::new();
+
+        buffer.extend_from_slice(MAGIC);
+        buffer.extend_from_slice(&VERSION.to_le_bytes());
+
+        buffer.extend_from_slice(&(self.steps.len() as u32).to_le_bytes());
+        for step in &self.steps {
+            step.write(&mut buffer);
+        }
+
+        buffer
+    }
+
+    pub fn save(&self, filepath: &str) -> Result<(), SnapshotError> {
+        fs::write(filepath, self.to_bytes())?;
+        Ok(())
+    }
+
+    pub fn steps(&self) -> &[StepDelta] {
+        &self.steps
+    }
+
+    /// Number of recorded steps currently applied to the machine.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    pub fn output(&self) -> Vec<u8> {
+        self.steps.iter().flat_map(|step| step.output.iter().copied()).collect()
+    }
+
+    pub fn step<R: io::BufRead, W: io::Write>(&mut self, vm: &mut Vm<R, W>) -> Result<bool, VmError> {
+        self.discard_if_replaced(vm);
+        vm.run_hooks();
+
+        let pc = vm.processor.pc();
+        let registers = vm.processor.registers;
+        let flags = vm.processor.flag_register.to_bits();
+        let fp = vm.processor.fp;
+        let locals = vm.processor.locals;
+        let fuel = vm.processor.fuel();
+        vm.stack.record_writes(true);
+        vm.call_stack.record_writes(true);
+        vm.data_memory.record_writes(true);
+        vm.data_memory.heap_mut().record_writes(true);
+        vm.data_memory.linear_memory_mut().record_writes(true);
+
+        let replaying = self.position < self.steps.len();
+        let mut output = Vec::new();
+
+        // A step that fails is logged like any other, so stepping back across a
+        // fault restores whatever the instruction changed before it failed.
+        let (result, input) = if replaying {
+            let mut input: &[u8] = &self.steps[self.position].input;
+            let result = vm.processor.step(&vm.program_memory, &mut vm.data_memory, &mut vm.stack,
+                                           &mut vm.call_stack, &mut input, &mut output);
+            (result, std::mem::take(&mut self.steps[self.position].input))
+        } else {
+            let mut input = RecordingReader { inner: &mut vm.stdin, consumed: Vec::new() };
+            let result = vm.processor.step(&vm.program_memory, &mut vm.data_memory, &mut vm.stack,
+                                           &mut vm.call_stack, &mut input, &mut output);
+            (result, input.consumed)
+        };
+
+        if replaying {
+            output = std::mem::take(&mut self.steps[self.position].output);
+        }
+
+        let (stack_len, stack_tail) = vm.stack.take_writes();
+        let (call_stack_len, call_stack_tail) = vm.call_stack.take_writes();
+        let writes = vm.data_memory.take_writes();
+        let heap_writes = vm.data_memory.heap_mut().take_writes();
+        let memory_writes = vm.data_memory.linear_memory_mut().take_writes();
+
+        vm.stack.record_writes(false);
+        vm.call_stack.record_writes(false);
+        vm.data_memory.record_writes(false);
+        vm.data_memory.heap_mut().record_writes(false);
+        vm.data_memory.linear_memory_mut().record_writes(false);
+
+        let delta = StepDelta {
+            pc,
+            registers,
+            flags,
+            fp,
+            locals,
+            fuel,
+            stack_len,
+            stack_tail,
+            call_stack_len,
+            call_stack_tail,
+            writes,
+            heap_writes,
+            memory_writes,
+            input,
+            output,
+        };
+
+        if let Err(error) = vm.stdout.write_all(&delta.output) {
+            panic!("{}", error);
+        }
+
+        if replaying {
+            self.steps[self.position] = delta;
+        } else {
+            self.steps.push(delta);
+        }
+
+        self.position += 1;
+
+        result
+    }
+
+    pub fn run<R: io::BufRead, W: io::Write>(&mut self, vm: &mut Vm<R, W>) -> Result<(), VmError> {
+        while !self.step(vm)? {}
+
+        Ok(())
+    }
+
+    /// Undoes the most recent step. Returns `false` if there is nothing to undo.
+    pub fn step_back<R, W>(&mut self, vm: &mut Vm<R, W>) -> bool {
+        self.discard_if_replaced(vm);
+
+        if self.position == 0 {
+            return false;
+        }
+
+        self.position -= 1;
+        let delta = &self.steps[self.position];
+
+        vm.processor.pc = delta.pc;
+        vm.processor.registers = delta.registers;
+        vm.processor.flag_register = FlagRegister::from_bits(delta.flags);
+        vm.processor.fp = delta.fp;
+        vm.processor.locals = delta.locals;
+        vm.processor.set_fuel(delta.fuel);
+
+        restore_stack(&mut vm.stack, delta.stack_len, &delta.stack_tail);
+        restore_stack(&mut vm.call_stack, delta.call_stack_len, &delta.call_stack_tail);
+
+        for (idx, previous) in delta.writes.iter().rev() {
+            vm.data_memory.undo(*idx, previous.clone());
+        }
+
+        for write in delta.heap_writes.iter().rev() {
+            vm.data_memory.heap_mut().undo(write);
+        }
+
+        for (address, previous) in delta.memory_writes.iter().rev() {
+            vm.data_memory.linear_memory_mut().undo(*address, previous);
+        }
+
+        true
+    }
+
+    /// Steps back until just before the most recent write to data memory slot `idx`.
+    /// Returns `false` if no recorded step wrote to it, leaving the machine rewound
+    /// to the start of the recording.
+    pub fn reverse_continue_to_write<R, W>(&mut self, idx: u32, vm: &mut Vm<R, W>) -> bool {
+        while self.position > 0 {
+            let wrote = self.steps[self.position - 1].wrote(idx);
+
+            self.step_back(vm);
+
+            if wrote {
+                return true;
+            }
+        }
+
+        false
+    }
+
+    /// Steps back to the start of the recording.
+    pub fn rewind<R, W>(&mut self, vm: &mut Vm<R, W>) {
+        while self.step_back(vm) {}
+    }
+}
@@ -0,0 +1,335 @@
+use crate::instructions::{InstructionSet, Operands};
+use std::collections::BTreeMap;
+
+use crate::memory::{BigInt, HeapObject, InnerData, MapKey, StructType};
+
+use super::error::SnapshotError;
+
+const TAG_INT: u8 = 0;
+const TAG_INT16: u8 = 1;
+const TAG_INT32: u8 = 2;
+const TAG_STR: u8 = 3;
+const TAG_BOOL: u8 = 4;
+const TAG_INT64: u8 = 5;
+const TAG_U8: u8 = 6;
+const TAG_U16: u8 = 7;
+const TAG_U32: u8 = 8;
+const TAG_U64: u8 = 9;
+const TAG_BIGINT: u8 = 10;
+const TAG_FLOAT: u8 = 11;
+const TAG_REF: u8 = 12;
+const TAG_FUNC: u8 = 13;
+
+const TAG_ARRAY: u8 = 0;
+const TAG_STRUCT: u8 = 1;
+const TAG_MAP: u8 = 2;
+const TAG_FREE: u8 = 3;
+
+pub(crate) struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    pub(crate) fn new(bytes: &'a [u8]) -> Reader<'a> {
+        Reader {
+            bytes,
+            pos: 0,
+        }
+    }
+
+    pub(crate) fn read_bytes(&mut self, len: usize) -> Result<&'a [u8], SnapshotError> {
+        if self.bytes.len() - self.pos < len {
+            return Err(SnapshotError::UnexpectedEnd);
+        }
+
+        let bytes = &self.bytes[self.pos..self.pos + len];
+        self.pos += len;
+
+        Ok(bytes)
+    }
+
+    pub(crate) fn read_u8(&mut self) -> Result<u8, SnapshotError> {
+        Ok(self.read_bytes(1)?[0])
+    }
+
+    pub(crate) fn read_u16(&mut self) -> Result<u16, SnapshotError> {
+        Ok(u16::from_le_bytes(self.read_bytes(2)?.try_into().unwrap()))
+    }
+
+    pub(crate) fn read_u32(&mut self) -> Result<u32, SnapshotError> {
+        Ok(u32::from_le_bytes(self.read_bytes(4)?.try_into().unwrap()))
+    }
+
+    pub(crate) fn read_u64(&mut self) -> Result<u64, SnapshotError> {
+        Ok(u64::from_le_bytes(self.read_bytes(8)?.try_into().unwrap()))
+    }
+
+    pub(crate) fn read_data(&mut self) -> Result<InnerData, SnapshotError> {
+        match self.read_u8()? {
+            TAG_INT => Ok(InnerData::INT(self.read_u8()? as i8)),
+            TAG_INT16 => Ok(InnerData::INT16(self.read_u16()? as i16)),
+            TAG_INT32 => Ok(InnerData::INT32(self.read_u32()? as i32)),
+            TAG_STR => Ok(InnerData::STR(self.read_str()?)),
+            TAG_BOOL => Ok(InnerData::BOOL(self.read_u8()? != 0)),
+            TAG_INT64 => Ok(InnerData::INT64(self.read_u64()? as i64)),
+            TAG_U8 => Ok(InnerData::U8(self.read_u8()?)),
+            TAG_U16 => Ok(InnerData::U16(self.read_u16()?)),
+            TAG_U32 => Ok(InnerData::U32(self.read_u32()?)),
+            TAG_U64 => Ok(InnerData::U64(self.read_u64()?)),
+            TAG_BIGINT => {
+                let negative = self.read_u8()? != 0;
+                let len = self.read_u32()? as usize;
+
+                let mut limbs = Vec::new();
+                for _ in 0..len {
+                    limbs.push(self.read_u32()?);
+                }
+
+                Ok(InnerData::BIGINT(BigInt::from_parts(negative, limbs)))
+            },
+            TAG_FLOAT => Ok(InnerData::FLOAT(f64::from_bits(self.read_u64()?))),
+            TAG_REF => Ok(InnerData::REF(self.read_u64()? as usize)),
+            TAG_FUNC => Ok(InnerData::FUNC(self.read_u64()? as usize)),
+            tag => Err(SnapshotError::InvalidTag(tag)),
+        }
+    }
+
+    fn read_str(&mut self) -> Result<String, SnapshotError> {
+        let len = self.read_u32()? as usize;
+        let bytes = self.read_bytes(len)?;
+
+        String::from_utf8(bytes.to_vec()).map_err(|_| SnapshotError::InvalidString)
+    }
+
+    pub(crate) fn read_type(&mut self) -> Result<StructType, SnapshotError> {
+        let name = self.read_str()?;
+
+        let mut fields = Vec::new();
+        for _ in 0..self.read_u32()? {
+            fields.push(self.read_str()?);
+        }
+
+        Ok(StructType { name, fields })
+    }
+
+    /// Reads a heap slot: an object, or `None` for a slot the collector freed.
+    pub(crate) fn read_slot(&mut self) -> Result<Option<HeapObject>, SnapshotError> {
+        match self.read_u8()? {
+            TAG_FREE => Ok(None),
+            tag => self.read_object(tag).map(Some),
+        }
+    }
+
+    /// Reads a live heap object.
+    pub(crate) fn read_heap_object(&mut self) -> Result<HeapObject, SnapshotError> {
+        let tag = self.read_u8()?;
+        self.read_object(tag)
+    }
+
+    fn read_object(&mut self, tag: u8) -> Result<HeapObject, SnapshotError> {
+        match tag {
+            TAG_ARRAY => {
+                let mut elements = Vec::new();
+                for _ in 0..self.read_u32()? {
+                    elements.push(self.read_data()?);
+                }
+
+                Ok(HeapObject::Array(elements))
+            },
+            TAG_STRUCT => {
+                let id = self.read_u32()? as usize;
+
+                let mut values = Vec::new();
+                for _ in 0..self.read_u32()? {
+                    values.push(self.read_data()?);
+                }
+
+                Ok(HeapObject::Struct(id, values))
+            },
+            TAG_MAP => {
+                let mut entries = BTreeMap::new();
+                for _ in 0..self.read_u32()? {
+                    let key = self.read_data()?;
+                    let value = self.read_data()?;
+
+                    match MapKey::from_data(&key) {
+                        Some(map_key) => entries.insert(map_key, (key, value)),
+                        None => return Err(SnapshotError::InvalidTag(TAG_MAP)),
+                    };
+                }
+
+                Ok(HeapObject::Map(entries))
+            },
+            tag => Err(SnapshotError::InvalidTag(tag)),
+        }
+    }
+
+    pub(crate) fn read_instruction(&mut self) -> Result<InstructionSet, SnapshotError> {
+        let opcode = self.read_u8()?;
+
+        let (arg, arg1) = match InstructionSet::operands(opcode) {
+            Some(Operands::ValueAndOffset) => {
+                (Some(self.read_data()?), Some(InnerData::INT(self.read_u8()? as i8)))
+            },
+            Some(Operands::LabelAndCount) => (Some(self.read_data()?), Some(self.read_data()?)),
+            Some(Operands::One) => (Some(self.read_data()?), None),
+            Some(Operands::Zero) => (None, None),
+            None => return Err(SnapshotError::InvalidTag(opcode)),
+        };
+
+        Ok(InstructionSet::from_int(opcode, arg, arg1))
+    }
+}
+
+pub(crate) fn write_data(buffer: &mut Vec<u8>, value: &InnerData) {
+    match value {
+        InnerData::INT(a) => {
+            buffer.push(TAG_INT);
+            buffer.push(*a as u8);
+        },
+        InnerData::INT16(a) => {
+            buffer.push(TAG_INT16);
+            buffer.extend_from_slice(&a.to_le_bytes());
+        },
+        InnerData::INT32(a) => {
+            buffer.push(TAG_INT32);
+            buffer.extend_from_slice(&a.to_le_bytes());
+        },
+        InnerData::STR(a) => {
+            buffer.push(TAG_STR);
+            write_str(buffer, a);
+        },
+        InnerData::BOOL(a) => {
+            buffer.push(TAG_BOOL);
+            buffer.push(*a as u8);
+        },
+        InnerData::INT64(a) => {
+            buffer.push(TAG_INT64);
+            buffer.extend_from_slice(&a.to_le_bytes());
+        },
+        InnerData::U8(a) => {
+            buffer.push(TAG_U8);
+            buffer.push(*a);
+        },
+        InnerData::U16(a) => {
+            buffer.push(TAG_U16);
+            buffer.extend_from_slice(&a.to_le_bytes());
+        },
+        InnerData::U32(a) => {
+            buffer.push(TAG_U32);
+            buffer.extend_from_slice(&a.to_le_bytes());
+        },
+        InnerData::U64(a) => {
+            buffer.push(TAG_U64);
+            buffer.extend_from_slice(&a.to_le_bytes());
+        },
+        InnerData::BIGINT(a) => {
+            let (negative, limbs) = a.parts();
+
+            buffer.push(TAG_BIGINT);
+            buffer.push(negative as u8);
+            buffer.extend_from_slice(&(limbs.len() as u32).to_le_bytes());
+            for limb in limbs {
+                buffer.extend_from_slice(&limb.to_le_bytes());
+            }
+        },
+        InnerData::FLOAT(a) => {
+            buffer.push(TAG_FLOAT);
+            buffer.extend_from_slice(&a.to_bits().to_le_bytes());
+        },
+        InnerData::REF(a) => {
+            buffer.push(TAG_REF);
+            buffer.extend_from_slice(&(*a as u64).to_le_bytes());
+        },
+        InnerData::FUNC(a) => {
+            buffer.push(TAG_FUNC);
+            buffer.extend_from_slice(&(*a as u64).to_le_bytes());
+        },
+    }
+}
+
+pub(crate) fn write_slot(buffer: &mut Vec<u8>, slot: &Option<HeapObject>) {
+    match slot {
+        Some(object) => write_object(buffer, object),
+        None => buffer.push(TAG_FREE),
+    }
+}
+
+pub(crate) fn write_object(buffer: &mut Vec<u8>, object: &HeapObject) {
+    match object {
+        HeapObject::Array(elements) => {
+            buffer.push(TAG_ARRAY);
+            buffer.extend_from_slice(&(elements.len() as u32).to_le_bytes());
+            for element in elements {
+                write_data(buffer, element);
+            }
+        },
+        HeapObject::Struct(id, values) => {
+            buffer.push(TAG_STRUCT);
+            buffer.extend_from_slice(&(*id as u32).to_le_bytes());
+            buffer.extend_from_slice(&(values.len() as u32).to_le_bytes());
+            for value in values {
+                write_data(buffer, value);
+            }
+        },
+        HeapObject::Map(entries) => {
+            buffer.push(TAG_MAP);
+            buffer.extend_from_slice(&(entries.len() as u32).to_le_bytes());
+            for (key, value) in entries.values() {
+                write_data(buffer, key);
+                write_data(buffer, value);
+            }
+        },
+    }
+}
+
+fn write_str(buffer: &mut Vec<u8>, value: &str) {
+    buffer.extend_from_slice(&(value.len() as u32).to_le_bytes());
+    buffer.extend_from_slice(value.as_bytes());
+}
+
+pub(crate) fn write_type(buffer: &mut Vec<u8>, struct_type: &StructType) {
+    write_str(buffer, &struct_type.name);
+    buffer.extend_from_slice(&(struct_type.fields.len() as u32).to_le_bytes());
+    for field in &struct_type.fields {
+        write_str(buffer, field);
+    }
+}
+
+pub(crate) fn write_instruction(buffer: &mut Vec<u8>, instruction: &InstructionSet) {
+    buffer.push(instruction.opcode());
+
+    match instruction {
+        InstructionSet::LOAD(value, offset) | InstructionSet::POP(value, offset) => {
+            write_data(buffer, value);
+            buffer.push(*offset);
+        },
+        InstructionSet::CALLF(label, argc) => {
+            write_data(buffer, label);
+            write_data(buffer, argc);
+        },
+        InstructionSet::JMP(label) | InstructionSet::JZ(label) |
+        InstructionSet::JN(label) | InstructionSet::CALL(label) |
+        InstructionSet::JNZ(label) | InstructionSet::JP(label) |
+        InstructionSet::JT(label) | InstructionSet::JF(label) |
+        InstructionSet::JC(label) | InstructionSet::JO(label) |
+        InstructionSet::JNC(label) | InstructionSet::JNO(label) |
+        InstructionSet::PICK(label) | InstructionSet::ROLL(label) |
+        InstructionSet::FTOI(label) | InstructionSet::ADDP(label) |
+        InstructionSet::SUBP(label) | InstructionSet::MULP(label) |
+        InstructionSet::DIVP(label) | InstructionSet::MODP(label) |
+        InstructionSet::NEGP(label) | InstructionSet::WIDEN(label) |
+        InstructionSet::NARROW(label) | InstructionSet::NARROWC(label) |
+        InstructionSet::SEXT(label) | InstructionSet::ZEXT(label) |
+        InstructionSet::FORMAT(label) | InstructionSet::NEWARRAY(label) |
+        InstructionSet::NEW(label) | InstructionSet::GETFIELD(label) |
+        InstructionSet::SETFIELD(label) | InstructionSet::MLOAD(label) |
+        InstructionSet::MSTORE(label) | InstructionSet::RETF(label) |
+        InstructionSet::LOADLOCAL(label) | InstructionSet::STORELOCAL(label) |
+        InstructionSet::FUNCREF(label) | InstructionSet::CALLI(label) => {
+            write_data(buffer, label);
+        },
+        _ => {},
+    }
+}
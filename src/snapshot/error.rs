@@ -0,0 +1,37 @@
+use std::fmt::{Display, self};
+use std::io;
+
+#[derive(Debug)]
+pub enum SnapshotError {
+    Io(io::Error),
+    InvalidMagic,
+    UnsupportedVersion(u16),
+    UnexpectedEnd,
+    InvalidTag(u8),
+    InvalidString,
+    InvalidMemorySize,
+}
+
+impl Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotError::Io(error) => write!(f, "{}", error),
+            SnapshotError::InvalidMagic => write!(f, "Not a yamini snapshot!"),
+            SnapshotError::UnsupportedVersion(version) => {
+                write!(f, "Unsupported snapshot version: {}", version)
+            },
+            SnapshotError::UnexpectedEnd => write!(f, "Snapshot is truncated!"),
+            SnapshotError::InvalidTag(tag) => write!(f, "Invalid tag in snapshot: {}", tag),
+            SnapshotError::InvalidString => write!(f, "Invalid string in snapshot!"),
+            SnapshotError::InvalidMemorySize => write!(f, "Invalid linear memory size in snapshot!"),
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+impl From<io::Error> for SnapshotError {
+    fn from(error: io::Error) -> Self {
+        SnapshotError::Io(error)
+    }
+}
@@ -0,0 +1,6 @@
+pub mod state;
+pub mod error;
+pub(crate) mod codec;
+
+pub use state::{Snapshot, MAX_LINEAR_MEMORY};
+pub use error::SnapshotError;
@@ -0,0 +1,217 @@
+use std::fs;
+
+use crate::instructions::InstructionSet;
+use crate::memory::{ProgramMemory, DataMemory, HeapObject, InnerData, Stack, StructType};
+use crate::processor::Processor;
+use crate::processor::processor::FlagRegister;
+
+use super::codec::{Reader, write_data, write_instruction, write_slot, write_type};
+use super::error::SnapshotError;
+
+const MAGIC: &[u8; 4] = b"YSNP";
+const VERSION: u16 = 2;
+
+/// Largest linear memory `Snapshot::from_bytes` will allocate. Trailing zeros are
+/// not stored, so the size in a snapshot is not bounded by its length.
+pub const MAX_LINEAR_MEMORY: usize = 1 << 28;
+
+/// Complete machine state, detached from the objects it was captured from.
+///
+/// Host configuration such as fuel and limits lives on the `Processor` and is not
+/// part of a snapshot, so a restored program runs under the restoring host's rules.
+#[derive(Debug, PartialEq)]
+pub struct Snapshot {
+    pub pc: usize,
+    pub registers: [i8; 10],
+    pub flags: u8,
+    pub fp: usize,
+    pub locals: usize,
+    pub stack: Vec<InnerData>,
+    pub call_stack: Vec<InnerData>,
+    pub data_memory: Vec<(u32, InnerData)>,
+    pub types: Vec<StructType>,
+    pub heap: Vec<Option<HeapObject>>,
+    pub linear_memory: Vec<u8>,
+    pub program: Vec<InstructionSet>,
+}
+
+impl Snapshot {
+    pub fn capture(processor: &Processor, program_memory: &ProgramMemory,
+                   data_memory: &DataMemory, stack: &Stack, call_stack: &Stack) -> Snapshot {
+        Snapshot {
+            pc: processor.pc,
+            registers: processor.registers,
+            flags: processor.flag_register.to_bits(),
+            fp: processor.fp,
+            locals: processor.locals,
+            stack: stack.data().to_vec(),
+            call_stack: call_stack.data().to_vec(),
+            data_memory: data_memory.slots().map(|(idx, value)| (idx, value.clone())).collect(),
+            types: data_memory.heap().types().to_vec(),
+            heap: data_memory.heap().objects().to_vec(),
+            linear_memory: data_memory.linear_memory().bytes().to_vec(),
+            program: program_memory.program().to_vec(),
+        }
+    }
+
+    pub fn restore(self, processor: &mut Processor, program_memory: &mut ProgramMemory,
+                   data_memory: &mut DataMemory, stack: &mut Stack, call_stack: &mut Stack) {
+        processor.pc = self.pc;
+        processor.registers = self.registers;
+        processor.flag_register = FlagRegister::from_bits(self.flags);
+        processor.fp = self.fp;
+        processor.locals = self.locals;
+
+        stack.head = self.stack.len();
+        stack.data = self.stack;
+
+        call_stack.head = self.call_stack.len();
+        call_stack.data = self.call_stack;
+
+        data_memory.load(self.data_memory);
+        data_memory.heap_mut().define_types(self.types);
+        data_memory.heap_mut().load(self.heap);
+        data_memory.linear_memory_mut().load(self.linear_memory);
+        program_memory.load_program(self.program);
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buffer = Vec::new();
+
+        buffer.extend_from_slice(MAGIC);
+        buffer.extend_from_slice(&VERSION.to_le_bytes());
+
+        buffer.extend_from_slice(&(self.pc as u64).to_le_bytes());
+        buffer.extend(self.registers.iter().map(|register| *register as u8));
+        buffer.push(self.flags);
+        buffer.extend_from_slice(&(self.fp as u64).to_le_bytes());
+        buffer.extend_from_slice(&(self.locals as u64).to_le_bytes());
+
+        buffer.extend_from_slice(&(self.stack.len() as u32).to_le_bytes());
+        for value in &self.stack {
+            write_data(&mut buffer, value);
+        }
+
+        buffer.extend_from_slice(&(self.call_stack.len() as u32).to_le_bytes());
+        for value in &self.call_stack {
+            write_data(&mut buffer, value);
+        }
+
+        buffer.extend_from_slice(&(self.data_memory.len() as u32).to_le_bytes());
+        for (idx, value) in &self.data_memory {
+            buffer.extend_from_slice(&idx.to_le_bytes());
+            write_data(&mut buffer, value);
+        }
+
+        buffer.extend_from_slice(&(self.types.len() as u32).to_le_bytes());
+        for struct_type in &self.types {
+            write_type(&mut buffer, struct_type);
+        }
+
+        buffer.extend_from_slice(&(self.heap.len() as u32).to_le_bytes());
+        for slot in &self.heap {
+            write_slot(&mut buffer, slot);
+        }
+
+        // Linear memory is mostly zeros, so only the bytes up to the last non-zero
+        // one are written.
+        let used = self.linear_memory.iter().rposition(|byte| *byte != 0).map_or(0, |last| last + 1);
+        buffer.extend_from_slice(&(self.linear_memory.len() as u64).to_le_bytes());
+        buffer.extend_from_slice(&(used as u32).to_le_bytes());
+        buffer.extend_from_slice(&self.linear_memory[..used]);
+
+        buffer.extend_from_slice(&(self.program.len() as u32).to_le_bytes());
+        for instruction in &self.program {
+            write_instruction(&mut buffer, instruction);
+        }
+
+        buffer
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Snapshot, SnapshotError> {
+        let mut reader = Reader::new(bytes);
+
+        if reader.read_bytes(MAGIC.len())? != MAGIC {
+            return Err(SnapshotError::InvalidMagic);
+        }
+
+        let version = reader.read_u16()?;
+        if version != VERSION {
+            return Err(SnapshotError::UnsupportedVersion(version));
+        }
+
+        let pc = reader.read_u64()? as usize;
+
+        let mut registers = [0; 10];
+        for register in registers.iter_mut() {
+            *register = reader.read_u8()? as i8;
+        }
+
+        let flags = reader.read_u8()?;
+        let fp = reader.read_u64()? as usize;
+        let locals = reader.read_u64()? as usize;
+
+        let mut stack = Vec::new();
+        for _ in 0..reader.read_u32()? {
+            stack.push(reader.read_data()?);
+        }
+
+        let mut call_stack = Vec::new();
+        for _ in 0..reader.read_u32()? {
+            call_stack.push(reader.read_data()?);
+        }
+
+        let mut data_memory = Vec::new();
+        for _ in 0..reader.read_u32()? {
+            let idx = reader.read_u32()?;
+            data_memory.push((idx, reader.read_data()?));
+        }
+
+        let mut types = Vec::new();
+        for _ in 0..reader.read_u32()? {
+            types.push(reader.read_type()?);
+        }
+
+        let mut heap = Vec::new();
+        for _ in 0..reader.read_u32()? {
+            heap.push(reader.read_slot()?);
+        }
+
+        let size = reader.read_u64()? as usize;
+        let used = reader.read_u32()? as usize;
+        if used > size || size > MAX_LINEAR_MEMORY {
+            return Err(SnapshotError::InvalidMemorySize);
+        }
+        let mut linear_memory = reader.read_bytes(used)?.to_vec();
+        linear_memory.resize(size, 0);
+
+        let mut program = Vec::new();
+        for _ in 0..reader.read_u32()? {
+            program.push(reader.read_instruction()?);
+        }
+
+        Ok(Snapshot {
+            pc,
+            registers,
+            flags,
+            fp,
+            locals,
+            stack,
+            call_stack,
+            data_memory,
+            types,
+            heap,
+            linear_memory,
+            program,
+        })
+    }
+
+    pub fn save(&self, filepath: &str) -> Result<(), SnapshotError> {
+        fs::write(filepath, self.to_bytes())?;
+        Ok(())
+    }
+
+    pub fn load(filepath: &str) -> Result<Snapshot, SnapshotError> {
+        Snapshot::from_bytes(&fs::read(filepath)?)
+    }
+}
@@ -0,0 +1,168 @@
+use std::io;
+
+use crate::instructions::InstructionSet;
+use crate::memory::{ProgramMemory, DataMemory, Stack, StructType};
+use crate::processor::{Processor, Limits, OverflowPolicy};
+
+use super::machine::{Vm, InstructionHook};
+
+/// Bytes of linear memory a machine gets unless the builder says otherwise.
+pub const DEFAULT_LINEAR_MEMORY: usize = 64 * 1024;
+
+pub struct VmBuilder<R = io::StdinLock<'static>, W = io::Stdout> {
+    program: Vec<InstructionSet>,
+    types: Vec<StructType>,
+    stdin: R,
+    stdout: W,
+    limits: Limits,
+    overflow_policy: OverflowPolicy,
+    gc_stress: bool,
+    strict_memory: bool,
+    linear_memory: usize,
+    fuel: Option<u64>,
+    instruction_costs: Vec<(u8, u64)>,
+    hooks: Vec<InstructionHook>,
+}
+
+impl Default for VmBuilder {
+    fn default() -> Self {
+        VmBuilder::new()
+    }
+}
+
+impl VmBuilder {
+    pub fn new() -> VmBuilder {
+        VmBuilder {
+            program: Vec::new(),
+            types: Vec::new(),
+            stdin: io::stdin().lock(),
+            stdout: io::stdout(),
+            limits: Limits::new(),
+            overflow_policy: OverflowPolicy::Promote,
+            gc_stress: false,
+            strict_memory: false,
+            linear_memory: DEFAULT_LINEAR_MEMORY,
+            fuel: None,
+            instruction_costs: Vec::new(),
+            hooks: Vec::new(),
+        }
+    }
+}
+
+impl<R: io::BufRead, W: io::Write> VmBuilder<R, W> {
+    pub fn program(mut self, program: Vec<InstructionSet>) -> Self {
+        self.program = program;
+        self
+    }
+
+    pub fn types(mut self, types: Vec<StructType>) -> Self {
+        self.types = types;
+        self
+    }
+
+    pub fn stdin<T: io::BufRead>(self, stdin: T) -> VmBuilder<T, W> {
+        VmBuilder {
+            program: self.program,
+            types: self.types,
+            stdin,
+            stdout: self.stdout,
+            limits: self.limits,
+            overflow_policy: self.overflow_policy,
+            gc_stress: self.gc_stress,
+            strict_memory: self.strict_memory,
+            linear_memory: self.linear_memory,
+            fuel: self.fuel,
+            instruction_costs: self.instruction_costs,
+            hooks: self.hooks,
+        }
+    }
+
+    pub fn stdout<T: io::Write>(self, stdout: T) -> VmBuilder<R, T> {
+        VmBuilder {
+            program: self.program,
+            types: self.types,
+            stdin: self.stdin,
+            stdout,
+            limits: self.limits,
+            overflow_policy: self.overflow_policy,
+            gc_stress: self.gc_stress,
+            strict_memory: self.strict_memory,
+            linear_memory: self.linear_memory,
+            fuel: self.fuel,
+            instruction_costs: self.instruction_costs,
+            hooks: self.hooks,
+        }
+    }
+
+    pub fn limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    pub fn overflow_policy(mut self, policy: OverflowPolicy) -> Self {
+        self.overflow_policy = policy;
+        self
+    }
+
+    pub fn gc_stress(mut self, enabled: bool) -> Self {
+        self.gc_stress = enabled;
+        self
+    }
+
+    pub fn strict_memory(mut self, enabled: bool) -> Self {
+        self.strict_memory = enabled;
+        self
+    }
+
+    /// Sets the size in bytes of the memory `MLOAD` and `MSTORE` address.
+    pub fn linear_memory(mut self, size: usize) -> Self {
+        self.linear_memory = size;
+        self
+    }
+
+    pub fn fuel(mut self, fuel: u64) -> Self {
+        self.fuel = Some(fuel);
+        self
+    }
+
+    pub fn instruction_cost(mut self, opcode: u8, cost: u64) -> Self {
+        self.instruction_costs.push((opcode, cost));
+        self
+    }
+
+    pub fn on_instruction(mut self, hook: InstructionHook) -> Self {
+        self.hooks.push(hook);
+        self
+    }
+
+    pub fn build(self) -> Vm<R, W> {
+        let mut processor = Processor::new();
+        processor.set_limits(self.limits);
+        processor.set_overflow_policy(self.overflow_policy);
+        processor.set_gc_stress(self.gc_stress);
+        processor.set_strict_memory(self.strict_memory);
+        processor.set_fuel(self.fuel);
+        for (opcode, cost) in self.instruction_costs {
+            processor.set_instruction_cost(opcode, cost);
+        }
+
+        let mut program_memory = ProgramMemory::new();
+        program_memory.load_program(self.program);
+
+        let mut data_memory = DataMemory::with_linear_memory(self.linear_memory);
+        data_memory.heap_mut().define_types(self.types);
+
+        Vm {
+            processor,
+            program_memory,
+            data_memory,
+            stack: Stack::new(),
+            call_stack: Stack::new(),
+            stdin: self.stdin,
+            stdout: self.stdout,
+            fuel: self.fuel,
+            hooks: self.hooks,
+            epoch: 0,
+        }
+    }
+}
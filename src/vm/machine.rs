@@ -0,0 +1,133 @@
+use std::io;
+
+use crate::instructions::InstructionSet;
+use crate::memory::{ProgramMemory, DataMemory, Stack};
+use crate::processor::{Frame, Processor, VmError};
+use crate::snapshot::Snapshot;
+
+use super::builder::VmBuilder;
+
+/// Called with the pc and the instruction about to be executed.
+pub type InstructionHook = Box<dyn FnMut(usize, &InstructionSet)>;
+
+/// A processor together with the memories and I/O handles it runs against.
+pub struct Vm<R = io::StdinLock<'static>, W = io::Stdout> {
+    pub(crate) processor: Processor,
+    pub(crate) program_memory: ProgramMemory,
+    pub(crate) data_memory: DataMemory,
+    pub(crate) stack: Stack,
+    pub(crate) call_stack: Stack,
+    pub(crate) stdin: R,
+    pub(crate) stdout: W,
+    pub(crate) fuel: Option<u64>,
+    pub(crate) hooks: Vec<InstructionHook>,
+    /// Bumped whenever `reset` or `restore` replaces the machine state, so a
+    /// `Recorder` can tell that its log no longer leads to the current state.
+    pub(crate) epoch: u64,
+}
+
+impl Vm {
+    pub fn builder() -> VmBuilder {
+        VmBuilder::new()
+    }
+}
+
+impl<R: io::BufRead, W: io::Write> Vm<R, W> {
+    pub(crate) fn run_hooks(&mut self) {
+        let pc = self.processor.pc();
+        let instruction = self.program_memory.get_instruction(pc);
+
+        for hook in self.hooks.iter_mut() {
+            hook(pc, instruction);
+        }
+    }
+
+    /// Executes a single instruction. Returns whether it was `HALT`.
+    pub fn step(&mut self) -> Result<bool, VmError> {
+        self.run_hooks();
+
+        self.processor.step(&self.program_memory, &mut self.data_memory, &mut self.stack,
+                            &mut self.call_stack, &mut self.stdin, &mut self.stdout)
+    }
+
+    /// Runs until `HALT` or an error. After `VmError::OutOfFuel`, add fuel through
+    /// `processor_mut` and call `run` again to resume.
+    pub fn run(&mut self) -> Result<(), VmError> {
+        while !self.step()? {}
+
+        Ok(())
+    }
+
+    /// Puts the machine back into its initial state with the same program loaded,
+    /// refilling fuel to the amount it was built with.
+    pub fn reset(&mut self) {
+        self.processor.reset();
+        self.processor.set_fuel(self.fuel);
+
+        self.stack = Stack::new();
+        self.call_stack = Stack::new();
+
+        // Struct types come with the program, so they outlive the heap's objects.
+        let types = self.data_memory.heap().types().to_vec();
+        self.data_memory = DataMemory::with_linear_memory(self.data_memory.linear_memory().size());
+        self.data_memory.heap_mut().define_types(types);
+        self.epoch += 1;
+    }
+
+    /// Frees every heap object the program can no longer reach.
+    pub fn collect_garbage(&mut self) {
+        self.data_memory.collect_garbage(&[self.stack.data(), self.call_stack.data()]);
+    }
+
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot::capture(&self.processor, &self.program_memory, &self.data_memory,
+                          &self.stack, &self.call_stack)
+    }
+
+    pub fn restore(&mut self, snapshot: Snapshot) {
+        snapshot.restore(&mut self.processor, &mut self.program_memory, &mut self.data_memory,
+                         &mut self.stack, &mut self.call_stack);
+        self.epoch += 1;
+    }
+
+    pub fn processor(&self) -> &Processor {
+        &self.processor
+    }
+
+    pub fn processor_mut(&mut self) -> &mut Processor {
+        &mut self.processor
+    }
+
+    pub fn program_memory(&self) -> &ProgramMemory {
+        &self.program_memory
+    }
+
+    pub fn data_memory(&self) -> &DataMemory {
+        &self.data_memory
+    }
+
+    pub fn data_memory_mut(&mut self) -> &mut DataMemory {
+        &mut self.data_memory
+    }
+
+    pub fn stack(&self) -> &Stack {
+        &self.stack
+    }
+
+    pub fn stack_mut(&mut self) -> &mut Stack {
+        &mut self.stack
+    }
+
+    /// The frames made by `CALLF` and their locals, innermost first.
+    pub fn frames(&self) -> Vec<Frame<'_>> {
+        self.processor.frames(&self.call_stack)
+    }
+
+    pub fn call_stack(&self) -> &Stack {
+        &self.call_stack
+    }
+
+    pub fn stdout(&self) -> &W {
+        &self.stdout
+    }
+}
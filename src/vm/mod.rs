@@ -0,0 +1,5 @@
+pub mod machine;
+pub mod builder;
+
+pub use machine::{Vm, InstructionHook};
+pub use builder::{VmBuilder, DEFAULT_LINEAR_MEMORY};
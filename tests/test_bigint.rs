@@ -0,0 +1,65 @@
+use std::cmp::Ordering;
+
+use yamini::memory::BigInt;
+
+fn big(value: &str) -> BigInt {
+    BigInt::parse(value).unwrap()
+}
+
+#[test]
+fn test_parse_and_display() {
+    assert_eq!(big("0").to_string(), "0");
+    assert_eq!(big("-0").to_string(), "0");
+    assert_eq!(big("000123").to_string(), "123");
+    assert_eq!(big("-123456789012345678901234567890").to_string(), "-123456789012345678901234567890");
+    assert_eq!(BigInt::parse("12a"), None);
+    assert_eq!(BigInt::parse("-"), None);
+}
+
+#[test]
+fn test_arithmetic() {
+    let a = big("123456789012345678901234567890");
+    let b = big("-987654321098765432109876543210");
+
+    assert_eq!((a.clone() + b.clone()).to_string(), "-864197532086419753208641975320");
+    assert_eq!((a.clone() - b.clone()).to_string(), "1111111110111111111011111111100");
+    assert_eq!((a.clone() * b.clone()).to_string(),
+               "-121932631137021795226185032733622923332237463801111263526900");
+    assert_eq!((b.clone() / a.clone()).to_string(), "-8");
+    assert_eq!((b.clone() % a.clone()).to_string(), "-9000000000900000000090");
+    assert_eq!((-a.clone()).to_string(), "-123456789012345678901234567890");
+    assert!((a.clone() - a).is_zero());
+}
+
+#[test]
+fn test_bits() {
+    assert_eq!(BigInt::zero().bits(), 0);
+    assert_eq!(BigInt::from(-1).bits(), 1);
+    assert_eq!(BigInt::from(u32::MAX as i128).bits(), 32);
+    assert_eq!(BigInt::from(1i128 << 100).bits(), 101);
+}
+
+#[test]
+fn test_division_matches_fixed_width() {
+    for (x, y) in [(7i128, 2i128), (-7, 2), (7, -2), (-7, -2), (1 << 100, 3), (-(1 << 90), 1 << 40)] {
+        assert_eq!(BigInt::from(x) / BigInt::from(y), BigInt::from(x / y));
+        assert_eq!(BigInt::from(x) % BigInt::from(y), BigInt::from(x % y));
+    }
+}
+
+#[test]
+#[should_panic(expected = "Division by zero!")]
+fn test_division_by_zero() {
+    let _ = big("5") / BigInt::zero();
+}
+
+#[test]
+fn test_ordering_and_conversion() {
+    assert_eq!(big("-5").cmp(&big("3")), Ordering::Less);
+    assert_eq!(big("-5").cmp(&big("-30000000000000000000")), Ordering::Greater);
+    assert_eq!(big("30000000000000000000").cmp(&big("29999999999999999999")), Ordering::Greater);
+
+    assert_eq!(BigInt::from(i128::MIN).to_i128(), Some(i128::MIN));
+    assert_eq!(BigInt::from(i128::MAX).to_i128(), Some(i128::MAX));
+    assert_eq!((BigInt::from(i128::MAX) + big("1")).to_i128(), None);
+}
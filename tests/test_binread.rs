@@ -1,6 +1,6 @@
-use yamini::binread::read_from_file;
+use yamini::binread::{read_from_file, read_binary};
 use yamini::instructions::InstructionSet;
-use yamini::memory::InnerData;
+use yamini::memory::{InnerData, StructType};
 
 #[test]
 fn test_read_from_file() {
@@ -16,4 +16,83 @@ fn test_read_from_file() {
     ];
 
     assert_eq!(program, expected_program);
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_read_bool_literals() {
+    let program = read_from_file("tests/data/bool.out");
+
+    let expected_program = vec![
+        InstructionSet::LOAD(InnerData::BOOL(true), 7),
+        InstructionSet::SHOW,
+        InstructionSet::LOAD(InnerData::BOOL(false), 7),
+        InstructionSet::SHOW,
+        InstructionSet::HALT,
+    ];
+
+    assert_eq!(program, expected_program);
+}
+
+#[test]
+fn test_read_wide_and_unsigned_literals() {
+    let program = read_from_file("tests/data/int64.out");
+
+    let expected_program = vec![
+        InstructionSet::LOAD(InnerData::INT64(5000000000), 2),
+        InstructionSet::LOAD(InnerData::U8(200), 2),
+        InstructionSet::LOAD(InnerData::U32(70000), 2),
+        InstructionSet::SHOW,
+        InstructionSet::HALT,
+    ];
+
+    assert_eq!(program, expected_program);
+}
+
+#[test]
+fn test_read_float_literals() {
+    let program = read_from_file("tests/data/float.out");
+
+    let expected_program = vec![
+        InstructionSet::LOAD(InnerData::FLOAT(2.5), 2),
+        InstructionSet::LOAD(InnerData::FLOAT(10.0), 2),
+        InstructionSet::FTOI(InnerData::INT(1)),
+        InstructionSet::HALT,
+    ];
+
+    assert_eq!(program, expected_program);
+}
+
+#[test]
+fn test_read_type_section() {
+    let binary = read_binary("tests/data/struct.out");
+
+    assert_eq!(binary.types, vec![StructType::new("Point", &["x", "y"])]);
+
+    let expected_program = vec![
+        InstructionSet::NEW(InnerData::INT(0)),
+        InstructionSet::DUP,
+        InstructionSet::LOAD(InnerData::INT(7), 2),
+        InstructionSet::SETFIELD(InnerData::INT(1)),
+        InstructionSet::SHOW,
+        InstructionSet::HALT,
+    ];
+
+    assert_eq!(binary.program, expected_program);
+}
+
+#[test]
+fn test_read_wide_slot_operands() {
+    let program = read_from_file("tests/data/slots.out");
+
+    let expected_program = vec![
+        InstructionSet::LOAD(InnerData::INT(7), 2),
+        InstructionSet::POP(InnerData::U32(1000), 4),
+        InstructionSet::LOAD(InnerData::U32(1000), 4),
+        InstructionSet::LOAD(InnerData::U32(300), 5),
+        // A one-byte slot index equal to STARTSTR.
+        InstructionSet::LOAD(InnerData::INT(12), 4),
+        InstructionSet::HALT,
+    ];
+
+    assert_eq!(program, expected_program);
+}
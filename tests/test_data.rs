@@ -1,4 +1,8 @@
-use yamini::memory::InnerData;
+#![allow(clippy::bool_assert_comparison)]
+
+use std::cmp::Ordering;
+
+use yamini::memory::{BigInt, InnerData};
 
 #[test]
 fn test_inner_data_equality() {
@@ -6,6 +10,8 @@ fn test_inner_data_equality() {
     assert_eq!(InnerData::INT16(200), InnerData::INT16(200));
     assert_eq!(InnerData::INT32(1000), InnerData::INT32(1000));
     assert_eq!(InnerData::STR("ab".to_string()), InnerData::STR("ab".to_string()));
+    assert_eq!(InnerData::BOOL(true), InnerData::BOOL(true));
+    assert_ne!(InnerData::BOOL(false), InnerData::INT(0));
 }
 
 #[test]
@@ -108,4 +114,175 @@ fn test_mul_overflow_i16() {
 
     assert_eq!(result, InnerData::INT32(327670));
     assert_eq!(InnerData::variant_eq(&result, &InnerData::INT32(327670)), true);
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_compare_across_widths() {
+    assert_eq!(InnerData::INT(5).compare(&InnerData::INT16(5)), Some(Ordering::Equal));
+    assert_eq!(InnerData::INT32(-70000).compare(&InnerData::INT(3)), Some(Ordering::Less));
+    assert_eq!(InnerData::INT16(300).compare(&InnerData::INT(100)), Some(Ordering::Greater));
+}
+
+#[test]
+fn test_compare_strings() {
+    let a = InnerData::STR("abc".to_string());
+    let b = InnerData::STR("abd".to_string());
+
+    assert_eq!(a.compare(&b), Some(Ordering::Less));
+    assert_eq!(a.compare(&a.clone()), Some(Ordering::Equal));
+    assert_eq!(a.compare(&InnerData::INT(1)), None);
+}
+
+#[test]
+fn test_bitwise_promotion() {
+    assert_eq!(InnerData::INT(0b1100) & InnerData::INT(0b1010), InnerData::INT(0b1000));
+    assert_eq!(InnerData::INT(1) | InnerData::INT16(256), InnerData::INT16(257));
+    assert_eq!(InnerData::INT32(-1) ^ InnerData::INT(1), InnerData::INT32(-2));
+    assert_eq!(!InnerData::INT16(0), InnerData::INT16(-1));
+}
+
+#[test]
+fn test_shifts() {
+    assert_eq!(InnerData::INT(1) << InnerData::INT(3), InnerData::INT(8));
+    assert_eq!(InnerData::INT(64) << InnerData::INT(2), InnerData::INT(0));
+    assert_eq!(InnerData::INT(-8) >> InnerData::INT(1), InnerData::INT(-4));
+    assert_eq!(InnerData::INT(-8).ushr(InnerData::INT(1)), InnerData::INT(124));
+    assert_eq!(InnerData::INT32(-1).ushr(InnerData::INT(28)), InnerData::INT32(15));
+    assert_eq!(InnerData::INT16(1) << InnerData::INT(17), InnerData::INT16(2));
+}
+
+#[test]
+fn test_bool_display() {
+    assert_eq!(InnerData::BOOL(true).to_string(), "true");
+    assert_eq!(InnerData::BOOL(false).to_string(), "false");
+}
+
+#[test]
+fn test_add_overflow_i32() {
+    let a = InnerData::INT32(2147483647);
+    let b = InnerData::INT32(1);
+
+    let result = a + b;
+
+    assert_eq!(result, InnerData::INT64(2147483648));
+    assert!(InnerData::variant_eq(&result, &InnerData::INT64(2147483648)));
+}
+
+#[test]
+fn test_mul_overflow_i32() {
+    let result = InnerData::INT32(100000) * InnerData::INT(100) * InnerData::INT32(100000);
+
+    assert_eq!(result, InnerData::INT64(1000000000000));
+}
+
+#[test]
+fn test_unsigned_arithmetic() {
+    assert_eq!(InnerData::U8(200) + InnerData::U8(50), InnerData::U8(250));
+    assert_eq!(InnerData::U8(200) + InnerData::U8(100), InnerData::INT16(300));
+    assert_eq!(InnerData::U8(3) - InnerData::U8(5), InnerData::INT16(-2));
+    assert_eq!(InnerData::U16(300) * InnerData::U8(2), InnerData::U16(600));
+    assert_eq!(InnerData::U32(u32::MAX) + InnerData::U32(1), InnerData::INT64(4294967296));
+    assert_eq!(InnerData::U64(10) / InnerData::U64(3), InnerData::U64(3));
+}
+
+#[test]
+fn test_mixed_sign_promotion() {
+    assert_eq!(InnerData::U8(200) + InnerData::INT(-1), InnerData::INT16(199));
+    assert_eq!(InnerData::INT32(-1) + InnerData::U16(1), InnerData::INT32(0));
+    assert_eq!(InnerData::U32(1) - InnerData::INT16(2), InnerData::INT64(-1));
+    assert_eq!(InnerData::U64(5) * InnerData::INT(-2), InnerData::INT64(-10));
+}
+
+#[test]
+fn test_mixed_sign_promotion_above_i64() {
+    let big = |value: &str| InnerData::BIGINT(BigInt::parse(value).unwrap());
+
+    assert_eq!(InnerData::U64(u64::MAX) + InnerData::INT(-1), big("18446744073709551614"));
+    assert_eq!(InnerData::INT16(1) + InnerData::U64(u64::MAX), big("18446744073709551616"));
+    assert_eq!(InnerData::U64(u64::MAX) - InnerData::INT32(-1), big("18446744073709551616"));
+    assert_eq!(InnerData::U64(u64::MAX) + InnerData::INT64(1), big("18446744073709551616"));
+    assert_eq!(InnerData::INT64(-1) * InnerData::U64(1 << 63), big("-9223372036854775808"));
+    assert_eq!(InnerData::U64(i64::MAX as u64) + InnerData::INT64(-1), InnerData::INT64(i64::MAX - 1));
+
+    // Bitwise operators combine such pairs as 64-bit patterns instead.
+    assert_eq!(InnerData::U64(u64::MAX) & InnerData::INT(-2), InnerData::U64(u64::MAX - 1));
+    assert_eq!(InnerData::INT64(1) | InnerData::U64(1 << 63), InnerData::U64((1 << 63) + 1));
+    assert_eq!(InnerData::U64(u64::MAX) ^ InnerData::INT16(-1), InnerData::U64(0));
+}
+
+#[test]
+fn test_unsigned_shifts() {
+    assert_eq!(InnerData::U8(0x80) >> InnerData::INT(7), InnerData::U8(1));
+    assert_eq!(InnerData::U16(1) << InnerData::INT(15), InnerData::U16(0x8000));
+    assert_eq!(!InnerData::U8(0), InnerData::U8(255));
+    assert_eq!(InnerData::INT64(-1).ushr(InnerData::INT(60)), InnerData::INT64(15));
+}
+
+#[test]
+fn test_wide_display() {
+    assert_eq!(InnerData::INT64(-5000000000).to_string(), "-5000000000");
+    assert_eq!(InnerData::U64(u64::MAX).to_string(), "18446744073709551615");
+    assert_eq!(InnerData::U8(7).get_type(), "u8");
+    assert_eq!(InnerData::INT64(7).get_type(), "i64");
+}
+
+#[test]
+fn test_overflow_i64_promotes_to_bigint() {
+    let result = InnerData::INT64(i64::MAX) + InnerData::INT(1);
+
+    assert_eq!(result, InnerData::BIGINT(BigInt::parse("9223372036854775808").unwrap()));
+    assert_eq!(result.get_type(), "bigint");
+
+    let result = InnerData::U64(u64::MAX) * InnerData::U64(u64::MAX);
+    assert_eq!(result.to_string(), "340282366920938463426481119284349108225");
+
+    let result = InnerData::INT64(i64::MIN) / InnerData::INT(-1);
+    assert_eq!(result.to_string(), "9223372036854775808");
+}
+
+#[test]
+fn test_bigint_mixed_arithmetic() {
+    let big = InnerData::BIGINT(BigInt::parse("100000000000000000000").unwrap());
+
+    assert_eq!((big.clone() - InnerData::INT(1)).to_string(), "99999999999999999999");
+    assert_eq!((InnerData::U8(3) * big.clone()).to_string(), "300000000000000000000");
+    assert_eq!((big.clone() % InnerData::INT16(7)).to_string(), "2");
+    assert_eq!(big.compare(&InnerData::INT64(i64::MAX)), Some(Ordering::Greater));
+    assert_eq!(InnerData::BIGINT(BigInt::from(5)).compare(&InnerData::INT(5)), Some(Ordering::Equal));
+}
+
+#[test]
+fn test_float_arithmetic() {
+    assert_eq!(InnerData::FLOAT(1.5) + InnerData::FLOAT(2.25), InnerData::FLOAT(3.75));
+    assert_eq!(InnerData::FLOAT(1.5) * InnerData::INT(2), InnerData::FLOAT(3.0));
+    assert_eq!(InnerData::INT32(7) / InnerData::FLOAT(2.0), InnerData::FLOAT(3.5));
+    assert_eq!(InnerData::FLOAT(-7.5) % InnerData::INT(2), InnerData::FLOAT(-1.5));
+    assert_eq!(InnerData::U8(1) - InnerData::FLOAT(0.5), InnerData::FLOAT(0.5));
+    assert_eq!(InnerData::FLOAT(1.0) / InnerData::INT(0), InnerData::FLOAT(f64::INFINITY));
+}
+
+#[test]
+fn test_float_compare() {
+    assert_eq!(InnerData::FLOAT(2.0).compare(&InnerData::INT(2)), Some(Ordering::Equal));
+    assert_eq!(InnerData::INT16(300).compare(&InnerData::FLOAT(299.5)), Some(Ordering::Greater));
+    assert_eq!(InnerData::FLOAT(f64::NAN).compare(&InnerData::FLOAT(f64::NAN)), None);
+    assert_eq!(InnerData::FLOAT(1.0).compare(&InnerData::STR(String::from("1"))), None);
+}
+
+#[test]
+fn test_float_display() {
+    assert_eq!(InnerData::FLOAT(3.0).to_string(), "3.0");
+    assert_eq!(InnerData::FLOAT(-0.1).to_string(), "-0.1");
+    assert_eq!(InnerData::FLOAT(f64::INFINITY).to_string(), "inf");
+    assert_eq!(InnerData::FLOAT(1.0).get_type(), "f64");
+}
+
+#[test]
+fn test_func_values() {
+    assert_eq!(InnerData::FUNC(4).to_string(), "<func 4>");
+    assert_eq!(InnerData::FUNC(4).get_type(), "func");
+    assert_eq!(InnerData::FUNC(4).compare(&InnerData::FUNC(4)), Some(Ordering::Equal));
+    assert_eq!(InnerData::FUNC(4).compare(&InnerData::FUNC(5)), None);
+    assert_eq!(InnerData::FUNC(4).compare(&InnerData::INT(4)), None);
+    assert!(!InnerData::FUNC(4).is_integer());
+}
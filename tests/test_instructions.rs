@@ -1,4 +1,4 @@
-use yamini::{instructions::InstructionSet, memory::InnerData};
+use yamini::{instructions::{InstructionSet, Operands}, memory::InnerData};
 
 #[test]
 fn test_instruction_equality() {
@@ -61,6 +61,250 @@ fn test_instruction_equality() {
 
     let instruction = InstructionSet::DEREF;
     assert_eq!(instruction, InstructionSet::DEREF);
+
+    let instruction = InstructionSet::READ;
+    assert_eq!(instruction, InstructionSet::READ);
+
+    let instruction = InstructionSet::LT;
+    assert_eq!(instruction, InstructionSet::LT);
+
+    let instruction = InstructionSet::LE;
+    assert_eq!(instruction, InstructionSet::LE);
+
+    let instruction = InstructionSet::GT;
+    assert_eq!(instruction, InstructionSet::GT);
+
+    let instruction = InstructionSet::GE;
+    assert_eq!(instruction, InstructionSet::GE);
+
+    let instruction = InstructionSet::NE;
+    assert_eq!(instruction, InstructionSet::NE);
+
+    let instruction = InstructionSet::JNZ(InnerData::INT(2));
+    assert_eq!(instruction, InstructionSet::JNZ(InnerData::INT(2)));
+
+    let instruction = InstructionSet::JP(InnerData::INT(2));
+    assert_eq!(instruction, InstructionSet::JP(InnerData::INT(2)));
+
+    let instruction = InstructionSet::JT(InnerData::INT(2));
+    assert_eq!(instruction, InstructionSet::JT(InnerData::INT(2)));
+
+    let instruction = InstructionSet::JF(InnerData::INT(2));
+    assert_eq!(instruction, InstructionSet::JF(InnerData::INT(2)));
+
+    let instruction = InstructionSet::CMP;
+    assert_eq!(instruction, InstructionSet::CMP);
+
+    let instruction = InstructionSet::JC(InnerData::INT(2));
+    assert_eq!(instruction, InstructionSet::JC(InnerData::INT(2)));
+
+    let instruction = InstructionSet::JO(InnerData::INT(2));
+    assert_eq!(instruction, InstructionSet::JO(InnerData::INT(2)));
+
+    let instruction = InstructionSet::JNC(InnerData::INT(2));
+    assert_eq!(instruction, InstructionSet::JNC(InnerData::INT(2)));
+
+    let instruction = InstructionSet::JNO(InnerData::INT(2));
+    assert_eq!(instruction, InstructionSet::JNO(InnerData::INT(2)));
+
+    let instruction = InstructionSet::PUSHF;
+    assert_eq!(instruction, InstructionSet::PUSHF);
+
+    let instruction = InstructionSet::POPF;
+    assert_eq!(instruction, InstructionSet::POPF);
+
+    let instruction = InstructionSet::AND;
+    assert_eq!(instruction, InstructionSet::AND);
+
+    let instruction = InstructionSet::OR;
+    assert_eq!(instruction, InstructionSet::OR);
+
+    let instruction = InstructionSet::XOR;
+    assert_eq!(instruction, InstructionSet::XOR);
+
+    let instruction = InstructionSet::NOT;
+    assert_eq!(instruction, InstructionSet::NOT);
+
+    let instruction = InstructionSet::SHL;
+    assert_eq!(instruction, InstructionSet::SHL);
+
+    let instruction = InstructionSet::SHR;
+    assert_eq!(instruction, InstructionSet::SHR);
+
+    let instruction = InstructionSet::USHR;
+    assert_eq!(instruction, InstructionSet::USHR);
+
+    let instruction = InstructionSet::DUP;
+    assert_eq!(instruction, InstructionSet::DUP);
+
+    let instruction = InstructionSet::DROP;
+    assert_eq!(instruction, InstructionSet::DROP);
+
+    let instruction = InstructionSet::SWAP;
+    assert_eq!(instruction, InstructionSet::SWAP);
+
+    let instruction = InstructionSet::OVER;
+    assert_eq!(instruction, InstructionSet::OVER);
+
+    let instruction = InstructionSet::ROT;
+    assert_eq!(instruction, InstructionSet::ROT);
+
+    let instruction = InstructionSet::PICK(InnerData::INT(2));
+    assert_eq!(instruction, InstructionSet::PICK(InnerData::INT(2)));
+
+    let instruction = InstructionSet::ROLL(InnerData::INT(2));
+    assert_eq!(instruction, InstructionSet::ROLL(InnerData::INT(2)));
+
+    let instruction = InstructionSet::ITOF;
+    assert_eq!(instruction, InstructionSet::ITOF);
+
+    let instruction = InstructionSet::FTOI(InnerData::INT(1));
+    assert_eq!(instruction, InstructionSet::FTOI(InnerData::INT(1)));
+
+    let instruction = InstructionSet::ADDP(InnerData::INT(1));
+    assert_eq!(instruction, InstructionSet::ADDP(InnerData::INT(1)));
+
+    let instruction = InstructionSet::SUBP(InnerData::INT(1));
+    assert_eq!(instruction, InstructionSet::SUBP(InnerData::INT(1)));
+
+    let instruction = InstructionSet::MULP(InnerData::INT(1));
+    assert_eq!(instruction, InstructionSet::MULP(InnerData::INT(1)));
+
+    let instruction = InstructionSet::DIVP(InnerData::INT(1));
+    assert_eq!(instruction, InstructionSet::DIVP(InnerData::INT(1)));
+
+    let instruction = InstructionSet::MODP(InnerData::INT(1));
+    assert_eq!(instruction, InstructionSet::MODP(InnerData::INT(1)));
+
+    let instruction = InstructionSet::NEGP(InnerData::INT(1));
+    assert_eq!(instruction, InstructionSet::NEGP(InnerData::INT(1)));
+
+    let instruction = InstructionSet::WIDEN(InnerData::INT(1));
+    assert_eq!(instruction, InstructionSet::WIDEN(InnerData::INT(1)));
+
+    let instruction = InstructionSet::NARROW(InnerData::INT(1));
+    assert_eq!(instruction, InstructionSet::NARROW(InnerData::INT(1)));
+
+    let instruction = InstructionSet::NARROWC(InnerData::INT(1));
+    assert_eq!(instruction, InstructionSet::NARROWC(InnerData::INT(1)));
+
+    let instruction = InstructionSet::SEXT(InnerData::INT(1));
+    assert_eq!(instruction, InstructionSet::SEXT(InnerData::INT(1)));
+
+    let instruction = InstructionSet::ZEXT(InnerData::INT(1));
+    assert_eq!(instruction, InstructionSet::ZEXT(InnerData::INT(1)));
+
+    let instruction = InstructionSet::PARSE;
+    assert_eq!(instruction, InstructionSet::PARSE);
+
+    let instruction = InstructionSet::TOSTR;
+    assert_eq!(instruction, InstructionSet::TOSTR);
+
+    let instruction = InstructionSet::LEN;
+    assert_eq!(instruction, InstructionSet::LEN);
+
+    let instruction = InstructionSet::CHARAT;
+    assert_eq!(instruction, InstructionSet::CHARAT);
+
+    let instruction = InstructionSet::SUBSTR;
+    assert_eq!(instruction, InstructionSet::SUBSTR);
+
+    let instruction = InstructionSet::FIND;
+    assert_eq!(instruction, InstructionSet::FIND);
+
+    let instruction = InstructionSet::SPLIT;
+    assert_eq!(instruction, InstructionSet::SPLIT);
+
+    let instruction = InstructionSet::UPPER;
+    assert_eq!(instruction, InstructionSet::UPPER);
+
+    let instruction = InstructionSet::LOWER;
+    assert_eq!(instruction, InstructionSet::LOWER);
+
+    let instruction = InstructionSet::TRIM;
+    assert_eq!(instruction, InstructionSet::TRIM);
+
+    let instruction = InstructionSet::ORD;
+    assert_eq!(instruction, InstructionSet::ORD);
+
+    let instruction = InstructionSet::CHR;
+    assert_eq!(instruction, InstructionSet::CHR);
+
+    let instruction = InstructionSet::FORMAT(InnerData::INT(2));
+    assert_eq!(instruction, InstructionSet::FORMAT(InnerData::INT(2)));
+
+    let instruction = InstructionSet::PRINT;
+    assert_eq!(instruction, InstructionSet::PRINT);
+
+    let instruction = InstructionSet::NEWARRAY(InnerData::INT(3));
+    assert_eq!(instruction, InstructionSet::NEWARRAY(InnerData::INT(3)));
+
+    let instruction = InstructionSet::ALOAD;
+    assert_eq!(instruction, InstructionSet::ALOAD);
+
+    let instruction = InstructionSet::ASTORE;
+    assert_eq!(instruction, InstructionSet::ASTORE);
+
+    let instruction = InstructionSet::ALEN;
+    assert_eq!(instruction, InstructionSet::ALEN);
+
+    let instruction = InstructionSet::APPEND;
+    assert_eq!(instruction, InstructionSet::APPEND);
+
+    let instruction = InstructionSet::NEW(InnerData::INT(0));
+    assert_eq!(instruction, InstructionSet::NEW(InnerData::INT(0)));
+
+    let instruction = InstructionSet::GETFIELD(InnerData::INT(1));
+    assert_eq!(instruction, InstructionSet::GETFIELD(InnerData::INT(1)));
+
+    let instruction = InstructionSet::SETFIELD(InnerData::INT(1));
+    assert_eq!(instruction, InstructionSet::SETFIELD(InnerData::INT(1)));
+
+    let instruction = InstructionSet::MAPNEW;
+    assert_eq!(instruction, InstructionSet::MAPNEW);
+
+    let instruction = InstructionSet::MAPGET;
+    assert_eq!(instruction, InstructionSet::MAPGET);
+
+    let instruction = InstructionSet::MAPSET;
+    assert_eq!(instruction, InstructionSet::MAPSET);
+
+    let instruction = InstructionSet::MAPHAS;
+    assert_eq!(instruction, InstructionSet::MAPHAS);
+
+    let instruction = InstructionSet::MAPDEL;
+    assert_eq!(instruction, InstructionSet::MAPDEL);
+
+    let instruction = InstructionSet::MAPKEYS;
+    assert_eq!(instruction, InstructionSet::MAPKEYS);
+
+    let instruction = InstructionSet::MLOAD(InnerData::INT(4));
+    assert_eq!(instruction, InstructionSet::MLOAD(InnerData::INT(4)));
+
+    let instruction = InstructionSet::MSTORE(InnerData::INT(4));
+    assert_eq!(instruction, InstructionSet::MSTORE(InnerData::INT(4)));
+
+    let instruction = InstructionSet::MSIZE;
+    assert_eq!(instruction, InstructionSet::MSIZE);
+
+    let instruction = InstructionSet::CALLF(InnerData::INT(4), InnerData::INT(2));
+    assert_eq!(instruction, InstructionSet::CALLF(InnerData::INT(4), InnerData::INT(2)));
+    assert_ne!(instruction, InstructionSet::CALLF(InnerData::INT(4), InnerData::INT(1)));
+
+    let instruction = InstructionSet::RETF(InnerData::INT(1));
+    assert_eq!(instruction, InstructionSet::RETF(InnerData::INT(1)));
+
+    let instruction = InstructionSet::LOADLOCAL(InnerData::INT(0));
+    assert_eq!(instruction, InstructionSet::LOADLOCAL(InnerData::INT(0)));
+
+    let instruction = InstructionSet::STORELOCAL(InnerData::INT(0));
+    assert_eq!(instruction, InstructionSet::STORELOCAL(InnerData::INT(0)));
+
+    let instruction = InstructionSet::FUNCREF(InnerData::INT(4));
+    assert_eq!(instruction, InstructionSet::FUNCREF(InnerData::INT(4)));
+
+    let instruction = InstructionSet::CALLI(InnerData::INT(1));
+    assert_eq!(instruction, InstructionSet::CALLI(InnerData::INT(1)));
 }
 
 #[test]
@@ -124,4 +368,270 @@ fn test_instruction_from_int() {
 
     let instruction = InstructionSet::from_int(19, None, None);
     assert_eq!(instruction, InstructionSet::DEREF);
-}
\ No newline at end of file
+
+    let instruction = InstructionSet::from_int(20, None, None);
+    assert_eq!(instruction, InstructionSet::READ);
+
+    let instruction = InstructionSet::from_int(21, None, None);
+    assert_eq!(instruction, InstructionSet::LT);
+
+    let instruction = InstructionSet::from_int(22, None, None);
+    assert_eq!(instruction, InstructionSet::LE);
+
+    let instruction = InstructionSet::from_int(23, None, None);
+    assert_eq!(instruction, InstructionSet::GT);
+
+    let instruction = InstructionSet::from_int(24, None, None);
+    assert_eq!(instruction, InstructionSet::GE);
+
+    let instruction = InstructionSet::from_int(25, None, None);
+    assert_eq!(instruction, InstructionSet::NE);
+
+    let instruction = InstructionSet::from_int(26, Some(InnerData::INT(2)), None);
+    assert_eq!(instruction, InstructionSet::JNZ(InnerData::INT(2)));
+
+    let instruction = InstructionSet::from_int(27, Some(InnerData::INT(2)), None);
+    assert_eq!(instruction, InstructionSet::JP(InnerData::INT(2)));
+
+    let instruction = InstructionSet::from_int(28, Some(InnerData::INT(2)), None);
+    assert_eq!(instruction, InstructionSet::JT(InnerData::INT(2)));
+
+    let instruction = InstructionSet::from_int(29, Some(InnerData::INT(2)), None);
+    assert_eq!(instruction, InstructionSet::JF(InnerData::INT(2)));
+
+    let instruction = InstructionSet::from_int(30, None, None);
+    assert_eq!(instruction, InstructionSet::CMP);
+
+    let instruction = InstructionSet::from_int(31, Some(InnerData::INT(2)), None);
+    assert_eq!(instruction, InstructionSet::JC(InnerData::INT(2)));
+
+    let instruction = InstructionSet::from_int(32, Some(InnerData::INT(2)), None);
+    assert_eq!(instruction, InstructionSet::JO(InnerData::INT(2)));
+
+    let instruction = InstructionSet::from_int(33, Some(InnerData::INT(2)), None);
+    assert_eq!(instruction, InstructionSet::JNC(InnerData::INT(2)));
+
+    let instruction = InstructionSet::from_int(34, Some(InnerData::INT(2)), None);
+    assert_eq!(instruction, InstructionSet::JNO(InnerData::INT(2)));
+
+    let instruction = InstructionSet::from_int(35, None, None);
+    assert_eq!(instruction, InstructionSet::PUSHF);
+
+    let instruction = InstructionSet::from_int(36, None, None);
+    assert_eq!(instruction, InstructionSet::POPF);
+
+    let instruction = InstructionSet::from_int(37, None, None);
+    assert_eq!(instruction, InstructionSet::AND);
+
+    let instruction = InstructionSet::from_int(38, None, None);
+    assert_eq!(instruction, InstructionSet::OR);
+
+    let instruction = InstructionSet::from_int(39, None, None);
+    assert_eq!(instruction, InstructionSet::XOR);
+
+    let instruction = InstructionSet::from_int(40, None, None);
+    assert_eq!(instruction, InstructionSet::NOT);
+
+    let instruction = InstructionSet::from_int(41, None, None);
+    assert_eq!(instruction, InstructionSet::SHL);
+
+    let instruction = InstructionSet::from_int(42, None, None);
+    assert_eq!(instruction, InstructionSet::SHR);
+
+    let instruction = InstructionSet::from_int(43, None, None);
+    assert_eq!(instruction, InstructionSet::USHR);
+
+    let instruction = InstructionSet::from_int(44, None, None);
+    assert_eq!(instruction, InstructionSet::DUP);
+
+    let instruction = InstructionSet::from_int(45, None, None);
+    assert_eq!(instruction, InstructionSet::DROP);
+
+    let instruction = InstructionSet::from_int(46, None, None);
+    assert_eq!(instruction, InstructionSet::SWAP);
+
+    let instruction = InstructionSet::from_int(47, None, None);
+    assert_eq!(instruction, InstructionSet::OVER);
+
+    let instruction = InstructionSet::from_int(48, None, None);
+    assert_eq!(instruction, InstructionSet::ROT);
+
+    let instruction = InstructionSet::from_int(49, Some(InnerData::INT(2)), None);
+    assert_eq!(instruction, InstructionSet::PICK(InnerData::INT(2)));
+
+    let instruction = InstructionSet::from_int(50, Some(InnerData::INT(2)), None);
+    assert_eq!(instruction, InstructionSet::ROLL(InnerData::INT(2)));
+
+    let instruction = InstructionSet::from_int(51, None, None);
+    assert_eq!(instruction, InstructionSet::ITOF);
+
+    let instruction = InstructionSet::from_int(52, Some(InnerData::INT(1)), None);
+    assert_eq!(instruction, InstructionSet::FTOI(InnerData::INT(1)));
+
+    let instruction = InstructionSet::from_int(53, Some(InnerData::INT(1)), None);
+    assert_eq!(instruction, InstructionSet::ADDP(InnerData::INT(1)));
+
+    let instruction = InstructionSet::from_int(54, Some(InnerData::INT(1)), None);
+    assert_eq!(instruction, InstructionSet::SUBP(InnerData::INT(1)));
+
+    let instruction = InstructionSet::from_int(55, Some(InnerData::INT(1)), None);
+    assert_eq!(instruction, InstructionSet::MULP(InnerData::INT(1)));
+
+    let instruction = InstructionSet::from_int(56, Some(InnerData::INT(1)), None);
+    assert_eq!(instruction, InstructionSet::DIVP(InnerData::INT(1)));
+
+    let instruction = InstructionSet::from_int(57, Some(InnerData::INT(1)), None);
+    assert_eq!(instruction, InstructionSet::MODP(InnerData::INT(1)));
+
+    let instruction = InstructionSet::from_int(58, Some(InnerData::INT(1)), None);
+    assert_eq!(instruction, InstructionSet::NEGP(InnerData::INT(1)));
+
+    let instruction = InstructionSet::from_int(59, Some(InnerData::INT(1)), None);
+    assert_eq!(instruction, InstructionSet::WIDEN(InnerData::INT(1)));
+
+    let instruction = InstructionSet::from_int(60, Some(InnerData::INT(1)), None);
+    assert_eq!(instruction, InstructionSet::NARROW(InnerData::INT(1)));
+
+    let instruction = InstructionSet::from_int(61, Some(InnerData::INT(1)), None);
+    assert_eq!(instruction, InstructionSet::NARROWC(InnerData::INT(1)));
+
+    let instruction = InstructionSet::from_int(62, Some(InnerData::INT(1)), None);
+    assert_eq!(instruction, InstructionSet::SEXT(InnerData::INT(1)));
+
+    let instruction = InstructionSet::from_int(63, Some(InnerData::INT(1)), None);
+    assert_eq!(instruction, InstructionSet::ZEXT(InnerData::INT(1)));
+
+    let instruction = InstructionSet::from_int(64, None, None);
+    assert_eq!(instruction, InstructionSet::PARSE);
+
+    let instruction = InstructionSet::from_int(65, None, None);
+    assert_eq!(instruction, InstructionSet::TOSTR);
+
+    let instruction = InstructionSet::from_int(66, None, None);
+    assert_eq!(instruction, InstructionSet::LEN);
+
+    let instruction = InstructionSet::from_int(67, None, None);
+    assert_eq!(instruction, InstructionSet::CHARAT);
+
+    let instruction = InstructionSet::from_int(68, None, None);
+    assert_eq!(instruction, InstructionSet::SUBSTR);
+
+    let instruction = InstructionSet::from_int(69, None, None);
+    assert_eq!(instruction, InstructionSet::FIND);
+
+    let instruction = InstructionSet::from_int(70, None, None);
+    assert_eq!(instruction, InstructionSet::SPLIT);
+
+    let instruction = InstructionSet::from_int(71, None, None);
+    assert_eq!(instruction, InstructionSet::UPPER);
+
+    let instruction = InstructionSet::from_int(72, None, None);
+    assert_eq!(instruction, InstructionSet::LOWER);
+
+    let instruction = InstructionSet::from_int(73, None, None);
+    assert_eq!(instruction, InstructionSet::TRIM);
+
+    let instruction = InstructionSet::from_int(74, None, None);
+    assert_eq!(instruction, InstructionSet::ORD);
+
+    let instruction = InstructionSet::from_int(75, None, None);
+    assert_eq!(instruction, InstructionSet::CHR);
+
+    let instruction = InstructionSet::from_int(76, Some(InnerData::INT(2)), None);
+    assert_eq!(instruction, InstructionSet::FORMAT(InnerData::INT(2)));
+
+    let instruction = InstructionSet::from_int(77, None, None);
+    assert_eq!(instruction, InstructionSet::PRINT);
+
+    let instruction = InstructionSet::from_int(78, Some(InnerData::INT(3)), None);
+    assert_eq!(instruction, InstructionSet::NEWARRAY(InnerData::INT(3)));
+
+    let instruction = InstructionSet::from_int(79, None, None);
+    assert_eq!(instruction, InstructionSet::ALOAD);
+
+    let instruction = InstructionSet::from_int(80, None, None);
+    assert_eq!(instruction, InstructionSet::ASTORE);
+
+    let instruction = InstructionSet::from_int(81, None, None);
+    assert_eq!(instruction, InstructionSet::ALEN);
+
+    let instruction = InstructionSet::from_int(82, None, None);
+    assert_eq!(instruction, InstructionSet::APPEND);
+
+    let instruction = InstructionSet::from_int(83, Some(InnerData::INT(0)), None);
+    assert_eq!(instruction, InstructionSet::NEW(InnerData::INT(0)));
+
+    let instruction = InstructionSet::from_int(84, Some(InnerData::INT(1)), None);
+    assert_eq!(instruction, InstructionSet::GETFIELD(InnerData::INT(1)));
+
+    let instruction = InstructionSet::from_int(85, Some(InnerData::INT(1)), None);
+    assert_eq!(instruction, InstructionSet::SETFIELD(InnerData::INT(1)));
+
+    let instruction = InstructionSet::from_int(86, None, None);
+    assert_eq!(instruction, InstructionSet::MAPNEW);
+
+    let instruction = InstructionSet::from_int(87, None, None);
+    assert_eq!(instruction, InstructionSet::MAPGET);
+
+    let instruction = InstructionSet::from_int(88, None, None);
+    assert_eq!(instruction, InstructionSet::MAPSET);
+
+    let instruction = InstructionSet::from_int(89, None, None);
+    assert_eq!(instruction, InstructionSet::MAPHAS);
+
+    let instruction = InstructionSet::from_int(90, None, None);
+    assert_eq!(instruction, InstructionSet::MAPDEL);
+
+    let instruction = InstructionSet::from_int(91, None, None);
+    assert_eq!(instruction, InstructionSet::MAPKEYS);
+
+    let instruction = InstructionSet::from_int(92, Some(InnerData::INT(4)), None);
+    assert_eq!(instruction, InstructionSet::MLOAD(InnerData::INT(4)));
+
+    let instruction = InstructionSet::from_int(93, Some(InnerData::INT(4)), None);
+    assert_eq!(instruction, InstructionSet::MSTORE(InnerData::INT(4)));
+
+    let instruction = InstructionSet::from_int(94, None, None);
+    assert_eq!(instruction, InstructionSet::MSIZE);
+
+    let instruction = InstructionSet::from_int(95, Some(InnerData::INT(4)), Some(InnerData::INT(2)));
+    assert_eq!(instruction, InstructionSet::CALLF(InnerData::INT(4), InnerData::INT(2)));
+
+    let instruction = InstructionSet::from_int(96, Some(InnerData::INT(1)), None);
+    assert_eq!(instruction, InstructionSet::RETF(InnerData::INT(1)));
+
+    let instruction = InstructionSet::from_int(97, Some(InnerData::INT(0)), None);
+    assert_eq!(instruction, InstructionSet::LOADLOCAL(InnerData::INT(0)));
+
+    let instruction = InstructionSet::from_int(98, Some(InnerData::INT(0)), None);
+    assert_eq!(instruction, InstructionSet::STORELOCAL(InnerData::INT(0)));
+
+    let instruction = InstructionSet::from_int(99, Some(InnerData::INT(4)), None);
+    assert_eq!(instruction, InstructionSet::FUNCREF(InnerData::INT(4)));
+
+    let instruction = InstructionSet::from_int(100, Some(InnerData::INT(1)), None);
+    assert_eq!(instruction, InstructionSet::CALLI(InnerData::INT(1)));
+}
+#[test]
+fn test_instruction_operands() {
+    assert_eq!(InstructionSet::operands(0), Some(Operands::ValueAndOffset));
+    assert_eq!(InstructionSet::operands(9), Some(Operands::ValueAndOffset));
+    assert_eq!(InstructionSet::operands(95), Some(Operands::LabelAndCount));
+    assert_eq!(InstructionSet::operands(8), Some(Operands::One));
+    assert_eq!(InstructionSet::operands(100), Some(Operands::One));
+    assert_eq!(InstructionSet::operands(20), Some(Operands::Zero));
+    assert_eq!(InstructionSet::operands(94), Some(Operands::Zero));
+    assert_eq!(InstructionSet::operands(101), None);
+
+    // Every opcode with operands is decoded with them.
+    for opcode in 0..=100 {
+        let (arg, arg1) = match InstructionSet::operands(opcode).unwrap() {
+            Operands::Zero => (None, None),
+            Operands::One => (Some(InnerData::INT(1)), None),
+            Operands::ValueAndOffset => (Some(InnerData::INT(1)), Some(InnerData::INT(2))),
+            Operands::LabelAndCount => (Some(InnerData::INT(1)), Some(InnerData::INT(2))),
+        };
+
+        assert_eq!(InstructionSet::from_int(opcode, arg, arg1).opcode(), opcode);
+    }
+}
@@ -1,4 +1,4 @@
-use yamini::memory::{Stack, ProgramMemory, InnerData, DataMemory};
+use yamini::memory::{Stack, ProgramMemory, InnerData, DataMemory, Heap, HeapObject, LinearMemory};
 use yamini::instructions::InstructionSet;
 
 #[test]
@@ -31,6 +31,58 @@ fn test_stack_top() {
     assert_eq!(*stack.top(), InnerData::INT(4));
 }
 
+#[test]
+fn test_stack_peek() {
+    let mut stack = Stack::new();
+    stack.push(InnerData::INT(3));
+    stack.push(InnerData::INT(4));
+
+    assert_eq!(stack.peek(0), Some(&InnerData::INT(4)));
+    assert_eq!(stack.peek(1), Some(&InnerData::INT(3)));
+    assert_eq!(stack.peek(2), None);
+}
+
+#[test]
+fn test_stack_roll() {
+    let mut stack = Stack::new();
+    stack.push(InnerData::INT(1));
+    stack.push(InnerData::INT(2));
+    stack.push(InnerData::INT(3));
+
+    assert!(stack.roll(2));
+    assert_eq!(stack.data(), &[InnerData::INT(2), InnerData::INT(3), InnerData::INT(1)]);
+    assert_eq!(stack.head(), 3);
+
+    assert!(!stack.roll(3));
+}
+
+#[test]
+fn test_stack_undo_writes() {
+    let mut stack = Stack::new();
+    for value in 1..=4 {
+        stack.push(InnerData::INT(value));
+    }
+    stack.record_writes(true);
+
+    stack.pop();
+    stack.push(InnerData::INT(5));
+    stack.set(1, InnerData::INT(6));
+    stack.roll(2);
+    stack.insert(2, [InnerData::INT(7)]);
+
+    let (len, tail) = stack.take_writes();
+    assert_eq!(len, 1);
+    assert_eq!(tail, vec![InnerData::INT(2), InnerData::INT(3), InnerData::INT(4)]);
+
+    stack.record_writes(false);
+    stack.truncate(len);
+    for value in tail {
+        stack.push(value);
+    }
+
+    assert_eq!(stack.data(), &[InnerData::INT(1), InnerData::INT(2), InnerData::INT(3), InnerData::INT(4)]);
+}
+
 #[test]
 fn test_program_memory_get_value() {
     let mut memory = ProgramMemory::new();
@@ -63,6 +115,7 @@ fn test_program_memory_add_value() {
 }
 
 #[test]
+#[allow(clippy::vec_init_then_push)]
 fn test_program_memory_load_program() {
     let mut memory = ProgramMemory::new();
     let mut program = Vec::new();
@@ -111,4 +164,198 @@ fn test_data_memory_set_var_value_existing() {
     memory.set_var_value(0, InnerData::INT(4));
 
     assert_eq!(memory.get_var_value(0), &InnerData::INT(4));
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_data_memory_wide_indices() {
+    let mut memory = DataMemory::new();
+
+    memory.set_var_value(70000, InnerData::INT(1));
+    memory.set_var_value(3, InnerData::INT(2));
+
+    assert_eq!(memory.len(), 2);
+    assert_eq!(memory.get(70000), Some(&InnerData::INT(1)));
+    assert_eq!(memory.get(69999), None);
+    assert_eq!(memory.get(u32::MAX), None);
+    assert_eq!(memory.slots().collect::<Vec<_>>(), vec![(3, &InnerData::INT(2)), (70000, &InnerData::INT(1))]);
+
+    // The top slot is as cheap to write as any other.
+    memory.set_var_value(u32::MAX, InnerData::INT(3));
+    assert_eq!(memory.len(), 3);
+    assert_eq!(memory.get(u32::MAX), Some(&InnerData::INT(3)));
+    assert_eq!(memory.slots().last(), Some((u32::MAX, &InnerData::INT(3))));
+}
+
+#[test]
+fn test_data_memory_undo_writes() {
+    let mut memory = DataMemory::new();
+    memory.set_var_value(1, InnerData::INT(5));
+    memory.record_writes(true);
+
+    memory.set_var_value(1, InnerData::INT(6));
+    memory.set_var_value(300, InnerData::INT(7));
+    memory.set_var_value(u32::MAX, InnerData::INT(8));
+
+    for (idx, previous) in memory.take_writes().into_iter().rev() {
+        memory.undo(idx, previous);
+    }
+
+    assert_eq!(memory.len(), 1);
+    assert_eq!(memory.get(1), Some(&InnerData::INT(5)));
+    assert_eq!(memory.get(300), None);
+    assert_eq!(memory.get(u32::MAX), None);
+}
+#[test]
+fn test_heap_alloc_and_set_element() {
+    let mut heap = Heap::new();
+
+    let array = heap.alloc(HeapObject::Array(vec![InnerData::INT(0); 2]));
+    assert_eq!(array, InnerData::REF(0));
+
+    assert!(heap.set_element(0, 1, InnerData::INT(5)));
+    assert!(!heap.set_element(0, 2, InnerData::INT(5)));
+    assert!(heap.push_element(0, InnerData::INT(6)));
+    assert!(!heap.push_element(1, InnerData::INT(6)));
+
+    assert_eq!(heap.get(0), Some(&HeapObject::Array(vec![InnerData::INT(0), InnerData::INT(5), InnerData::INT(6)])));
+    assert_eq!(heap.cells(), 3);
+}
+
+#[test]
+fn test_heap_render_nested_and_cyclic() {
+    let mut heap = Heap::new();
+
+    let inner = heap.alloc(HeapObject::Array(vec![InnerData::STR("a".to_string()), InnerData::BOOL(true)]));
+    let outer = heap.alloc(HeapObject::Array(vec![InnerData::INT(1), inner]));
+    heap.push_element(1, outer.clone());
+
+    assert_eq!(heap.render(&outer), "[1, [a, true], [...]]");
+    assert_eq!(heap.render(&InnerData::INT16(300)), "300");
+}
+
+#[test]
+fn test_heap_map_keys_compare_across_widths() {
+    let mut heap = Heap::new();
+    let map = heap.alloc(HeapObject::Map(Default::default()));
+
+    assert!(heap.map_insert(0, InnerData::INT(5), InnerData::STR("a".to_string())));
+    assert!(heap.map_insert(0, InnerData::U64(5), InnerData::STR("b".to_string())));
+    assert!(heap.map_insert(0, InnerData::STR("5".to_string()), InnerData::INT(1)));
+    assert!(!heap.map_insert(0, InnerData::FLOAT(5.0), InnerData::INT(1)));
+    assert_eq!(heap.cells(), 4);
+
+    // The first key stored for a value is kept.
+    assert_eq!(heap.render(&map), "{5: b, 5: 1}");
+
+    assert_eq!(heap.map_remove(0, &InnerData::INT16(5)), Some(InnerData::STR("b".to_string())));
+    assert_eq!(heap.map_remove(0, &InnerData::INT16(5)), None);
+    assert_eq!(heap.cells(), 2);
+}
+
+#[test]
+fn test_heap_undo_map_writes() {
+    let mut heap = Heap::new();
+    heap.alloc(HeapObject::Map(Default::default()));
+    heap.map_insert(0, InnerData::INT(1), InnerData::INT(10));
+
+    heap.record_writes(true);
+    heap.map_insert(0, InnerData::INT(1), InnerData::INT(11));
+    heap.map_insert(0, InnerData::INT(2), InnerData::INT(20));
+    heap.map_remove(0, &InnerData::INT(1));
+
+    for write in heap.take_writes().iter().rev() {
+        heap.undo(write);
+    }
+
+    assert_eq!(heap.render(&InnerData::REF(0)), "{1: 10}");
+    assert_eq!(heap.cells(), 2);
+}
+
+#[test]
+fn test_heap_collect_frees_unreachable_objects() {
+    let mut heap = Heap::new();
+
+    let kept = heap.alloc(HeapObject::Array(vec![InnerData::INT(1)]));
+    let cycle = heap.alloc(HeapObject::Array(vec![]));
+    heap.push_element(1, cycle.clone());
+    let child = heap.alloc(HeapObject::Array(vec![InnerData::INT(2), InnerData::INT(3)]));
+    heap.push_element(0, child);
+
+    heap.collect([kept.clone()].iter());
+
+    assert_eq!(heap.len(), 2);
+    assert!(heap.get(1).is_none());
+    assert_eq!(heap.render(&kept), "[1, [2, 3]]");
+    assert_eq!(heap.cells(), 4);
+    assert_eq!(heap.stats().collections, 1);
+    assert_eq!(heap.stats().objects_freed, 1);
+    assert_eq!(heap.stats().cells_freed, 1);
+
+    // The freed slot is reused by the next allocation.
+    assert_eq!(heap.alloc(HeapObject::Array(vec![])), InnerData::REF(1));
+}
+
+#[test]
+fn test_heap_undo_collection() {
+    let mut heap = Heap::new();
+    heap.alloc(HeapObject::Array(vec![InnerData::INT(1)]));
+    heap.alloc(HeapObject::Array(vec![InnerData::INT(2)]));
+
+    heap.record_writes(true);
+    heap.collect(std::iter::empty());
+    assert!(heap.is_empty());
+    assert!(heap.objects().is_empty());
+
+    for write in heap.take_writes().iter().rev() {
+        heap.undo(write);
+    }
+
+    assert_eq!(heap.len(), 2);
+    assert_eq!(heap.cells(), 2);
+    assert_eq!(heap.render(&InnerData::REF(1)), "[2]");
+}
+
+#[test]
+fn test_data_memory_collect_garbage_roots() {
+    let mut data_memory = DataMemory::new();
+
+    let slot = data_memory.heap_mut().alloc(HeapObject::Array(vec![]));
+    let stack = data_memory.heap_mut().alloc(HeapObject::Array(vec![]));
+    data_memory.heap_mut().alloc(HeapObject::Array(vec![]));
+    data_memory.set_var_value(4, slot);
+
+    data_memory.collect_garbage(&[&[stack]]);
+
+    assert_eq!(data_memory.heap().len(), 2);
+    assert!(data_memory.heap().get(2).is_none());
+}
+
+#[test]
+fn test_linear_memory_bounds() {
+    let mut memory = LinearMemory::new(8);
+
+    assert!(memory.write(4, &[1, 2, 3, 4]));
+    assert_eq!(memory.read(4, 4), Some(&[1, 2, 3, 4][..]));
+    assert_eq!(memory.read(0, 2), Some(&[0, 0][..]));
+
+    assert!(!memory.write(6, &[9, 9, 9]));
+    assert_eq!(memory.read(6, 2), Some(&[3, 4][..]));
+    assert_eq!(memory.read(5, 4), None);
+    assert_eq!(memory.read(usize::MAX, 2), None);
+}
+
+#[test]
+fn test_linear_memory_undo_writes() {
+    let mut memory = LinearMemory::new(4);
+    memory.write(0, &[7, 7]);
+    memory.record_writes(true);
+
+    memory.write(1, &[1, 2, 3]);
+    memory.write(0, &[5]);
+
+    for (address, previous) in memory.take_writes().iter().rev() {
+        memory.undo(*address, previous);
+    }
+
+    assert_eq!(memory.bytes(), &[7, 7, 0, 0]);
+}
@@ -1,6 +1,9 @@
-use yamini::processor::Processor;
-use yamini::memory::{Stack, ProgramMemory, InnerData, DataMemory};
-use yamini::instructions::InstructionSet;
+use std::io;
+
+use yamini::processor::{Processor, VmError, Limits, OverflowPolicy, Frame};
+use yamini::memory::{Stack, ProgramMemory, InnerData, DataMemory, BigInt, StructType};
+use yamini::instructions::{InstructionSet, Operands};
+use yamini::vm::Vm;
 
 
 #[test]
@@ -14,8 +17,9 @@ fn test_execute_load() {
         &mut DataMemory::new(),
         &mut stack, 
         &mut Stack::new(),
+        &mut io::empty(),
         &mut Vec::new()
-    );
+    ).unwrap();
 
     assert_eq!(stack.data(), &[InnerData::INT(3)]);
     assert_eq!(stack.head(), 1);
@@ -34,8 +38,9 @@ fn test_execute_add() {
         &mut DataMemory::new(),
         &mut stack, 
         &mut Stack::new(), 
+        &mut io::empty(), 
         &mut Vec::new()
-    );
+    ).unwrap();
 
     assert_eq!(stack.data(), &[InnerData::INT(7)]);
     assert_eq!(stack.head(), 1);
@@ -54,8 +59,9 @@ fn test_execute_sub() {
         &mut DataMemory::new(),
         &mut stack, 
         &mut Stack::new(), 
+        &mut io::empty(), 
         &mut Vec::new()
-    );
+    ).unwrap();
 
     assert_eq!(stack.data(), &[InnerData::INT(-1)]);
     assert_eq!(stack.head(), 1);
@@ -74,8 +80,9 @@ fn test_execute_mul() {
         &mut DataMemory::new(),
         &mut stack, 
         &mut Stack::new(), 
+        &mut io::empty(), 
         &mut Vec::new()
-    );
+    ).unwrap();
 
     assert_eq!(stack.data(), &[InnerData::INT(12)]);
     assert_eq!(stack.head(), 1);
@@ -94,8 +101,9 @@ fn test_execute_div() {
         &mut DataMemory::new(),
         &mut stack, 
         &mut Stack::new(), 
+        &mut io::empty(), 
         &mut Vec::new()
-    );
+    ).unwrap();
 
     assert_eq!(stack.data(), &[InnerData::INT(3)]);
     assert_eq!(stack.head(), 1);
@@ -115,8 +123,9 @@ fn test_execute_halt() {
         &mut DataMemory::new(),
         &mut stack, 
         &mut Stack::new(), 
+        &mut io::empty(), 
         &mut stdout
-    );
+    ).unwrap();
 
     assert_eq!(stack.data(), &[InnerData::INT(3)]);
     assert_eq!(stack.head(), 1);
@@ -135,8 +144,9 @@ fn test_execute_mod() {
         &mut DataMemory::new(),
         &mut stack, 
         &mut Stack::new(), 
+        &mut io::empty(), 
         &mut Vec::new()
-    );
+    ).unwrap();
 
     assert_eq!(stack.data(), &[InnerData::INT(2)]);
     assert_eq!(stack.head(), 1);
@@ -152,8 +162,9 @@ fn test_execute_loadlabel() {
         &mut DataMemory::new(),
         &mut stack, 
         &mut Stack::new(), 
+        &mut io::empty(), 
         &mut Vec::new()
-    );
+    ).unwrap();
 
     assert_eq!(stack.data(), &[]);
     assert_eq!(stack.head(), 0);
@@ -169,8 +180,9 @@ fn test_execute_jmp() {
         &mut DataMemory::new(),
         &mut stack, 
         &mut Stack::new(),  
+        &mut io::empty(),  
         &mut Vec::new()
-    );
+    ).unwrap();
 
     assert_eq!(stack.data(), &[]);
     assert_eq!(stack.head(), 0);
@@ -188,8 +200,9 @@ fn test_execute_neg() {
         &mut DataMemory::new(),
         &mut stack, 
         &mut Stack::new(), 
+        &mut io::empty(), 
         &mut Vec::new()
-    );
+    ).unwrap();
 
     assert_eq!(stack.data(), &[InnerData::INT(-3)]);
     assert_eq!(stack.head(), 1);
@@ -205,16 +218,18 @@ fn test_execute_popregister() {
         &mut DataMemory::new(),
         &mut stack, 
         &mut Stack::new(),
+        &mut io::empty(),
         &mut Vec::new()
-    );
+    ).unwrap();
 
     processor.execute(
         &InstructionSet::POP(InnerData::INT(2), 1), 
         &mut DataMemory::new(),
         &mut stack, 
         &mut Stack::new(),
+        &mut io::empty(),
         &mut Vec::new()
-    );
+    ).unwrap();
 
     assert_eq!(stack.data(), &[]);
     assert_eq!(stack.head(), 0);
@@ -230,8 +245,9 @@ fn test_execute_jz() {
         &mut DataMemory::new(),
         &mut stack, 
         &mut Stack::new(),
+        &mut io::empty(),
         &mut Vec::new()
-    );
+    ).unwrap();
 
     assert_eq!(stack.data(), &[]);
     assert_eq!(stack.head(), 0);
@@ -247,8 +263,9 @@ fn test_execute_jn() {
         &mut DataMemory::new(),
         &mut stack, 
         &mut Stack::new(),
+        &mut io::empty(),
         &mut Vec::new()
-    );
+    ).unwrap();
 
     assert_eq!(stack.data(), &[]);
     assert_eq!(stack.head(), 0);
@@ -268,8 +285,9 @@ fn test_execute_show() {
         &mut DataMemory::new(),
         &mut stack, 
         &mut Stack::new(), 
+        &mut io::empty(), 
         &mut stdout
-    );
+    ).unwrap();
 
     assert_eq!(stack.data(), &[]);
     assert_eq!(stack.head(), 0);
@@ -290,8 +308,9 @@ fn test_execute_ret() {
         &mut DataMemory::new(),
         &mut stack, 
         &mut Stack::new(), 
+        &mut io::empty(), 
         &mut stdout
-    );
+    ).unwrap();
 
     assert_eq!(stack.data(), &[]);
     assert_eq!(stack.head(), 0);
@@ -310,8 +329,9 @@ fn test_execute_call() {
         &mut DataMemory::new(),
         &mut stack, 
         &mut Stack::new(),
+        &mut io::empty(),
         &mut stdout
-    );
+    ).unwrap();
 
     assert_eq!(stack.data(), &[]);
     assert_eq!(stack.head(), 0);
@@ -330,10 +350,11 @@ fn test_execute_equ() {
         &mut DataMemory::new(),
         &mut stack, 
         &mut Stack::new(), 
+        &mut io::empty(), 
         &mut Vec::new()
-    );
+    ).unwrap();
 
-    assert_eq!(stack.data(), &[InnerData::INT(1)]);
+    assert_eq!(stack.data(), &[InnerData::BOOL(true)]);
     assert_eq!(stack.head(), 1);
 }
 
@@ -349,8 +370,9 @@ fn test_execute_deref() {
         &mut DataMemory::new(),
         &mut stack, 
         &mut Stack::new(), 
+        &mut io::empty(), 
         &mut Vec::new()
-    );
+    ).unwrap();
 
     assert_eq!(stack.data(), &[InnerData::INT(0)]);
     assert_eq!(stack.head(), 1);
@@ -358,12 +380,12 @@ fn test_execute_deref() {
 
 #[test]
 fn test_execute_program() {
-    let mut program = Vec::new();
-
-    program.push(InstructionSet::LOAD(InnerData::INT(3), 2));
-    program.push(InstructionSet::LOAD(InnerData::INT(4), 2));
-    program.push(InstructionSet::MUL);
-    program.push(InstructionSet::HALT);
+    let program = vec![
+        InstructionSet::LOAD(InnerData::INT(3), 2),
+        InstructionSet::LOAD(InnerData::INT(4), 2),
+        InstructionSet::MUL,
+        InstructionSet::HALT,
+    ];
 
     let mut stack = Stack::new();
 
@@ -375,13 +397,2071 @@ fn test_execute_program() {
     let mut stdout = Vec::new();
 
     processor.execute_program(
-        memory, 
+        &memory,
         &mut DataMemory::new(),
         &mut stack, 
         &mut Stack::new(), 
+        &mut io::empty(), 
         &mut stdout
-    );
+    ).unwrap();
 
     assert_eq!(stack.data(), &[InnerData::INT(12)]);
     assert_eq!(stack.head(), 1);
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_execute_program_out_of_fuel() {
+    let program = vec![
+        InstructionSet::LABEL,
+        InstructionSet::JMP(InnerData::INT(0)),
+    ];
+
+    let mut memory = ProgramMemory::new();
+    memory.load_program(program);
+
+    let mut processor = Processor::new();
+    processor.set_fuel(Some(10));
+
+    let result = processor.execute_program(
+        &memory,
+        &mut DataMemory::new(),
+        &mut Stack::new(),
+        &mut Stack::new(),
+        &mut io::empty(),
+        &mut Vec::new()
+    );
+
+    assert_eq!(result, Err(VmError::OutOfFuel));
+    assert_eq!(processor.fuel(), Some(0));
+}
+
+#[test]
+fn test_execute_program_resume_after_refuel() {
+    let program = vec![
+        InstructionSet::LOAD(InnerData::INT(3), 2),
+        InstructionSet::LOAD(InnerData::INT(4), 2),
+        InstructionSet::ADD,
+        InstructionSet::SHOW,
+        InstructionSet::HALT,
+    ];
+
+    let mut memory = ProgramMemory::new();
+    memory.load_program(program);
+
+    let mut data_memory = DataMemory::new();
+    let mut stack = Stack::new();
+    let mut call_stack = Stack::new();
+    let mut stdout = Vec::new();
+
+    let mut processor = Processor::new();
+    processor.set_fuel(Some(2));
+
+    let result = processor.execute_program(
+        &memory, &mut data_memory, &mut stack, &mut call_stack, &mut io::empty(), &mut stdout
+    );
+
+    assert_eq!(result, Err(VmError::OutOfFuel));
+    assert_eq!(processor.pc(), 2);
+    assert_eq!(stack.data(), &[InnerData::INT(3), InnerData::INT(4)]);
+
+    processor.add_fuel(10);
+
+    let result = processor.execute_program(
+        &memory, &mut data_memory, &mut stack, &mut call_stack, &mut io::empty(), &mut stdout
+    );
+
+    assert_eq!(result, Ok(()));
+    assert_eq!(processor.fuel(), Some(7));
+    assert_eq!(String::from_utf8(stdout).unwrap(), "7\n");
+}
+
+#[test]
+fn test_execute_program_instruction_cost() {
+    let program = vec![
+        InstructionSet::LOAD(InnerData::INT(3), 2),
+        InstructionSet::LOAD(InnerData::INT(4), 2),
+        InstructionSet::MUL,
+        InstructionSet::HALT,
+    ];
+
+    let mut memory = ProgramMemory::new();
+    memory.load_program(program);
+
+    let mut stack = Stack::new();
+
+    let mut processor = Processor::new();
+    processor.set_instruction_cost(InstructionSet::MUL.opcode(), 5);
+    processor.set_fuel(Some(6));
+
+    let result = processor.execute_program(
+        &memory,
+        &mut DataMemory::new(),
+        &mut stack,
+        &mut Stack::new(),
+        &mut io::empty(),
+        &mut Vec::new()
+    );
+
+    assert_eq!(result, Err(VmError::OutOfFuel));
+    assert_eq!(processor.pc(), 2);
+    assert_eq!(processor.fuel(), Some(4));
+}
+
+#[test]
+fn test_execute_stack_depth_limit() {
+    let mut stack = Stack::new();
+    stack.push(InnerData::INT(3));
+
+    let mut processor = Processor::new();
+    processor.set_limits(Limits { max_stack_depth: 1, ..Limits::new() });
+
+    let result = processor.execute(
+        &InstructionSet::LOAD(InnerData::INT(4), 2),
+        &mut DataMemory::new(),
+        &mut stack,
+        &mut Stack::new(),
+        &mut io::empty(),
+        &mut Vec::new()
+    );
+
+    assert_eq!(result, Err(VmError::StackOverflow));
+    assert_eq!(stack.head(), 1);
+
+    // SPLIT only knows how many pieces it pushes once it has split.
+    let mut stack = Stack::new();
+    stack.push(InnerData::STR("a,b,c".to_string()));
+    stack.push(InnerData::STR(",".to_string()));
+    processor.set_limits(Limits { max_stack_depth: 3, ..Limits::new() });
+
+    let result = processor.execute(
+        &InstructionSet::SPLIT,
+        &mut DataMemory::new(),
+        &mut stack,
+        &mut Stack::new(),
+        &mut io::empty(),
+        &mut Vec::new()
+    );
+
+    assert_eq!(result, Err(VmError::StackOverflow));
+    assert_eq!(stack.head(), 0);
+}
+
+#[test]
+fn test_execute_call_depth_limit() {
+    let program = vec![
+        InstructionSet::LABEL,
+        InstructionSet::CALL(InnerData::INT(0)),
+    ];
+
+    let mut memory = ProgramMemory::new();
+    memory.load_program(program);
+
+    let mut call_stack = Stack::new();
+
+    let mut processor = Processor::new();
+    processor.set_limits(Limits { max_call_depth: 16, ..Limits::new() });
+
+    let result = processor.execute_program(
+        &memory,
+        &mut DataMemory::new(),
+        &mut Stack::new(),
+        &mut call_stack,
+        &mut io::empty(),
+        &mut Vec::new()
+    );
+
+    assert_eq!(result, Err(VmError::CallStackOverflow));
+    assert_eq!(call_stack.head(), 16);
+}
+
+#[test]
+fn test_execute_data_slots_limit() {
+    let mut data_memory = DataMemory::new();
+    data_memory.set_var_value(0, InnerData::INT(1));
+
+    let mut stack = Stack::new();
+    stack.push(InnerData::INT(3));
+    stack.push(InnerData::INT(4));
+
+    let mut processor = Processor::new();
+    processor.set_limits(Limits { max_data_slots: 1, ..Limits::new() });
+
+    processor.execute(
+        &InstructionSet::POP(InnerData::INT(0), 4),
+        &mut data_memory,
+        &mut stack,
+        &mut Stack::new(),
+        &mut io::empty(),
+        &mut Vec::new()
+    ).unwrap();
+
+    let result = processor.execute(
+        &InstructionSet::POP(InnerData::INT(1), 4),
+        &mut data_memory,
+        &mut stack,
+        &mut Stack::new(),
+        &mut io::empty(),
+        &mut Vec::new()
+    );
+
+    assert_eq!(result, Err(VmError::DataMemoryExhausted));
+}
+
+#[test]
+fn test_execute_wide_data_slots() {
+    let program = vec![
+        InstructionSet::LOAD(InnerData::INT(9), 2),
+        InstructionSet::POP(InnerData::U32(100000), 4),
+        // Slot 300 holds a pointer to slot 100000.
+        InstructionSet::LOAD(InnerData::U32(100000), 5),
+        InstructionSet::POP(InnerData::INT16(300), 4),
+        InstructionSet::LOAD(InnerData::INT(4), 2),
+        InstructionSet::POP(InnerData::INT16(300), 6),
+        InstructionSet::LOAD(InnerData::INT16(300), 4),
+        InstructionSet::DEREF,
+        InstructionSet::LOAD(InnerData::INT(16), 5),
+        InstructionSet::HALT,
+    ];
+
+    let mut memory = ProgramMemory::new();
+    memory.load_program(program);
+
+    let mut data_memory = DataMemory::new();
+    let mut stack = Stack::new();
+
+    Processor::new().execute_program(
+        &memory, &mut data_memory, &mut stack, &mut Stack::new(), &mut io::empty(), &mut Vec::new()
+    ).unwrap();
+
+    assert_eq!(stack.data(), &[InnerData::INT(4), InnerData::INT16(128)]);
+    assert_eq!(data_memory.get(300), Some(&InnerData::INT32(800000)));
+    assert_eq!(data_memory.len(), 2);
+}
+
+#[test]
+fn test_execute_slot_addresses() {
+    let (_, result, _) = execute(&mut Processor::new(), &mut DataMemory::new(), vec![InnerData::INT(-8)],
+                                 vec![InstructionSet::DEREF]);
+    assert_eq!(result, Err(VmError::MemoryFault));
+
+    let (_, result, _) = execute(&mut Processor::new(), &mut DataMemory::new(), vec![InnerData::FLOAT(8.0)],
+                                 vec![InstructionSet::DEREF]);
+    assert_eq!(result, Err(VmError::TypeMismatch));
+
+    let (_, result, _) = execute(&mut Processor::new(), &mut DataMemory::new(), vec![],
+                                 vec![InstructionSet::LOAD(InnerData::INT64(1 << 40), 4)]);
+    assert_eq!(result, Err(VmError::InvalidConversion));
+}
+
+#[test]
+fn test_execute_strict_memory() {
+    let mut data_memory = DataMemory::new();
+    data_memory.set_var_value(1, InnerData::INT(16));
+
+    let mut processor = Processor::new();
+    processor.set_strict_memory(true);
+
+    let mut stack = Stack::new();
+    let mut execute = |instruction: InstructionSet| processor.execute(
+        &instruction,
+        &mut data_memory,
+        &mut stack,
+        &mut Stack::new(),
+        &mut io::empty(),
+        &mut Vec::new()
+    );
+
+    assert_eq!(execute(InstructionSet::LOAD(InnerData::INT(1), 4)), Ok(()));
+    assert_eq!(execute(InstructionSet::DEREF), Err(VmError::UninitializedRead));
+    assert_eq!(execute(InstructionSet::LOAD(InnerData::INT(0), 4)), Err(VmError::UninitializedRead));
+    assert_eq!(execute(InstructionSet::POP(InnerData::INT(0), 6)), Err(VmError::UninitializedRead));
+}
+
+#[test]
+fn test_execute_data_slot_index_limit() {
+    let mut processor = Processor::new();
+    processor.set_limits(Limits { max_data_slots: 1000, ..Limits::new() });
+
+    let mut stack = Stack::new();
+    stack.push(InnerData::INT(1));
+
+    let result = processor.execute(
+        &InstructionSet::POP(InnerData::U32(u32::MAX), 4),
+        &mut DataMemory::new(),
+        &mut stack,
+        &mut Stack::new(),
+        &mut io::empty(),
+        &mut Vec::new()
+    );
+
+    assert_eq!(result, Err(VmError::DataMemoryExhausted));
+
+    // The default limits reject a huge index too, rather than growing data memory.
+    let mut processor = Processor::new();
+    let mut data_memory = DataMemory::new();
+    stack.push(InnerData::INT(1));
+
+    let result = processor.execute(
+        &InstructionSet::POP(InnerData::U32(4_000_000_000), 4),
+        &mut data_memory,
+        &mut stack,
+        &mut Stack::new(),
+        &mut io::empty(),
+        &mut Vec::new()
+    );
+
+    assert_eq!(result, Err(VmError::DataMemoryExhausted));
+    assert!(data_memory.is_empty());
+}
+
+#[test]
+fn test_execute_string_length_limit() {
+    let mut stack = Stack::new();
+    stack.push(InnerData::STR("ab".to_string()));
+    stack.push(InnerData::INT(100));
+
+    let mut processor = Processor::new();
+    processor.set_limits(Limits { max_string_len: 64, ..Limits::new() });
+
+    let result = processor.execute(
+        &InstructionSet::MUL,
+        &mut DataMemory::new(),
+        &mut stack,
+        &mut Stack::new(),
+        &mut io::empty(),
+        &mut Vec::new()
+    );
+
+    assert_eq!(result, Err(VmError::StringTooLong));
+    assert_eq!(stack.head(), 0);
+
+    // Every integer type repeats strings, so every one is checked up front.
+    let counts = vec![
+        InnerData::INT16(1000),
+        InnerData::U32(100000),
+        InnerData::U64(u64::MAX),
+        InnerData::BIGINT(BigInt::parse("100000000000000000000000").unwrap()),
+    ];
+
+    for count in counts {
+        stack.push(count);
+        stack.push(InnerData::STR("ab".to_string()));
+
+        let result = processor.execute(
+            &InstructionSet::MUL,
+            &mut DataMemory::new(),
+            &mut stack,
+            &mut Stack::new(),
+            &mut io::empty(),
+            &mut Vec::new()
+        );
+
+        assert_eq!(result, Err(VmError::StringTooLong));
+    }
+
+    stack.push(InnerData::STR("ab".to_string()));
+    stack.push(InnerData::U16(32));
+
+    let result = processor.execute(
+        &InstructionSet::MUL,
+        &mut DataMemory::new(),
+        &mut stack,
+        &mut Stack::new(),
+        &mut io::empty(),
+        &mut Vec::new()
+    );
+
+    assert_eq!(result, Ok(()));
+    assert_eq!(stack.data(), &[InnerData::STR("ab".repeat(32))]);
+}
+
+#[test]
+fn test_execute_read_invalid_utf8() {
+    let mut processor = Processor::new();
+    processor.set_limits(Limits { max_string_len: 4, ..Limits::new() });
+
+    let cases: Vec<(&[u8], Result<(), VmError>)> = vec![
+        (b"\xffab\n", Err(VmError::InvalidConversion)),
+        // The limit cuts the line inside the two-byte character.
+        ("abc\u{e9}\n".as_bytes(), Err(VmError::StringTooLong)),
+        ("ab\u{e9}\n".as_bytes(), Ok(())),
+    ];
+
+    for (mut input, expected) in cases {
+        let mut stack = Stack::new();
+
+        let result = processor.execute(
+            &InstructionSet::READ,
+            &mut DataMemory::new(),
+            &mut stack,
+            &mut Stack::new(),
+            &mut input,
+            &mut Vec::new()
+        );
+
+        assert_eq!(result, expected);
+        assert_eq!(stack.head(), expected.map_or(0, |_| 1));
+    }
+}
+
+/// Runs `instructions` on `processor` and `data_memory`, one `execute` after the
+/// other, from a stack holding `values`. Stops at the first error. Jumps only move
+/// the pc, so programs that follow them run on a `Vm` instead.
+fn execute(processor: &mut Processor, data_memory: &mut DataMemory, values: Vec<InnerData>,
+           instructions: Vec<InstructionSet>) -> (Stack, Result<(), VmError>, String) {
+    let mut stack = Stack::new();
+    for value in values {
+        stack.push(value);
+    }
+
+    let mut stdout = Vec::new();
+
+    let mut result = Ok(());
+    for instruction in instructions {
+        result = processor.execute(
+            &instruction,
+            data_memory,
+            &mut stack,
+            &mut Stack::new(),
+            &mut io::empty(),
+            &mut stdout
+        );
+
+        if result.is_err() {
+            break;
+        }
+    }
+
+    (stack, result, String::from_utf8(stdout).unwrap())
+}
+
+#[test]
+fn test_execute_equ_mixed_widths() {
+    let (stack, _, _) = execute(
+        &mut Processor::new(), &mut DataMemory::new(),
+        vec![InnerData::INT(5), InnerData::INT16(5)], vec![InstructionSet::EQU]
+    );
+
+    assert_eq!(stack.data(), &[InnerData::BOOL(true)]);
+}
+
+#[test]
+fn test_execute_ne() {
+    let (stack, _, _) = execute(
+        &mut Processor::new(), &mut DataMemory::new(),
+        vec![InnerData::INT(5), InnerData::INT32(6)], vec![InstructionSet::NE]
+    );
+    assert_eq!(stack.data(), &[InnerData::BOOL(true)]);
+
+    let (stack, _, _) = execute(
+        &mut Processor::new(), &mut DataMemory::new(),
+        vec![InnerData::STR("a".to_string()), InnerData::STR("a".to_string())], vec![InstructionSet::NE]
+    );
+    assert_eq!(stack.data(), &[InnerData::BOOL(false)]);
+}
+
+#[test]
+fn test_execute_ordering_comparisons() {
+    let cases = vec![
+        (InstructionSet::LT, InnerData::INT(3), InnerData::INT16(300), true),
+        (InstructionSet::LT, InnerData::INT(3), InnerData::INT(3), false),
+        (InstructionSet::LE, InnerData::INT(3), InnerData::INT(3), true),
+        (InstructionSet::LE, InnerData::INT32(4), InnerData::INT(3), false),
+        (InstructionSet::GT, InnerData::INT32(70000), InnerData::INT16(-3), true),
+        (InstructionSet::GT, InnerData::INT(3), InnerData::INT(3), false),
+        (InstructionSet::GE, InnerData::INT16(3), InnerData::INT(3), true),
+        (InstructionSet::GE, InnerData::INT(-1), InnerData::INT(3), false),
+        (InstructionSet::LT, InnerData::STR("abc".to_string()), InnerData::STR("abd".to_string()), true),
+        (InstructionSet::GE, InnerData::STR("b".to_string()), InnerData::STR("abd".to_string()), true),
+    ];
+
+    for (instruction, a, b, expected) in cases {
+        let (stack, result, _) = execute(&mut Processor::new(), &mut DataMemory::new(), vec![a, b],
+                                         vec![instruction]);
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(stack.data(), &[InnerData::BOOL(expected)]);
+    }
+}
+
+#[test]
+fn test_execute_ordering_type_mismatch() {
+    let (_, result, _) = execute(
+        &mut Processor::new(), &mut DataMemory::new(),
+        vec![InnerData::STR("a".to_string()), InnerData::INT(1)], vec![InstructionSet::LT]
+    );
+
+    assert_eq!(result, Err(VmError::TypeMismatch));
+}
+
+#[test]
+fn test_execute_jt_jf() {
+    let mut processor = Processor::new();
+    let (stack, _, _) = execute(&mut processor, &mut DataMemory::new(), vec![InnerData::BOOL(true)],
+                                vec![InstructionSet::JT(InnerData::INT(7))]);
+    assert_eq!(processor.pc(), 7);
+    assert_eq!(stack.head(), 0);
+
+    let mut processor = Processor::new();
+    let (stack, _, _) = execute(&mut processor, &mut DataMemory::new(), vec![InnerData::BOOL(false)],
+                                vec![InstructionSet::JT(InnerData::INT(7))]);
+    assert_eq!(processor.pc(), 0);
+    assert_eq!(stack.head(), 0);
+
+    let mut processor = Processor::new();
+    let _ = execute(&mut processor, &mut DataMemory::new(), vec![InnerData::BOOL(false)],
+                    vec![InstructionSet::JF(InnerData::INT(7))]);
+    assert_eq!(processor.pc(), 7);
+
+    let (_, result, _) = execute(&mut Processor::new(), &mut DataMemory::new(), vec![InnerData::INT(1)],
+                                 vec![InstructionSet::JT(InnerData::INT(7))]);
+    assert_eq!(result, Err(VmError::TypeMismatch));
+}
+
+#[test]
+fn test_execute_program_conditional_branches() {
+    // Prints 5, 4, ..., 1 by looping while the counter is greater than zero.
+    let program = vec![
+        InstructionSet::LOAD(InnerData::INT(5), 2),
+        InstructionSet::POP(InnerData::INT(0), 4),
+        InstructionSet::LABEL,
+        InstructionSet::LOAD(InnerData::INT(0), 4),
+        InstructionSet::SHOW,
+        InstructionSet::LOAD(InnerData::INT(0), 4),
+        InstructionSet::LOAD(InnerData::INT(1), 2),
+        InstructionSet::SUB,
+        InstructionSet::POP(InnerData::INT(0), 4),
+        InstructionSet::LOAD(InnerData::INT(0), 4),
+        InstructionSet::LOAD(InnerData::INT(0), 2),
+        InstructionSet::GT,
+        InstructionSet::JT(InnerData::INT(2)),
+        InstructionSet::HALT,
+    ];
+
+    let mut memory = ProgramMemory::new();
+    memory.load_program(program);
+
+    let mut stack = Stack::new();
+    let mut stdout = Vec::new();
+
+    Processor::new().execute_program(
+        &memory,
+        &mut DataMemory::new(),
+        &mut stack,
+        &mut Stack::new(),
+        &mut io::empty(),
+        &mut stdout
+    ).unwrap();
+
+    assert_eq!(String::from_utf8(stdout).unwrap(), "5\n4\n3\n2\n1\n");
+    assert_eq!(stack.head(), 0);
+}
+
+#[test]
+fn test_execute_jnz_jp() {
+    let program = vec![
+        InstructionSet::LOAD(InnerData::INT(2), 2),
+        InstructionSet::JP(InnerData::INT(2)),
+        InstructionSet::HALT,
+        InstructionSet::LOAD(InnerData::INT(-2), 2),
+        InstructionSet::JNZ(InnerData::INT(5)),
+        InstructionSet::HALT,
+        InstructionSet::LOAD(InnerData::INT(9), 2),
+        InstructionSet::HALT,
+    ];
+
+    let mut memory = ProgramMemory::new();
+    memory.load_program(program);
+
+    let mut stack = Stack::new();
+
+    Processor::new().execute_program(
+        &memory,
+        &mut DataMemory::new(),
+        &mut stack,
+        &mut Stack::new(),
+        &mut io::empty(),
+        &mut Vec::new()
+    ).unwrap();
+
+    assert_eq!(stack.data(), &[InnerData::INT(2), InnerData::INT(-2), InnerData::INT(9)]);
+}
+
+const ZERO: i8 = 1;
+const NEGATIVE: i8 = 1 << 1;
+const CARRY: i8 = 1 << 2;
+const OVERFLOW: i8 = 1 << 3;
+
+fn flags_after(program: Vec<InstructionSet>) -> i8 {
+    let mut program = program;
+    program.push(InstructionSet::PUSHF);
+    program.push(InstructionSet::HALT);
+
+    let mut memory = ProgramMemory::new();
+    memory.load_program(program);
+
+    let mut stack = Stack::new();
+
+    Processor::new().execute_program(
+        &memory,
+        &mut DataMemory::new(),
+        &mut stack,
+        &mut Stack::new(),
+        &mut io::empty(),
+        &mut Vec::new()
+    ).unwrap();
+
+    stack.top().get_i8()
+}
+
+#[test]
+fn test_flags_zero_and_negative() {
+    let flags = flags_after(vec![InstructionSet::LOAD(InnerData::INT(0), 2)]);
+    assert_eq!(flags, ZERO);
+
+    let flags = flags_after(vec![
+        InstructionSet::LOAD(InnerData::INT(0), 2),
+        InstructionSet::LOAD(InnerData::INT(-1), 2),
+    ]);
+    assert_eq!(flags, NEGATIVE);
+}
+
+#[test]
+fn test_flags_carry_and_overflow() {
+    let flags = flags_after(vec![
+        InstructionSet::LOAD(InnerData::INT(100), 2),
+        InstructionSet::LOAD(InnerData::INT(100), 2),
+        InstructionSet::ADD,
+    ]);
+    assert_eq!(flags, OVERFLOW);
+
+    let flags = flags_after(vec![
+        InstructionSet::LOAD(InnerData::INT(-1), 2),
+        InstructionSet::LOAD(InnerData::INT(1), 2),
+        InstructionSet::ADD,
+    ]);
+    assert_eq!(flags, ZERO | CARRY);
+
+    let flags = flags_after(vec![
+        InstructionSet::LOAD(InnerData::INT16(1), 2),
+        InstructionSet::LOAD(InnerData::INT(2), 2),
+        InstructionSet::SUB,
+    ]);
+    assert_eq!(flags, NEGATIVE | CARRY);
+}
+
+#[test]
+fn test_flags_untouched_by_pop() {
+    let flags = flags_after(vec![
+        InstructionSet::LOAD(InnerData::INT(5), 2),
+        InstructionSet::LOAD(InnerData::INT(0), 2),
+        InstructionSet::POP(InnerData::INT(0), 4),
+    ]);
+
+    assert_eq!(flags, ZERO);
+}
+
+#[test]
+fn test_execute_cmp() {
+    let mut processor = Processor::new();
+    let (stack, result, _) = execute(
+        &mut processor, &mut DataMemory::new(),
+        vec![InnerData::INT(7), InnerData::INT(-128), InnerData::INT(1)], vec![InstructionSet::CMP]
+    );
+
+    assert_eq!(result, Ok(()));
+    assert_eq!(stack.data(), &[InnerData::INT(7)]);
+    assert_eq!(processor.pc(), 0);
+
+    let flags = flags_after(vec![
+        InstructionSet::LOAD(InnerData::INT(-128), 2),
+        InstructionSet::LOAD(InnerData::INT(1), 2),
+        InstructionSet::CMP,
+    ]);
+    assert_eq!(flags, NEGATIVE | OVERFLOW);
+
+    let flags = flags_after(vec![
+        InstructionSet::LOAD(InnerData::INT16(5), 2),
+        InstructionSet::LOAD(InnerData::INT(5), 2),
+        InstructionSet::CMP,
+    ]);
+    assert_eq!(flags, ZERO);
+
+    let (_, result, _) = execute(
+        &mut Processor::new(), &mut DataMemory::new(),
+        vec![InnerData::INT(1), InnerData::STR("a".to_string())], vec![InstructionSet::CMP]
+    );
+    assert_eq!(result, Err(VmError::TypeMismatch));
+}
+
+#[test]
+fn test_execute_flag_jumps() {
+    let program = vec![
+        InstructionSet::LOAD(InnerData::INT(100), 2),
+        InstructionSet::LOAD(InnerData::INT(100), 2),
+        InstructionSet::ADD,
+        InstructionSet::JO(InnerData::INT(4)),
+        InstructionSet::HALT,
+        InstructionSet::JC(InnerData::INT(7)),
+        InstructionSet::JNC(InnerData::INT(7)),
+        InstructionSet::HALT,
+        InstructionSet::JNO(InnerData::INT(10)),
+        InstructionSet::SHOW,
+        InstructionSet::HALT,
+    ];
+
+    let mut memory = ProgramMemory::new();
+    memory.load_program(program);
+
+    let mut stdout = Vec::new();
+
+    Processor::new().execute_program(
+        &memory,
+        &mut DataMemory::new(),
+        &mut Stack::new(),
+        &mut Stack::new(),
+        &mut io::empty(),
+        &mut stdout
+    ).unwrap();
+
+    assert_eq!(String::from_utf8(stdout).unwrap(), "200\n");
+}
+
+#[test]
+fn test_execute_pushf_popf() {
+    let flags = flags_after(vec![
+        InstructionSet::LOAD(InnerData::INT(CARRY | OVERFLOW), 2),
+        InstructionSet::POPF,
+    ]);
+
+    assert_eq!(flags, CARRY | OVERFLOW);
+}
+
+#[test]
+fn test_execute_neg_promotes() {
+    let (stack, _, _) = execute(&mut Processor::new(), &mut DataMemory::new(), vec![InnerData::INT(-128)],
+                                vec![InstructionSet::NEG]);
+
+    assert_eq!(stack.data(), &[InnerData::INT16(128)]);
+}
+
+#[test]
+fn test_execute_bitwise() {
+    let cases = vec![
+        (InstructionSet::AND, InnerData::INT(6), InnerData::INT(3), InnerData::INT(2)),
+        (InstructionSet::OR, InnerData::INT(6), InnerData::INT16(300), InnerData::INT16(302)),
+        (InstructionSet::XOR, InnerData::INT(6), InnerData::INT(3), InnerData::INT(5)),
+        (InstructionSet::SHL, InnerData::INT32(1), InnerData::INT(20), InnerData::INT32(1 << 20)),
+        (InstructionSet::SHR, InnerData::INT16(-256), InnerData::INT(4), InnerData::INT16(-16)),
+        (InstructionSet::USHR, InnerData::INT16(-256), InnerData::INT(4), InnerData::INT16(0x0ff0)),
+    ];
+
+    for (instruction, a, b, expected) in cases {
+        let (stack, result, _) = execute(&mut Processor::new(), &mut DataMemory::new(), vec![a, b],
+                                         vec![instruction]);
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(stack.data(), &[expected]);
+    }
+
+    let (stack, _, _) = execute(&mut Processor::new(), &mut DataMemory::new(), vec![InnerData::INT(0)],
+                                vec![InstructionSet::NOT]);
+    assert_eq!(stack.data(), &[InnerData::INT(-1)]);
+}
+
+#[test]
+fn test_execute_bitwise_string_operands() {
+    let (_, result, _) = execute(
+        &mut Processor::new(), &mut DataMemory::new(),
+        vec![InnerData::STR("a".to_string()), InnerData::INT(1)], vec![InstructionSet::AND]
+    );
+    assert_eq!(result, Err(VmError::TypeMismatch));
+
+    let (_, result, _) = execute(
+        &mut Processor::new(), &mut DataMemory::new(),
+        vec![InnerData::INT(1), InnerData::STR("a".to_string())], vec![InstructionSet::SHL]
+    );
+    assert_eq!(result, Err(VmError::TypeMismatch));
+
+    let (_, result, _) = execute(
+        &mut Processor::new(), &mut DataMemory::new(),
+        vec![InnerData::STR("a".to_string())], vec![InstructionSet::NOT]
+    );
+    assert_eq!(result, Err(VmError::TypeMismatch));
+}
+
+#[test]
+fn test_execute_logical_operators() {
+    let cases = vec![
+        (InstructionSet::AND, true, false, false),
+        (InstructionSet::AND, true, true, true),
+        (InstructionSet::OR, false, true, true),
+        (InstructionSet::OR, false, false, false),
+        (InstructionSet::XOR, true, true, false),
+    ];
+
+    for (instruction, a, b, expected) in cases {
+        let (stack, result, _) = execute(
+            &mut Processor::new(), &mut DataMemory::new(),
+            vec![InnerData::BOOL(a), InnerData::BOOL(b)], vec![instruction]
+        );
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(stack.data(), &[InnerData::BOOL(expected)]);
+    }
+
+    let (stack, _, _) = execute(&mut Processor::new(), &mut DataMemory::new(), vec![InnerData::BOOL(false)],
+                                vec![InstructionSet::NOT]);
+    assert_eq!(stack.data(), &[InnerData::BOOL(true)]);
+
+    let (_, result, _) = execute(
+        &mut Processor::new(), &mut DataMemory::new(),
+        vec![InnerData::BOOL(true), InnerData::INT(1)], vec![InstructionSet::AND]
+    );
+    assert_eq!(result, Err(VmError::TypeMismatch));
+
+    let (_, result, _) = execute(
+        &mut Processor::new(), &mut DataMemory::new(),
+        vec![InnerData::BOOL(true), InnerData::BOOL(true)], vec![InstructionSet::SHL]
+    );
+    assert_eq!(result, Err(VmError::TypeMismatch));
+}
+
+#[test]
+fn test_execute_show_bool() {
+    let mut stdout = Vec::new();
+    let mut stack = Stack::new();
+    stack.push(InnerData::BOOL(false));
+    stack.push(InnerData::BOOL(true));
+
+    Processor::new().execute(
+        &InstructionSet::SHOW,
+        &mut DataMemory::new(),
+        &mut stack,
+        &mut Stack::new(),
+        &mut io::empty(),
+        &mut stdout
+    ).unwrap();
+
+    assert_eq!(String::from_utf8(stdout).unwrap(), "true\n");
+}
+
+#[test]
+fn test_equ_false_sets_zero_flag() {
+    let flags = flags_after(vec![
+        InstructionSet::LOAD(InnerData::INT(1), 2),
+        InstructionSet::LOAD(InnerData::INT(2), 2),
+        InstructionSet::EQU,
+    ]);
+
+    assert_eq!(flags, ZERO);
+}
+
+#[test]
+fn test_execute_stack_manipulation() {
+    let ints = |values: &[i8]| values.iter().map(|value| InnerData::INT(*value)).collect::<Vec<_>>();
+
+    let cases = vec![
+        (InstructionSet::DUP, ints(&[1, 2]), ints(&[1, 2, 2])),
+        (InstructionSet::DROP, ints(&[1, 2]), ints(&[1])),
+        (InstructionSet::SWAP, ints(&[1, 2]), ints(&[2, 1])),
+        (InstructionSet::OVER, ints(&[1, 2]), ints(&[1, 2, 1])),
+        (InstructionSet::ROT, ints(&[1, 2, 3]), ints(&[2, 3, 1])),
+        (InstructionSet::PICK(InnerData::INT(2)), ints(&[1, 2, 3]), ints(&[1, 2, 3, 1])),
+        (InstructionSet::PICK(InnerData::INT(0)), ints(&[1, 2, 3]), ints(&[1, 2, 3, 3])),
+        (InstructionSet::ROLL(InnerData::INT(3)), ints(&[1, 2, 3, 4]), ints(&[2, 3, 4, 1])),
+        (InstructionSet::ROLL(InnerData::INT(0)), ints(&[1, 2]), ints(&[1, 2])),
+    ];
+
+    for (instruction, before, after) in cases {
+        let (stack, result, _) = execute(&mut Processor::new(), &mut DataMemory::new(), before, vec![instruction]);
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(stack.data(), after.as_slice());
+        assert_eq!(stack.head(), after.len());
+    }
+}
+
+#[test]
+fn test_execute_stack_manipulation_underflow() {
+    let cases = vec![
+        (InstructionSet::DUP, 0),
+        (InstructionSet::DROP, 0),
+        (InstructionSet::SWAP, 1),
+        (InstructionSet::OVER, 1),
+        (InstructionSet::ROT, 2),
+        (InstructionSet::PICK(InnerData::INT(3)), 3),
+        (InstructionSet::ROLL(InnerData::INT(3)), 3),
+    ];
+
+    for (instruction, depth) in cases {
+        let (_, result, _) = execute(&mut Processor::new(), &mut DataMemory::new(), vec![InnerData::INT(1); depth],
+                                     vec![instruction]);
+
+        assert_eq!(result, Err(VmError::StackUnderflow));
+    }
+}
+
+#[test]
+fn test_execute_empty_stack_underflow() {
+    let cases = vec![
+        (InstructionSet::ADD, 1),
+        (InstructionSet::MOD, 1),
+        (InstructionSet::LT, 1),
+        (InstructionSet::AND, 1),
+        (InstructionSet::SHL, 1),
+        (InstructionSet::CMP, 1),
+        (InstructionSet::NEG, 0),
+        (InstructionSet::ITOF, 0),
+        (InstructionSet::FTOI(InnerData::INT(0)), 0),
+        (InstructionSet::LEN, 0),
+        (InstructionSet::SUBSTR, 2),
+        (InstructionSet::STORELOCAL(InnerData::INT(0)), 0),
+        (InstructionSet::POP(InnerData::INT(0), 1), 0),
+        (InstructionSet::CALLI(InnerData::INT(0)), 0),
+    ];
+
+    for (instruction, depth) in cases {
+        let (_, result, _) = execute(&mut Processor::new(), &mut DataMemory::new(), vec![InnerData::INT(1); depth],
+                                     vec![instruction]);
+
+        assert_eq!(result, Err(VmError::StackUnderflow));
+    }
+
+    // No opcode may abort the host, whatever it finds on an empty stack.
+    for opcode in 0..=100 {
+        let (arg, arg1) = match InstructionSet::operands(opcode).unwrap() {
+            Operands::Zero => (None, None),
+            Operands::One => (Some(InnerData::INT(0)), None),
+            Operands::ValueAndOffset | Operands::LabelAndCount => (Some(InnerData::INT(0)), Some(InnerData::INT(1))),
+        };
+
+        let _ = execute(&mut Processor::new(), &mut DataMemory::new(), Vec::new(),
+                        vec![InstructionSet::from_int(opcode, arg, arg1)]);
+    }
+}
+
+#[test]
+fn test_execute_invalid_register_and_offset() {
+    let cases = vec![
+        (InstructionSet::LOAD(InnerData::INT(10), 1), VmError::InvalidRegister),
+        (InstructionSet::POP(InnerData::INT(10), 1), VmError::InvalidRegister),
+        (InstructionSet::LOAD(InnerData::INT(0), 99), VmError::InvalidOffset),
+        (InstructionSet::POP(InnerData::INT(0), 99), VmError::InvalidOffset),
+    ];
+
+    for (instruction, error) in cases {
+        let (_, result, _) = execute(&mut Processor::new(), &mut DataMemory::new(), vec![InnerData::INT(1)],
+                                     vec![instruction]);
+
+        assert_eq!(result, Err(error));
+    }
+}
+
+#[test]
+fn test_execute_itof() {
+    let (stack, result, _) = execute(&mut Processor::new(), &mut DataMemory::new(), vec![InnerData::INT16(-300)],
+                                     vec![InstructionSet::ITOF]);
+
+    assert_eq!(result, Ok(()));
+    assert_eq!(stack.data(), &[InnerData::FLOAT(-300.0)]);
+
+    let (_, result, _) = execute(&mut Processor::new(), &mut DataMemory::new(), vec![InnerData::FLOAT(1.0)],
+                                 vec![InstructionSet::ITOF]);
+    assert_eq!(result, Err(VmError::TypeMismatch));
+}
+
+#[test]
+fn test_execute_ftoi_rounding_modes() {
+    let cases = vec![
+        (0, 2.5, InnerData::INT(2)),
+        (0, -2.7, InnerData::INT(-2)),
+        (1, 2.5, InnerData::INT(3)),
+        (1, -2.5, InnerData::INT(-3)),
+        (2, -2.1, InnerData::INT(-3)),
+        (3, 2.1, InnerData::INT(3)),
+        (4, 2.5, InnerData::INT(2)),
+        (4, 3.5, InnerData::INT(4)),
+        (0, 40000.9, InnerData::INT32(40000)),
+        (0, 1e20, InnerData::BIGINT(BigInt::parse("100000000000000000000").unwrap())),
+    ];
+
+    for (mode, value, expected) in cases {
+        let (stack, result, _) = execute(
+            &mut Processor::new(), &mut DataMemory::new(),
+            vec![InnerData::FLOAT(value)], vec![InstructionSet::FTOI(InnerData::INT(mode))]
+        );
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(stack.data(), &[expected]);
+    }
+
+    let (_, result, _) = execute(&mut Processor::new(), &mut DataMemory::new(), vec![InnerData::FLOAT(f64::NAN)],
+                                 vec![InstructionSet::FTOI(InnerData::INT(0))]);
+    assert_eq!(result, Err(VmError::InvalidConversion));
+
+    let (_, result, _) = execute(&mut Processor::new(), &mut DataMemory::new(), vec![InnerData::FLOAT(1.5)],
+                                 vec![InstructionSet::FTOI(InnerData::INT(5))]);
+    assert_eq!(result, Err(VmError::InvalidConversion));
+}
+
+#[test]
+fn test_execute_float_comparisons_with_nan() {
+    for instruction in [InstructionSet::LT, InstructionSet::GE, InstructionSet::EQU] {
+        let (stack, result, _) = execute(
+            &mut Processor::new(), &mut DataMemory::new(),
+            vec![InnerData::FLOAT(f64::NAN), InnerData::INT(1)], vec![instruction]
+        );
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(stack.data(), &[InnerData::BOOL(false)]);
+    }
+
+    let (stack, _, _) = execute(
+        &mut Processor::new(), &mut DataMemory::new(),
+        vec![InnerData::INT(1), InnerData::FLOAT(1.5)], vec![InstructionSet::LT]
+    );
+    assert_eq!(stack.data(), &[InnerData::BOOL(true)]);
+}
+
+#[test]
+fn test_execute_neg_float() {
+    let (stack, result, _) = execute(&mut Processor::new(), &mut DataMemory::new(), vec![InnerData::FLOAT(0.0)],
+                                     vec![InstructionSet::NEG]);
+
+    assert_eq!(result, Ok(()));
+    assert_eq!(stack.top().to_string(), "-0.0");
+
+    for value in [InnerData::BOOL(true), InnerData::STR(String::from("1")), InnerData::REF(0)] {
+        let (_, result, _) = execute(&mut Processor::new(), &mut DataMemory::new(), vec![value],
+                                     vec![InstructionSet::NEG]);
+
+        assert_eq!(result, Err(VmError::TypeMismatch));
+    }
+}
+
+#[test]
+fn test_execute_overflow_policies() {
+    let cases = vec![
+        (OverflowPolicy::Promote, InstructionSet::ADD, InnerData::INT16(200), InnerData::INT(127), InnerData::INT16(327)),
+        (OverflowPolicy::Promote, InstructionSet::ADD, InnerData::INT(100), InnerData::INT(100), InnerData::INT16(200)),
+        (OverflowPolicy::Wrap, InstructionSet::ADD, InnerData::INT(100), InnerData::INT(100), InnerData::INT(-56)),
+        (OverflowPolicy::Wrap, InstructionSet::SUB, InnerData::U8(3), InnerData::U8(5), InnerData::U8(254)),
+        (OverflowPolicy::Wrap, InstructionSet::MUL, InnerData::U64(u64::MAX), InnerData::U64(3), InnerData::U64(u64::MAX - 2)),
+        (OverflowPolicy::Wrap, InstructionSet::DIV, InnerData::INT(-128), InnerData::INT(-1), InnerData::INT(-128)),
+        (OverflowPolicy::Saturate, InstructionSet::ADD, InnerData::INT(100), InnerData::INT(100), InnerData::INT(127)),
+        (OverflowPolicy::Saturate, InstructionSet::SUB, InnerData::U16(3), InnerData::U8(5), InnerData::U16(0)),
+        (OverflowPolicy::Saturate, InstructionSet::MUL, InnerData::INT32(-70000), InnerData::INT32(70000), InnerData::INT32(i32::MIN)),
+        (OverflowPolicy::Saturate, InstructionSet::MUL, InnerData::U64(u64::MAX), InnerData::U64(2), InnerData::U64(u64::MAX)),
+        (OverflowPolicy::Trap, InstructionSet::ADD, InnerData::INT(1), InnerData::INT(2), InnerData::INT(3)),
+        (OverflowPolicy::Trap, InstructionSet::MOD, InnerData::INT(-7), InnerData::INT(2), InnerData::INT(-1)),
+        (OverflowPolicy::Wrap, InstructionSet::ADD, InnerData::FLOAT(1.5), InnerData::INT(1), InnerData::FLOAT(2.5)),
+        (OverflowPolicy::Trap, InstructionSet::ADD, InnerData::U64(u64::MAX), InnerData::INT(1),
+         InnerData::BIGINT(BigInt::parse("18446744073709551616").unwrap())),
+    ];
+
+    for (policy, instruction, a, b, expected) in cases {
+        let mut processor = Processor::new();
+        processor.set_overflow_policy(policy);
+        let (stack, result, _) = execute(&mut processor, &mut DataMemory::new(), vec![a, b], vec![instruction]);
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(stack.data(), &[expected]);
+    }
+}
+
+#[test]
+fn test_execute_overflow_trap() {
+    let mut processor = Processor::new();
+    processor.set_overflow_policy(OverflowPolicy::Trap);
+    let (_, result, _) = execute(
+        &mut processor, &mut DataMemory::new(),
+        vec![InnerData::INT(16), InnerData::INT(8)], vec![InstructionSet::MUL]
+    );
+    assert_eq!(result, Err(VmError::IntegerOverflow));
+
+    let mut processor = Processor::new();
+    processor.set_overflow_policy(OverflowPolicy::Trap);
+    let (_, result, _) = execute(&mut processor, &mut DataMemory::new(), vec![InnerData::INT(-128)],
+                                 vec![InstructionSet::NEG]);
+    assert_eq!(result, Err(VmError::IntegerOverflow));
+
+    let mut processor = Processor::new();
+    processor.set_overflow_policy(OverflowPolicy::Wrap);
+    let (_, result, _) = execute(
+        &mut processor, &mut DataMemory::new(),
+        vec![InnerData::INT(1), InnerData::INT(0)], vec![InstructionSet::DIV]
+    );
+    assert_eq!(result, Err(VmError::DivisionByZero));
+}
+
+#[test]
+fn test_execute_instruction_overflow_policy() {
+    let cases = vec![
+        (InstructionSet::ADDP(InnerData::INT(1)), vec![InnerData::INT(127), InnerData::INT(1)], InnerData::INT(-128)),
+        (InstructionSet::SUBP(InnerData::INT(2)), vec![InnerData::U8(1), InnerData::U8(2)], InnerData::U8(0)),
+        (InstructionSet::MULP(InnerData::INT(0)), vec![InnerData::INT(16), InnerData::INT(8)], InnerData::INT16(128)),
+        (InstructionSet::MODP(InnerData::INT(1)), vec![InnerData::INT(7), InnerData::INT(3)], InnerData::INT(1)),
+        (InstructionSet::NEGP(InnerData::INT(1)), vec![InnerData::U8(1)], InnerData::U8(255)),
+    ];
+
+    for (instruction, values, expected) in cases {
+        // The instruction's own policy wins over the processor's.
+        let mut processor = Processor::new();
+        processor.set_overflow_policy(OverflowPolicy::Trap);
+        let (stack, result, _) = execute(&mut processor, &mut DataMemory::new(), values, vec![instruction]);
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(stack.data(), &[expected]);
+    }
+
+    let mut processor = Processor::new();
+    processor.set_overflow_policy(OverflowPolicy::Wrap);
+    let (_, result, _) = execute(
+        &mut processor, &mut DataMemory::new(),
+        vec![InnerData::INT(-128), InnerData::INT(-1)], vec![InstructionSet::DIVP(InnerData::INT(3))]
+    );
+    assert_eq!(result, Err(VmError::IntegerOverflow));
+
+    let mut processor = Processor::new();
+    processor.set_overflow_policy(OverflowPolicy::Wrap);
+    let (_, result, _) = execute(
+        &mut processor, &mut DataMemory::new(),
+        vec![InnerData::INT(1), InnerData::INT(2)], vec![InstructionSet::ADDP(InnerData::INT(4))]
+    );
+    assert_eq!(result, Err(VmError::InvalidConversion));
+}
+
+#[test]
+fn test_execute_division_by_zero() {
+    let big = InnerData::BIGINT(BigInt::parse("18446744073709551617").unwrap());
+
+    let cases = vec![
+        (InstructionSet::DIV, InnerData::INT(1), InnerData::INT(0)),
+        (InstructionSet::MOD, InnerData::INT(1), InnerData::INT(0)),
+        (InstructionSet::DIV, InnerData::U64(1), InnerData::INT16(0)),
+        (InstructionSet::DIV, big.clone(), InnerData::BIGINT(BigInt::from(0))),
+        (InstructionSet::MOD, big, InnerData::INT(0)),
+    ];
+
+    for (instruction, a, b) in cases {
+        let (_, result, _) = execute(&mut Processor::new(), &mut DataMemory::new(), vec![a, b], vec![instruction]);
+
+        assert_eq!(result, Err(VmError::DivisionByZero));
+    }
+}
+
+#[test]
+fn test_execute_arithmetic_type_mismatch() {
+    let text = || InnerData::STR("ab".to_string());
+
+    let cases = vec![
+        (InstructionSet::ADD, text(), InnerData::INT(1)),
+        (InstructionSet::SUB, text(), text()),
+        (InstructionSet::MUL, InnerData::BOOL(true), InnerData::BOOL(true)),
+        (InstructionSet::MUL, text(), InnerData::FLOAT(2.0)),
+        (InstructionSet::DIV, text(), InnerData::INT(0)),
+        (InstructionSet::MOD, InnerData::BOOL(false), InnerData::INT(2)),
+        (InstructionSet::ADDP(InnerData::INT(3)), InnerData::FUNC(1), InnerData::INT(1)),
+    ];
+
+    for (instruction, a, b) in cases {
+        let (_, result, _) = execute(&mut Processor::new(), &mut DataMemory::new(), vec![a, b], vec![instruction]);
+
+        assert_eq!(result, Err(VmError::TypeMismatch));
+    }
+}
+
+#[test]
+fn test_execute_bigint_size_limit() {
+    // Squaring forever promotes all the way to BIGINT and would grow without bound.
+    let mut vm = Vm::builder().program(vec![
+        InstructionSet::LOAD(InnerData::INT(3), 2),
+        InstructionSet::LABEL,
+        InstructionSet::DUP,
+        InstructionSet::MUL,
+        InstructionSet::JMP(InnerData::INT(1)),
+    ]).limits(Limits { max_bigint_bits: 256, ..Limits::new() }).stdin(io::empty()).stdout(io::sink()).build();
+
+    assert_eq!(vm.run(), Err(VmError::BigIntTooLarge));
+}
+
+#[test]
+fn test_execute_conversions() {
+    let big = InnerData::BIGINT(BigInt::parse("18446744073709551617").unwrap());
+
+    let cases = vec![
+        (InstructionSet::WIDEN(InnerData::INT(2)), InnerData::INT(-5), InnerData::INT32(-5)),
+        (InstructionSet::WIDEN(InnerData::INT(1)), InnerData::U8(200), InnerData::INT16(200)),
+        (InstructionSet::WIDEN(InnerData::INT(8)), InnerData::U64(7), InnerData::BIGINT(BigInt::from(7))),
+        (InstructionSet::NARROW(InnerData::INT(0)), InnerData::INT16(300), InnerData::INT(44)),
+        (InstructionSet::NARROW(InnerData::INT(4)), InnerData::INT(-1), InnerData::U8(255)),
+        (InstructionSet::NARROW(InnerData::INT(7)), big, InnerData::U64(1)),
+        (InstructionSet::NARROWC(InnerData::INT(0)), InnerData::INT64(-128), InnerData::INT(-128)),
+        (InstructionSet::NARROWC(InnerData::INT(6)), InnerData::INT16(7), InnerData::U32(7)),
+        (InstructionSet::SEXT(InnerData::INT(8)), InnerData::INT32(0xFF), InnerData::INT32(-1)),
+        (InstructionSet::SEXT(InnerData::INT(4)), InnerData::U8(0x0E), InnerData::U8(0xFE)),
+        (InstructionSet::ZEXT(InnerData::INT(8)), InnerData::INT32(-1), InnerData::INT32(255)),
+        (InstructionSet::ZEXT(InnerData::INT(8)), InnerData::INT(-1), InnerData::INT(-1)),
+        (InstructionSet::PARSE, InnerData::STR(String::from("-42")), InnerData::INT(-42)),
+        (InstructionSet::PARSE, InnerData::STR(String::from("+40000")), InnerData::INT32(40000)),
+        (InstructionSet::TOSTR, InnerData::INT16(-300), InnerData::STR(String::from("-300"))),
+        (InstructionSet::TOSTR, InnerData::BOOL(true), InnerData::STR(String::from("true"))),
+    ];
+
+    for (instruction, value, expected) in cases {
+        let (stack, result, _) = execute(&mut Processor::new(), &mut DataMemory::new(), vec![value],
+                                         vec![instruction]);
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(stack.data(), &[expected]);
+    }
+}
+
+#[test]
+fn test_execute_conversion_errors() {
+    let cases = vec![
+        (InstructionSet::WIDEN(InnerData::INT(0)), InnerData::INT16(1), VmError::InvalidConversion),
+        (InstructionSet::WIDEN(InnerData::INT(4)), InnerData::INT(1), VmError::InvalidConversion),
+        (InstructionSet::NARROWC(InnerData::INT(0)), InnerData::INT16(128), VmError::InvalidConversion),
+        (InstructionSet::NARROWC(InnerData::INT(4)), InnerData::INT(-1), VmError::InvalidConversion),
+        (InstructionSet::NARROW(InnerData::INT(0)), InnerData::STR(String::from("1")), VmError::TypeMismatch),
+        (InstructionSet::SEXT(InnerData::INT(9)), InnerData::INT(1), VmError::InvalidConversion),
+        (InstructionSet::ZEXT(InnerData::INT(0)), InnerData::INT(1), VmError::InvalidConversion),
+        (InstructionSet::WIDEN(InnerData::INT(9)), InnerData::INT(1), VmError::InvalidConversion),
+        (InstructionSet::NARROW(InnerData::INT(-1)), InnerData::INT(1), VmError::InvalidConversion),
+        (InstructionSet::NARROWC(InnerData::INT(42)), InnerData::INT(1), VmError::InvalidConversion),
+        (InstructionSet::PARSE, InnerData::STR(String::from("12a")), VmError::InvalidConversion),
+        (InstructionSet::PARSE, InnerData::STR(String::from("+-1")), VmError::InvalidConversion),
+        (InstructionSet::PARSE, InnerData::INT(1), VmError::TypeMismatch),
+    ];
+
+    for (instruction, value, error) in cases {
+        let (_, result, _) = execute(&mut Processor::new(), &mut DataMemory::new(), vec![value],
+                                     vec![instruction]);
+
+        assert_eq!(result, Err(error));
+    }
+}
+
+#[test]
+fn test_execute_checked_byte_operands() {
+    // Labels past 127 are loaded as negative INTs and still address the program.
+    let mut processor = Processor::new();
+    let (_, result, _) = execute(&mut processor, &mut DataMemory::new(), vec![],
+                                 vec![InstructionSet::JMP(InnerData::INT(-56))]);
+    assert_eq!(result, Ok(()));
+    assert_eq!(processor.pc(), 200);
+
+    let (_, result, _) = execute(&mut Processor::new(), &mut DataMemory::new(), vec![],
+                                 vec![InstructionSet::JMP(InnerData::INT16(300))]);
+    assert_eq!(result, Err(VmError::InvalidConversion));
+
+    let (_, result, _) = execute(&mut Processor::new(), &mut DataMemory::new(), vec![InnerData::INT16(300)],
+                                 vec![InstructionSet::POP(InnerData::INT(0), 1)]);
+    assert_eq!(result, Err(VmError::InvalidConversion));
+
+    let (stack, result, _) = execute(&mut Processor::new(), &mut DataMemory::new(), vec![InnerData::INT(9)],
+                                     vec![InstructionSet::POP(InnerData::INT16(3), 4)]);
+    assert_eq!(result, Ok(()));
+    assert_eq!(stack.head(), 0);
+}
+
+fn string(value: &str) -> InnerData {
+    InnerData::STR(String::from(value))
+}
+
+#[test]
+fn test_execute_string_instructions() {
+    let cases = vec![
+        (InstructionSet::LEN, vec![string("héllo")], vec![InnerData::INT(5)]),
+        (InstructionSet::CHARAT, vec![string("héllo"), InnerData::INT(1)], vec![string("é")]),
+        (InstructionSet::SUBSTR, vec![string("日本語です"), InnerData::INT(1), InnerData::INT(3)], vec![string("本語で")]),
+        (InstructionSet::SUBSTR, vec![string("abc"), InnerData::INT(3), InnerData::INT(0)], vec![string("")]),
+        (InstructionSet::FIND, vec![string("naïve cafe"), string("cafe")], vec![InnerData::INT(6)]),
+        (InstructionSet::FIND, vec![string("abc"), string("x")], vec![InnerData::INT(-1)]),
+        (InstructionSet::SPLIT, vec![string("a,b,,c"), string(",")],
+         vec![string("a"), string("b"), string(""), string("c"), InnerData::INT(4)]),
+        (InstructionSet::SPLIT, vec![string("añ"), string("")], vec![string("a"), string("ñ"), InnerData::INT(2)]),
+        (InstructionSet::UPPER, vec![string("straße")], vec![string("STRASSE")]),
+        (InstructionSet::LOWER, vec![string("ÀB")], vec![string("àb")]),
+        (InstructionSet::TRIM, vec![string(" \tx y\n")], vec![string("x y")]),
+        (InstructionSet::ORD, vec![string("€")], vec![InnerData::INT16(8364)]),
+        (InstructionSet::CHR, vec![InnerData::INT32(128512)], vec![string("😀")]),
+        (InstructionSet::LT, vec![string("apple"), string("äpple")], vec![InnerData::BOOL(true)]),
+    ];
+
+    for (instruction, values, expected) in cases {
+        let (stack, result, _) = execute(&mut Processor::new(), &mut DataMemory::new(), values, vec![instruction]);
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(stack.data(), expected.as_slice());
+    }
+}
+
+#[test]
+fn test_execute_string_instruction_errors() {
+    let cases = vec![
+        (InstructionSet::CHARAT, vec![string("héllo"), InnerData::INT(5)], VmError::IndexOutOfBounds),
+        (InstructionSet::CHARAT, vec![string("abc"), InnerData::INT(-1)], VmError::IndexOutOfBounds),
+        (InstructionSet::SUBSTR, vec![string("abc"), InnerData::INT(2), InnerData::INT(2)], VmError::IndexOutOfBounds),
+        (InstructionSet::LEN, vec![InnerData::INT(1)], VmError::TypeMismatch),
+        (InstructionSet::FIND, vec![string("abc"), InnerData::INT(1)], VmError::TypeMismatch),
+        (InstructionSet::ORD, vec![string("ab")], VmError::InvalidConversion),
+        (InstructionSet::CHR, vec![InnerData::INT32(0xD800)], VmError::InvalidConversion),
+    ];
+
+    for (instruction, values, error) in cases {
+        let (_, result, _) = execute(&mut Processor::new(), &mut DataMemory::new(), values, vec![instruction]);
+
+        assert_eq!(result, Err(error));
+    }
+}
+
+#[test]
+fn test_execute_format() {
+    let format = |count| InstructionSet::FORMAT(InnerData::INT(count));
+
+    let cases = vec![
+        (format(2), vec![string("{} + {} = ?"), InnerData::INT(1), string("two")], "1 + two = ?"),
+        (format(1), vec![string("[{:5}]"), InnerData::INT(42)], "[   42]"),
+        (format(1), vec![string("[{:05}]"), InnerData::INT16(-42)], "[-0042]"),
+        (format(1), vec![string("[{:<5}]"), InnerData::INT(42)], "[42   ]"),
+        (format(1), vec![string("[{:*^7}]"), string("ab")], "[**ab***]"),
+        (format(1), vec![string("[{:4}]"), string("ab")], "[ab  ]"),
+        (format(1), vec![string("{:x}"), InnerData::U16(48879)], "beef"),
+        (format(1), vec![string("{:#>4X}"), InnerData::INT(-1)], "##FF"),
+        (format(1), vec![string("{:08b}"), InnerData::U8(5)], "00000101"),
+        (format(1), vec![string("{:d}"), InnerData::INT64(-7)], "-7"),
+        (format(1), vec![string("{:x}"), InnerData::BIGINT(BigInt::from(-(1i128 << 70)))], "-400000000000000000"),
+        (format(0), vec![string("{{}}")], "{}"),
+    ];
+
+    for (instruction, values, expected) in cases {
+        let (stack, result, _) = execute(&mut Processor::new(), &mut DataMemory::new(), values, vec![instruction]);
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(stack.data(), &[string(expected)]);
+    }
+}
+
+#[test]
+fn test_execute_format_errors() {
+    let format = |count| InstructionSet::FORMAT(InnerData::INT(count));
+
+    let cases = vec![
+        (format(1), vec![string("{} {}"), InnerData::INT(1)], VmError::InvalidFormat),
+        (format(2), vec![string("{}"), InnerData::INT(1), InnerData::INT(2)], VmError::InvalidFormat),
+        (format(1), vec![string("{:q}"), InnerData::INT(1)], VmError::InvalidFormat),
+        (format(1), vec![string("{:x}"), InnerData::FLOAT(1.5)], VmError::InvalidFormat),
+        (format(0), vec![string("}")], VmError::InvalidFormat),
+        (format(1), vec![InnerData::INT(1), InnerData::INT(1)], VmError::TypeMismatch),
+        (format(2), vec![string("{}"), InnerData::INT(1)], VmError::StackUnderflow),
+    ];
+
+    for (instruction, values, error) in cases {
+        let (_, result, _) = execute(&mut Processor::new(), &mut DataMemory::new(), values, vec![instruction]);
+
+        assert_eq!(result, Err(error));
+    }
+}
+
+#[test]
+fn test_execute_print() {
+    let mut stack = Stack::new();
+    stack.push(InnerData::INT(3));
+
+    let mut processor = Processor::new();
+
+    let mut stdout = Vec::new();
+
+    processor.execute(
+        &InstructionSet::PRINT,
+        &mut DataMemory::new(),
+        &mut stack,
+        &mut Stack::new(),
+        &mut io::empty(),
+        &mut stdout
+    ).unwrap();
+
+    assert_eq!(stack.data(), &[]);
+    assert_eq!(String::from_utf8(stdout).unwrap(), "3");
+}
+
+fn memory_with_types() -> DataMemory {
+    let mut data_memory = DataMemory::new();
+    data_memory.heap_mut().define_types(vec![
+        StructType::new("Point", &["x", "y"]),
+        StructType::new("Unit", &[]),
+    ]);
+    data_memory
+}
+
+#[test]
+fn test_execute_format_renders_references() {
+    let (stack, result, stdout) = execute(&mut Processor::new(), &mut memory_with_types(), vec![], vec![
+        InstructionSet::LOAD(string("a = {:>8}"), 3),
+        InstructionSet::NEWARRAY(InnerData::INT(2)),
+        InstructionSet::DUP,
+        InstructionSet::LOAD(InnerData::INT(0), 2),
+        InstructionSet::LOAD(InnerData::INT(7), 2),
+        InstructionSet::ASTORE,
+        InstructionSet::DUP,
+        InstructionSet::SHOW,
+        InstructionSet::FORMAT(InnerData::INT(1)),
+        InstructionSet::NEWARRAY(InnerData::INT(1)),
+        InstructionSet::TOSTR,
+    ]);
+
+    assert_eq!(result, Ok(()));
+    assert_eq!(stdout, "[7, 0]\n");
+    assert_eq!(stack.data(), &[string("a =   [7, 0]"), string("[0]")]);
+}
+
+#[test]
+fn test_execute_arrays() {
+    let mut data_memory = memory_with_types();
+    let (stack, result, stdout) = execute(&mut Processor::new(), &mut data_memory, vec![], vec![
+        InstructionSet::NEWARRAY(InnerData::INT(2)),
+        InstructionSet::DUP,
+        InstructionSet::LOAD(InnerData::INT(1), 2),
+        InstructionSet::LOAD(string("b"), 3),
+        InstructionSet::ASTORE,
+        InstructionSet::DUP,
+        InstructionSet::NEWARRAY(InnerData::INT(0)),
+        InstructionSet::APPEND,
+        InstructionSet::DUP,
+        InstructionSet::SHOW,
+        InstructionSet::DUP,
+        InstructionSet::ALEN,
+        InstructionSet::SWAP,
+        InstructionSet::LOAD(InnerData::INT16(1), 2),
+        InstructionSet::ALOAD,
+    ]);
+
+    assert_eq!(result, Ok(()));
+    assert_eq!(stdout, "[0, b, []]\n");
+    assert_eq!(stack.data(), &[InnerData::INT(3), string("b")]);
+    assert_eq!(data_memory.heap().cells(), 3);
+}
+
+#[test]
+fn test_execute_array_errors() {
+    let array = || InstructionSet::NEWARRAY(InnerData::INT(2));
+
+    let cases = vec![
+        (vec![array(), InstructionSet::LOAD(InnerData::INT(2), 2), InstructionSet::ALOAD], VmError::IndexOutOfBounds),
+        (vec![array(), InstructionSet::LOAD(InnerData::INT(-1), 2), InstructionSet::ALOAD], VmError::IndexOutOfBounds),
+        (vec![array(), InstructionSet::LOAD(InnerData::INT(2), 2), InstructionSet::LOAD(InnerData::INT(0), 2),
+              InstructionSet::ASTORE], VmError::IndexOutOfBounds),
+        (vec![InstructionSet::LOAD(InnerData::INT(0), 2), InstructionSet::ALEN], VmError::TypeMismatch),
+        (vec![array(), InstructionSet::LOAD(string("0"), 3), InstructionSet::ALOAD], VmError::TypeMismatch),
+        (vec![InstructionSet::NEWARRAY(InnerData::INT(-1))], VmError::IndexOutOfBounds),
+    ];
+
+    for (instructions, error) in cases {
+        let (_, result, _) = execute(&mut Processor::new(), &mut memory_with_types(), vec![], instructions);
+
+        assert_eq!(result, Err(error));
+    }
+}
+
+#[test]
+fn test_execute_array_heap_limit() {
+    let mut processor = Processor::new();
+    processor.set_limits(Limits { max_heap_cells: 2, ..Limits::new() });
+
+    let mut data_memory = DataMemory::new();
+    let mut stack = Stack::new();
+
+    let mut execute = |instruction: InstructionSet| processor.execute(
+        &instruction,
+        &mut data_memory,
+        &mut stack,
+        &mut Stack::new(),
+        &mut io::empty(),
+        &mut Vec::new()
+    );
+
+    assert_eq!(execute(InstructionSet::NEWARRAY(InnerData::INT(3))), Err(VmError::HeapExhausted));
+    assert_eq!(execute(InstructionSet::NEWARRAY(InnerData::INT(2))), Ok(()));
+    assert_eq!(execute(InstructionSet::DUP), Ok(()));
+    assert_eq!(execute(InstructionSet::APPEND), Err(VmError::HeapExhausted));
+    assert_eq!(execute(InstructionSet::NEWARRAY(InnerData::INT(2))), Ok(()));
+
+    // Empty objects still fail on a heap that is over a lowered limit.
+    processor.set_limits(Limits { max_heap_cells: 1, ..Limits::new() });
+    for instruction in [InstructionSet::MAPNEW, InstructionSet::NEWARRAY(InnerData::INT(0))] {
+        let result = processor.execute(
+            &instruction,
+            &mut data_memory,
+            &mut stack,
+            &mut Stack::new(),
+            &mut io::empty(),
+            &mut Vec::new()
+        );
+
+        assert_eq!(result, Err(VmError::HeapExhausted));
+    }
+}
+
+#[test]
+fn test_execute_structs() {
+    let (stack, result, stdout) = execute(&mut Processor::new(), &mut memory_with_types(), vec![], vec![
+        InstructionSet::NEW(InnerData::INT(0)),
+        InstructionSet::DUP,
+        InstructionSet::LOAD(InnerData::INT(3), 2),
+        InstructionSet::SETFIELD(InnerData::INT(0)),
+        InstructionSet::DUP,
+        InstructionSet::NEW(InnerData::INT(1)),
+        InstructionSet::SETFIELD(string("y")),
+        InstructionSet::DUP,
+        InstructionSet::SHOW,
+        InstructionSet::DUP,
+        InstructionSet::GETFIELD(string("x")),
+        InstructionSet::SWAP,
+        InstructionSet::GETFIELD(InnerData::INT(1)),
+        InstructionSet::SHOW,
+    ]);
+
+    assert_eq!(result, Ok(()));
+    assert_eq!(stdout, "Point { x: 3, y: Unit {} }\nUnit {}\n");
+    assert_eq!(stack.data(), &[InnerData::INT(3)]);
+}
+
+#[test]
+fn test_execute_struct_errors() {
+    let point = || InstructionSet::NEW(InnerData::INT(0));
+
+    let cases = vec![
+        (vec![point(), InstructionSet::GETFIELD(InnerData::INT(2))], VmError::IndexOutOfBounds),
+        (vec![point(), InstructionSet::GETFIELD(string("z"))], VmError::IndexOutOfBounds),
+        (vec![point(), InstructionSet::LOAD(InnerData::INT(0), 2), InstructionSet::ALOAD], VmError::TypeMismatch),
+        (vec![InstructionSet::NEWARRAY(InnerData::INT(1)), InstructionSet::GETFIELD(InnerData::INT(0))],
+         VmError::TypeMismatch),
+        (vec![InstructionSet::LOAD(InnerData::INT(0), 2), InstructionSet::LOAD(InnerData::INT(0), 2),
+              InstructionSet::SETFIELD(InnerData::INT(0))], VmError::TypeMismatch),
+    ];
+
+    for (instructions, error) in cases {
+        let (_, result, _) = execute(&mut Processor::new(), &mut memory_with_types(), vec![], instructions);
+
+        assert_eq!(result, Err(error));
+    }
+}
+
+#[test]
+fn test_execute_new_invalid_type() {
+    let (_, result, _) = execute(&mut Processor::new(), &mut memory_with_types(), vec![],
+                                 vec![InstructionSet::NEW(InnerData::INT(2))]);
+    assert_eq!(result, Err(VmError::InvalidReference));
+
+    let (_, result, _) = execute(&mut Processor::new(), &mut memory_with_types(), vec![],
+                                 vec![InstructionSet::NEW(string("Point"))]);
+    assert_eq!(result, Err(VmError::TypeMismatch));
+}
+
+#[test]
+fn test_execute_maps() {
+    let (stack, result, stdout) = execute(&mut Processor::new(), &mut memory_with_types(), vec![], vec![
+        InstructionSet::MAPNEW,
+        InstructionSet::DUP,
+        InstructionSet::LOAD(string("b"), 3),
+        InstructionSet::LOAD(InnerData::INT(2), 2),
+        InstructionSet::MAPSET,
+        InstructionSet::DUP,
+        InstructionSet::LOAD(InnerData::INT16(300), 2),
+        InstructionSet::LOAD(string("x"), 3),
+        InstructionSet::MAPSET,
+        InstructionSet::DUP,
+        InstructionSet::LOAD(InnerData::INT(-1), 2),
+        InstructionSet::LOAD(BigInt::parse("100000000000000000000000").map(InnerData::BIGINT).unwrap(), 2),
+        InstructionSet::MAPSET,
+        InstructionSet::DUP,
+        InstructionSet::LOAD(string("a"), 3),
+        InstructionSet::LOAD(InnerData::BOOL(true), 7),
+        InstructionSet::MAPSET,
+        InstructionSet::DUP,
+        InstructionSet::SHOW,
+        InstructionSet::DUP,
+        InstructionSet::MAPKEYS,
+        InstructionSet::SHOW,
+        InstructionSet::DUP,
+        InstructionSet::LOAD(InnerData::U32(300), 2),
+        InstructionSet::MAPGET,
+        InstructionSet::SWAP,
+        InstructionSet::DUP,
+        InstructionSet::LOAD(string("a"), 3),
+        InstructionSet::MAPDEL,
+        InstructionSet::LOAD(string("a"), 3),
+        InstructionSet::MAPHAS,
+    ]);
+
+    assert_eq!(result, Ok(()));
+    assert_eq!(stdout, "{-1: 100000000000000000000000, 300: x, a: true, b: 2}\n[-1, 300, a, b]\n");
+    assert_eq!(stack.data(), &[string("x"), InnerData::BOOL(false)]);
+}
+
+#[test]
+fn test_execute_map_errors() {
+    let cases = vec![
+        (vec![InstructionSet::MAPNEW, InstructionSet::LOAD(InnerData::INT(1), 2), InstructionSet::MAPGET],
+         VmError::KeyNotFound),
+        (vec![InstructionSet::MAPNEW, InstructionSet::LOAD(InnerData::FLOAT(1.0), 2), InstructionSet::MAPHAS],
+         VmError::TypeMismatch),
+        (vec![InstructionSet::NEWARRAY(InnerData::INT(1)), InstructionSet::LOAD(InnerData::INT(0), 2),
+              InstructionSet::MAPGET], VmError::TypeMismatch),
+        (vec![InstructionSet::MAPNEW, InstructionSet::LOAD(InnerData::INT(0), 2), InstructionSet::ALOAD],
+         VmError::TypeMismatch),
+    ];
+
+    for (instructions, error) in cases {
+        let (_, result, _) = execute(&mut Processor::new(), &mut memory_with_types(), vec![], instructions);
+
+        assert_eq!(result, Err(error));
+    }
+}
+
+#[test]
+fn test_execute_empty_objects_trigger_collection() {
+    let mut processor = Processor::new();
+    let mut data_memory = DataMemory::new();
+    let mut stack = Stack::new();
+
+    // Empty maps hold no cells, but each one still counts towards a collection.
+    for _ in 0..3000 {
+        for instruction in [InstructionSet::MAPNEW, InstructionSet::DROP] {
+            processor.execute(
+                &instruction,
+                &mut data_memory,
+                &mut stack,
+                &mut Stack::new(),
+                &mut io::empty(),
+                &mut Vec::new()
+            ).unwrap();
+        }
+    }
+
+    let heap = data_memory.heap();
+    assert!(heap.stats().collections > 0);
+    assert!(heap.len() <= 1024);
+}
+
+#[test]
+fn test_execute_gc_stress_keeps_live_objects() {
+    let mut processor = Processor::new();
+    processor.set_gc_stress(true);
+
+    let mut data_memory = DataMemory::new();
+    let mut stack = Stack::new();
+    let mut call_stack = Stack::new();
+
+    let program = vec![
+        InstructionSet::NEWARRAY(InnerData::INT(1)),
+        InstructionSet::POP(InnerData::INT(0), 4),
+        InstructionSet::NEWARRAY(InnerData::INT(2)),
+        InstructionSet::DROP,
+        InstructionSet::MAPNEW,
+        InstructionSet::DUP,
+        InstructionSet::LOAD(string("k"), 3),
+        InstructionSet::LOAD(InnerData::INT(1), 2),
+        InstructionSet::MAPSET,
+        InstructionSet::MAPKEYS,
+        InstructionSet::NEWARRAY(InnerData::INT(3)),
+    ];
+
+    for instruction in program {
+        processor.execute(
+            &instruction,
+            &mut data_memory,
+            &mut stack,
+            &mut call_stack,
+            &mut io::empty(),
+            &mut Vec::new()
+        ).unwrap();
+    }
+
+    let heap = data_memory.heap();
+
+    // The dropped array and the map, unreachable once MAPKEYS had run, were freed
+    // and their slot reused.
+    assert_eq!(heap.stats().collections, 5);
+    assert_eq!(heap.stats().objects_freed, 2);
+    assert_eq!(heap.render(data_memory.get_var_value(0)), "[0]");
+    assert_eq!(stack.data(), &[InnerData::REF(2), InnerData::REF(1)]);
+    assert_eq!(heap.render(&InnerData::REF(2)), "[k]");
+    assert_eq!(heap.render(&InnerData::REF(1)), "[0, 0, 0]");
+}
+
+#[test]
+fn test_execute_linear_memory() {
+    let mut data_memory = DataMemory::with_linear_memory(16);
+    let (mut stack, result, _) = execute(&mut Processor::new(), &mut data_memory, vec![], vec![
+        InstructionSet::LOAD(InnerData::INT(8), 2),
+        InstructionSet::LOAD(InnerData::U32(0xdeadbeef), 2),
+        InstructionSet::MSTORE(InnerData::INT(6)),
+        InstructionSet::LOAD(InnerData::INT(0), 2),
+        InstructionSet::LOAD(InnerData::INT(-1), 2),
+        InstructionSet::MSTORE(InnerData::INT(5)),
+        InstructionSet::LOAD(InnerData::INT(8), 2),
+        InstructionSet::MLOAD(InnerData::INT(4)),
+        InstructionSet::LOAD(InnerData::INT(9), 2),
+        InstructionSet::MLOAD(InnerData::INT(5)),
+        InstructionSet::LOAD(InnerData::INT(8), 2),
+        InstructionSet::MLOAD(InnerData::INT(2)),
+        // Pointer arithmetic is integer arithmetic on the address.
+        InstructionSet::LOAD(InnerData::INT(8), 2),
+        InstructionSet::LOAD(InnerData::INT(2), 2),
+        InstructionSet::ADD,
+        InstructionSet::MLOAD(InnerData::INT(0)),
+        InstructionSet::LOAD(InnerData::INT(0), 2),
+        InstructionSet::MLOAD(InnerData::INT(6)),
+        InstructionSet::MSIZE,
+    ]);
+
+    assert_eq!(result, Ok(()));
+    assert_eq!(stack.pop(), Some(InnerData::INT(16)));
+    assert_eq!(stack.pop(), Some(InnerData::U32(0xffff)));
+    assert_eq!(stack.pop(), Some(InnerData::INT(-83)));
+    assert_eq!(stack.pop(), Some(InnerData::INT32(0xdeadbeefu32 as i32)));
+    assert_eq!(stack.pop(), Some(InnerData::U16(0xadbe)));
+    assert_eq!(stack.pop(), Some(InnerData::U8(0xef)));
+    assert_eq!(data_memory.linear_memory().read(8, 4), Some(&[0xef, 0xbe, 0xad, 0xde][..]));
+}
+
+#[test]
+fn test_execute_linear_memory_faults() {
+    let mut data_memory = DataMemory::with_linear_memory(16);
+    let (mut stack, result, _) = execute(&mut Processor::new(), &mut data_memory, vec![], vec![
+        InstructionSet::LOAD(InnerData::INT(12), 2),
+        InstructionSet::LOAD(InnerData::INT(1), 2),
+        InstructionSet::MSTORE(InnerData::INT(3)),
+    ]);
+    assert_eq!(result, Err(VmError::MemoryFault));
+    assert_eq!(stack.pop(), None);
+    assert_eq!(data_memory.linear_memory().bytes(), &[0; 16]);
+
+    let (_, result, _) = execute(&mut Processor::new(), &mut DataMemory::with_linear_memory(16), vec![], vec![
+        InstructionSet::LOAD(InnerData::INT(-1), 2),
+        InstructionSet::MLOAD(InnerData::INT(4)),
+    ]);
+    assert_eq!(result, Err(VmError::MemoryFault));
+
+    let (_, result, _) = execute(&mut Processor::new(), &mut DataMemory::with_linear_memory(16), vec![], vec![
+        InstructionSet::LOAD(InnerData::INT(15), 2),
+        InstructionSet::MLOAD(InnerData::INT(1)),
+    ]);
+    assert_eq!(result, Err(VmError::MemoryFault));
+
+    let (_, result, _) = execute(&mut Processor::new(), &mut DataMemory::with_linear_memory(16), vec![], vec![
+        InstructionSet::LOAD(string("0"), 3),
+        InstructionSet::MLOAD(InnerData::INT(4)),
+    ]);
+    assert_eq!(result, Err(VmError::TypeMismatch));
+
+    let (_, result, _) = execute(&mut Processor::new(), &mut DataMemory::with_linear_memory(16), vec![], vec![
+        InstructionSet::LOAD(InnerData::INT(0), 2),
+        InstructionSet::LOAD(InnerData::FLOAT(1.0), 2),
+        InstructionSet::MSTORE(InnerData::INT(7)),
+    ]);
+    assert_eq!(result, Err(VmError::TypeMismatch));
+}
+
+#[test]
+fn test_execute_linear_memory_invalid_width() {
+    for width in [8, 9, -1] {
+        let (_, result, _) = execute(&mut Processor::new(), &mut DataMemory::with_linear_memory(16), vec![], vec![
+            InstructionSet::LOAD(InnerData::INT(0), 2),
+            InstructionSet::MLOAD(InnerData::INT(width)),
+        ]);
+        assert_eq!(result, Err(VmError::InvalidConversion));
+
+        let (_, result, _) = execute(&mut Processor::new(), &mut DataMemory::with_linear_memory(16), vec![], vec![
+            InstructionSet::LOAD(InnerData::INT(0), 2),
+            InstructionSet::LOAD(InnerData::INT(1), 2),
+            InstructionSet::MSTORE(InnerData::INT(width)),
+        ]);
+        assert_eq!(result, Err(VmError::InvalidConversion));
+    }
+}
+
+/// Recursive factorial of 5 using frames. The base case leaves an extra value on
+/// the operand stack for RETF to clean up.
+fn factorial_program() -> Vec<InstructionSet> {
+    vec![
+        InstructionSet::LOAD(InnerData::INT(5), 2),
+        InstructionSet::CALLF(InnerData::INT(4), InnerData::INT(1)),
+        InstructionSet::SHOW,
+        InstructionSet::HALT,
+        InstructionSet::LABEL,
+        InstructionSet::LOADLOCAL(InnerData::INT(0)),
+        InstructionSet::LOAD(InnerData::INT(1), 2),
+        InstructionSet::LE,
+        InstructionSet::JT(InnerData::INT(18)),
+        InstructionSet::LOADLOCAL(InnerData::INT(0)),
+        InstructionSet::LOAD(InnerData::INT(1), 2),
+        InstructionSet::SUB,
+        InstructionSet::STORELOCAL(InnerData::INT(1)),
+        InstructionSet::LOADLOCAL(InnerData::INT(0)),
+        InstructionSet::LOADLOCAL(InnerData::INT(1)),
+        InstructionSet::CALLF(InnerData::INT(4), InnerData::INT(1)),
+        InstructionSet::MUL,
+        InstructionSet::RETF(InnerData::INT(1)),
+        InstructionSet::LABEL,
+        InstructionSet::LOAD(InnerData::INT(99), 2),
+        InstructionSet::LOAD(InnerData::INT(1), 2),
+        InstructionSet::RETF(InnerData::INT(1)),
+    ]
+}
+
+#[test]
+fn test_execute_program_recursive_frames() {
+    let mut memory = ProgramMemory::new();
+    memory.load_program(factorial_program());
+
+    let mut processor = Processor::new();
+    let mut stack = Stack::new();
+    let mut call_stack = Stack::new();
+    let mut stdout = Vec::new();
+
+    processor.execute_program(
+        &memory, &mut DataMemory::new(), &mut stack, &mut call_stack, &mut io::empty(), &mut stdout
+    ).unwrap();
+
+    assert_eq!(String::from_utf8(stdout).unwrap(), "120\n");
+    assert_eq!(stack.head(), 0);
+    assert_eq!(call_stack.head(), 0);
+    assert_eq!(processor.fp(), 0);
+}
+
+#[test]
+fn test_execute_frame_errors() {
+    let (stack, result, _) = execute(&mut Processor::new(), &mut DataMemory::new(), vec![InnerData::INT(1)],
+                                     vec![InstructionSet::RETF(InnerData::INT(0))]);
+    assert_eq!(result, Err(VmError::StackUnderflow));
+    assert_eq!(stack.data(), &[InnerData::INT(1)]);
+
+    let (stack, result, _) = execute(&mut Processor::new(), &mut DataMemory::new(), vec![InnerData::INT(1)],
+                                     vec![InstructionSet::CALLF(InnerData::INT(0), InnerData::INT(2))]);
+    assert_eq!(result, Err(VmError::StackUnderflow));
+    assert_eq!(stack.data(), &[InnerData::INT(1)]);
+
+    let mut processor = Processor::new();
+    let mut stack = Stack::new();
+    let mut call_stack = Stack::new();
+    stack.push(InnerData::INT(7));
+    stack.push(InnerData::INT(8));
+
+    let mut execute = |instruction: InstructionSet| processor.execute(
+        &instruction,
+        &mut DataMemory::new(),
+        &mut stack,
+        &mut call_stack,
+        &mut io::empty(),
+        &mut Vec::new()
+    );
+
+    // The callee may only return values it pushed itself.
+    assert_eq!(execute(InstructionSet::CALLF(InnerData::INT(0), InnerData::INT(1))), Ok(()));
+    assert_eq!(execute(InstructionSet::RETF(InnerData::INT(1))), Err(VmError::StackUnderflow));
+    assert_eq!(execute(InstructionSet::RETF(InnerData::INT(0))), Ok(()));
+    assert_eq!(stack.data(), &[InnerData::INT(7)]);
+
+    // A frame that would not fit the call stack is not opened at all.
+    let mut processor = Processor::new();
+    processor.set_limits(Limits { max_call_depth: 4, ..Limits::new() });
+    let mut stack = Stack::new();
+    let mut call_stack = Stack::new();
+    stack.push(InnerData::INT(7));
+    stack.push(InnerData::INT(8));
+
+    let result = processor.execute(
+        &InstructionSet::CALLF(InnerData::INT(0), InnerData::INT(2)),
+        &mut DataMemory::new(),
+        &mut stack,
+        &mut call_stack,
+        &mut io::empty(),
+        &mut Vec::new()
+    );
+
+    assert_eq!(result, Err(VmError::CallStackOverflow));
+    assert_eq!(stack.head(), 2);
+    assert_eq!(call_stack.head(), 0);
+}
+
+#[test]
+fn test_execute_locals() {
+    let mut processor = Processor::new();
+    let mut stack = Stack::new();
+    let mut call_stack = Stack::new();
+
+    for instruction in [
+        InstructionSet::LOAD(InnerData::INT(5), 2),
+        InstructionSet::STORELOCAL(InnerData::INT(2)),
+        InstructionSet::LOADLOCAL(InnerData::INT(1)),
+        InstructionSet::LOADLOCAL(InnerData::INT(2)),
+        InstructionSet::LOADLOCAL(InnerData::INT(3)),
+    ] {
+        processor.execute(
+            &instruction, &mut DataMemory::new(), &mut stack, &mut call_stack, &mut io::empty(), &mut Vec::new()
+        ).unwrap();
+    }
+
+    assert_eq!(stack.data(), &[InnerData::INT(0), InnerData::INT(5), InnerData::INT(0)]);
+    assert_eq!(processor.frames(&call_stack), vec![Frame {
+        return_pc: None,
+        locals: &[InnerData::INT(0), InnerData::INT(0), InnerData::INT(5)],
+    }]);
+
+    processor.set_strict_memory(true);
+    let result = processor.execute(
+        &InstructionSet::LOADLOCAL(InnerData::INT(3)), &mut DataMemory::new(), &mut stack,
+        &mut call_stack, &mut io::empty(), &mut Vec::new()
+    );
+    assert_eq!(result, Err(VmError::UninitializedRead));
+}
+
+#[test]
+fn test_execute_program_locals_with_subroutines() {
+    // A subroutine entered with CALL shares its caller's locals, but growing them
+    // must not touch the return pc, and reading past them must not expose it.
+    let mut vm = Vm::builder().program(vec![
+        InstructionSet::LOAD(InnerData::INT(5), 2),
+        InstructionSet::STORELOCAL(InnerData::INT(0)),
+        InstructionSet::CALL(InnerData::INT(6)),
+        InstructionSet::LOADLOCAL(InnerData::INT(0)),
+        InstructionSet::SHOW,
+        InstructionSet::HALT,
+        InstructionSet::LABEL,
+        InstructionSet::LOAD(InnerData::INT(9), 2),
+        InstructionSet::STORELOCAL(InnerData::INT(1)),
+        InstructionSet::LOAD(InnerData::INT(7), 2),
+        InstructionSet::STORELOCAL(InnerData::INT(0)),
+        InstructionSet::LOADLOCAL(InnerData::INT(2)),
+        InstructionSet::SHOW,
+        InstructionSet::RET,
+    ]).stdin(io::empty()).stdout(Vec::new()).build();
+
+    assert_eq!(vm.run(), Ok(()));
+    assert_eq!(vm.stdout(), b"0\n7\n");
+
+    // The same inside a CALLF frame, with the subroutine adding a local.
+    let mut vm = Vm::builder().program(vec![
+        InstructionSet::LOAD(InnerData::INT(3), 2),
+        InstructionSet::CALLF(InnerData::INT(4), InnerData::INT(1)),
+        InstructionSet::SHOW,
+        InstructionSet::HALT,
+        InstructionSet::LABEL,
+        InstructionSet::CALL(InnerData::INT(9)),
+        InstructionSet::LOADLOCAL(InnerData::INT(0)),
+        InstructionSet::RETF(InnerData::INT(1)),
+        InstructionSet::HALT,
+        InstructionSet::LABEL,
+        InstructionSet::LOAD(InnerData::INT(4), 2),
+        InstructionSet::STORELOCAL(InnerData::INT(1)),
+        InstructionSet::LOADLOCAL(InnerData::INT(0)),
+        InstructionSet::LOADLOCAL(InnerData::INT(1)),
+        InstructionSet::ADD,
+        InstructionSet::STORELOCAL(InnerData::INT(0)),
+        InstructionSet::RET,
+    ]).stdin(io::empty()).stdout(Vec::new()).build();
+
+    assert_eq!(vm.run(), Ok(()));
+    assert_eq!(vm.stdout(), b"7\n");
+    assert_eq!(vm.stack().head(), 0);
+}
+
+#[test]
+fn test_execute_program_unmatched_ret() {
+    // Inside a CALLF frame, RET has no CALL to return to.
+    let mut vm = Vm::builder().program(vec![
+        InstructionSet::LOAD(InnerData::INT(3), 2),
+        InstructionSet::CALLF(InnerData::INT(3), InnerData::INT(1)),
+        InstructionSet::HALT,
+        InstructionSet::LABEL,
+        InstructionSet::RET,
+    ]).stdin(io::empty()).stdout(Vec::new()).build();
+
+    assert_eq!(vm.run(), Err(VmError::InvalidReturn));
+
+    // In the outermost frame it still falls through, and never pops a local.
+    let mut vm = Vm::builder().program(vec![
+        InstructionSet::LOAD(InnerData::INT(5), 2),
+        InstructionSet::STORELOCAL(InnerData::INT(0)),
+        InstructionSet::RET,
+        InstructionSet::LOADLOCAL(InnerData::INT(0)),
+        InstructionSet::SHOW,
+        InstructionSet::HALT,
+    ]).stdin(io::empty()).stdout(Vec::new()).build();
+
+    assert_eq!(vm.run(), Ok(()));
+    assert_eq!(vm.stdout(), b"5\n");
+}
+
+#[test]
+fn test_execute_program_indirect_calls() {
+    // apply(f, x) = f(f(x)), called with f = double and x = 3.
+    let mut vm = Vm::builder().program(vec![
+        InstructionSet::FUNCREF(InnerData::INT(5)),
+        InstructionSet::LOAD(InnerData::INT(3), 2),
+        InstructionSet::CALLF(InnerData::INT(10), InnerData::INT(2)),
+        InstructionSet::SHOW,
+        InstructionSet::HALT,
+        InstructionSet::LABEL,
+        InstructionSet::LOADLOCAL(InnerData::INT(0)),
+        InstructionSet::LOADLOCAL(InnerData::INT(0)),
+        InstructionSet::ADD,
+        InstructionSet::RETF(InnerData::INT(1)),
+        InstructionSet::LABEL,
+        InstructionSet::LOADLOCAL(InnerData::INT(1)),
+        InstructionSet::LOADLOCAL(InnerData::INT(0)),
+        InstructionSet::CALLI(InnerData::INT(1)),
+        InstructionSet::LOADLOCAL(InnerData::INT(0)),
+        InstructionSet::CALLI(InnerData::INT(1)),
+        InstructionSet::RETF(InnerData::INT(1)),
+    ]).stdin(io::empty()).stdout(Vec::new()).build();
+
+    assert_eq!(vm.run(), Ok(()));
+    assert_eq!(vm.stdout(), b"12\n");
+    assert_eq!(vm.stack().head(), 0);
+}
+
+#[test]
+fn test_execute_invalid_call_targets() {
+    let mut vm = Vm::builder().program(vec![
+        InstructionSet::FUNCREF(InnerData::INT(1)),
+        InstructionSet::HALT,
+    ]).stdin(io::empty()).stdout(Vec::new()).build();
+    assert_eq!(vm.run(), Err(VmError::InvalidCallTarget));
+    assert_eq!(vm.stack().head(), 0);
+
+    let mut vm = Vm::builder().program(vec![
+        InstructionSet::FUNCREF(InnerData::INT(9)),
+        InstructionSet::HALT,
+    ]).stdin(io::empty()).stdout(Vec::new()).build();
+    assert_eq!(vm.run(), Err(VmError::InvalidCallTarget));
+
+    let mut vm = Vm::builder().program(vec![
+        InstructionSet::LOAD(InnerData::FUNC(0), 2),
+        InstructionSet::CALLI(InnerData::INT(0)),
+        InstructionSet::HALT,
+    ]).stdin(io::empty()).stdout(Vec::new()).build();
+    assert_eq!(vm.run(), Err(VmError::InvalidCallTarget));
+    assert_eq!(vm.stack().data(), &[InnerData::FUNC(0)]);
+
+    let mut vm = Vm::builder().program(vec![
+        InstructionSet::LOAD(InnerData::INT(2), 2),
+        InstructionSet::CALLI(InnerData::INT(0)),
+        InstructionSet::HALT,
+    ]).stdin(io::empty()).stdout(Vec::new()).build();
+    assert_eq!(vm.run(), Err(VmError::TypeMismatch));
+    assert_eq!(vm.stack().data(), &[InnerData::INT(2)]);
+
+    let mut vm = Vm::builder().program(vec![
+        InstructionSet::LABEL,
+        InstructionSet::FUNCREF(InnerData::INT(0)),
+        InstructionSet::CALLI(InnerData::INT(1)),
+        InstructionSet::HALT,
+    ]).stdin(io::empty()).stdout(Vec::new()).build();
+    assert_eq!(vm.run(), Err(VmError::StackUnderflow));
+}
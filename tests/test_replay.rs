@@ -0,0 +1,361 @@
+use std::io;
+
+use yamini::memory::InnerData;
+use yamini::instructions::InstructionSet;
+use yamini::processor::VmError;
+use yamini::replay::Recorder;
+use yamini::snapshot::SnapshotError;
+use yamini::vm::Vm;
+
+fn program() -> Vec<InstructionSet> {
+    vec![
+        InstructionSet::LOAD(InnerData::INT(3), 2),
+        InstructionSet::POP(InnerData::INT(0), 4),
+        InstructionSet::LOAD(InnerData::INT(0), 4),
+        InstructionSet::LOAD(InnerData::INT(4), 2),
+        InstructionSet::ADD,
+        InstructionSet::POP(InnerData::INT(0), 4),
+        InstructionSet::LOAD(InnerData::INT(0), 4),
+        InstructionSet::SHOW,
+        InstructionSet::HALT,
+    ]
+}
+
+#[test]
+fn test_recorder_step_back() {
+    let mut vm = Vm::builder().program(program()).stdin(io::empty()).stdout(Vec::new()).build();
+
+    let mut recorder = Recorder::new(&mut vm);
+    recorder.run(&mut vm).unwrap();
+
+    assert_eq!(recorder.position(), 9);
+    assert_eq!(String::from_utf8(recorder.output()).unwrap(), "7\n");
+
+    // Undo HALT and SHOW.
+    assert!(recorder.step_back(&mut vm));
+    assert!(recorder.step_back(&mut vm));
+
+    assert_eq!(vm.processor().pc(), 7);
+    assert_eq!(vm.stack().data(), &[InnerData::INT(7)]);
+
+    recorder.rewind(&mut vm);
+
+    assert_eq!(vm.processor().pc(), 0);
+    assert_eq!(vm.stack().head(), 0);
+    assert!(vm.data_memory().is_empty());
+    assert!(!recorder.step_back(&mut vm));
+}
+
+#[test]
+fn test_recorder_reverse_continue_to_write() {
+    let mut vm = Vm::builder().program(program()).stdin(io::empty()).stdout(io::sink()).build();
+
+    let mut recorder = Recorder::new(&mut vm);
+    recorder.run(&mut vm).unwrap();
+
+    assert!(recorder.reverse_continue_to_write(0, &mut vm));
+    assert_eq!(vm.processor().pc(), 5);
+    assert_eq!(vm.data_memory().get_var_value(0), &InnerData::INT(3));
+    assert_eq!(vm.stack().data(), &[InnerData::INT(7)]);
+
+    assert!(recorder.reverse_continue_to_write(0, &mut vm));
+    assert_eq!(vm.processor().pc(), 1);
+    assert!(vm.data_memory().is_empty());
+
+    assert!(!recorder.reverse_continue_to_write(0, &mut vm));
+    assert_eq!(vm.processor().pc(), 0);
+}
+
+#[test]
+fn test_recorder_replay_is_deterministic() {
+    let mut vm = Vm::builder().program(program()).stdin(io::empty()).stdout(Vec::new()).build();
+
+    let mut recorder = Recorder::new(&mut vm);
+    recorder.run(&mut vm).unwrap();
+
+    let first_run: Vec<_> = recorder.steps().iter().map(|step| step.pc).collect();
+
+    recorder.rewind(&mut vm);
+    recorder.run(&mut vm).unwrap();
+
+    let second_run: Vec<_> = recorder.steps().iter().map(|step| step.pc).collect();
+
+    assert_eq!(first_run, second_run);
+    assert_eq!(vm.stdout(), b"7\n7\n");
+    assert_eq!(recorder.output(), b"7\n");
+    assert_eq!(vm.data_memory().get_var_value(0), &InnerData::INT(7));
+}
+
+#[test]
+fn test_recorder_replays_recorded_input() {
+    let program = vec![
+        InstructionSet::READ,
+        InstructionSet::LOAD(InnerData::STR("!".to_string()), 3),
+        InstructionSet::ADD,
+        InstructionSet::SHOW,
+        InstructionSet::HALT,
+    ];
+
+    let mut vm = Vm::builder().program(program).stdin("hi\n".as_bytes()).stdout(Vec::new()).build();
+
+    let mut recorder = Recorder::new(&mut vm);
+    recorder.run(&mut vm).unwrap();
+
+    assert_eq!(recorder.steps()[0].input, b"hi\n");
+    assert_eq!(vm.stdout(), b"hi!\n");
+
+    // Stdin is exhausted now, so the replayed READ has to come from the log.
+    recorder.rewind(&mut vm);
+    recorder.run(&mut vm).unwrap();
+
+    assert_eq!(vm.stdout(), b"hi!\nhi!\n");
+    assert_eq!(recorder.output(), b"hi!\n");
+}
+
+#[test]
+fn test_recorder_step_back_undoes_heap_writes() {
+    let program = vec![
+        InstructionSet::NEWARRAY(InnerData::INT(1)),
+        InstructionSet::DUP,
+        InstructionSet::LOAD(InnerData::INT(0), 2),
+        InstructionSet::LOAD(InnerData::INT(7), 2),
+        InstructionSet::ASTORE,
+        InstructionSet::DUP,
+        InstructionSet::LOAD(InnerData::INT(8), 2),
+        InstructionSet::APPEND,
+        InstructionSet::SHOW,
+        InstructionSet::HALT,
+    ];
+
+    let mut vm = Vm::builder().program(program).stdin(io::empty()).stdout(Vec::new()).build();
+
+    let mut recorder = Recorder::new(&mut vm);
+    recorder.run(&mut vm).unwrap();
+
+    assert_eq!(vm.stdout(), b"[7, 8]\n");
+
+    // Undo HALT, SHOW and APPEND.
+    for _ in 0..3 {
+        assert!(recorder.step_back(&mut vm));
+    }
+    assert_eq!(vm.data_memory().heap().render(&InnerData::REF(0)), "[7]");
+
+    recorder.rewind(&mut vm);
+    assert!(vm.data_memory().heap().objects().is_empty());
+    assert_eq!(vm.data_memory().heap().cells(), 0);
+}
+
+#[test]
+fn test_recorder_step_back_restores_collected_objects() {
+    let program = vec![
+        InstructionSet::NEWARRAY(InnerData::INT(1)),
+        InstructionSet::DROP,
+        InstructionSet::NEWARRAY(InnerData::INT(2)),
+        InstructionSet::HALT,
+    ];
+
+    let mut vm = Vm::builder().program(program).gc_stress(true).stdin(io::empty()).stdout(io::sink()).build();
+
+    let mut recorder = Recorder::new(&mut vm);
+    recorder.run(&mut vm).unwrap();
+
+    assert_eq!(vm.data_memory().heap().len(), 1);
+    assert_eq!(vm.stack().data(), &[InnerData::REF(0)]);
+    assert_eq!(vm.data_memory().heap().render(&InnerData::REF(0)), "[0, 0]");
+
+    // Undo HALT and the second NEWARRAY, which collected the first array.
+    assert!(recorder.step_back(&mut vm));
+    assert!(recorder.step_back(&mut vm));
+
+    assert_eq!(vm.data_memory().heap().render(&InnerData::REF(0)), "[0]");
+}
+
+#[test]
+fn test_recorder_step_back_undoes_memory_writes() {
+    let program = vec![
+        InstructionSet::LOAD(InnerData::INT(2), 2),
+        InstructionSet::LOAD(InnerData::INT16(0x1234), 2),
+        InstructionSet::MSTORE(InnerData::INT(1)),
+        InstructionSet::LOAD(InnerData::INT(3), 2),
+        InstructionSet::LOAD(InnerData::INT(-1), 2),
+        InstructionSet::MSTORE(InnerData::INT(4)),
+        InstructionSet::HALT,
+    ];
+
+    let mut vm = Vm::builder().program(program).linear_memory(4).stdin(io::empty()).stdout(io::sink()).build();
+
+    let mut recorder = Recorder::new(&mut vm);
+    recorder.run(&mut vm).unwrap();
+
+    assert_eq!(vm.data_memory().linear_memory().bytes(), &[0, 0, 0x34, 0xff]);
+
+    // Undo HALT and the second MSTORE.
+    assert!(recorder.step_back(&mut vm));
+    assert!(recorder.step_back(&mut vm));
+    assert_eq!(vm.data_memory().linear_memory().bytes(), &[0, 0, 0x34, 0x12]);
+
+    recorder.rewind(&mut vm);
+    assert_eq!(vm.data_memory().linear_memory().bytes(), &[0; 4]);
+}
+
+#[test]
+fn test_recorder_step_back_restores_frames() {
+    let program = vec![
+        InstructionSet::LOAD(InnerData::INT(6), 2),
+        InstructionSet::CALLF(InnerData::INT(3), InnerData::INT(1)),
+        InstructionSet::HALT,
+        InstructionSet::LABEL,
+        InstructionSet::LOADLOCAL(InnerData::INT(0)),
+        InstructionSet::RETF(InnerData::INT(1)),
+    ];
+
+    let mut vm = Vm::builder().program(program).stdin(io::empty()).stdout(io::sink()).build();
+
+    let mut recorder = Recorder::new(&mut vm);
+    recorder.run(&mut vm).unwrap();
+
+    assert_eq!(vm.stack().data(), &[InnerData::INT(6)]);
+    assert_eq!(vm.frames().len(), 1);
+
+    // Undo HALT and RETF.
+    assert!(recorder.step_back(&mut vm));
+    assert!(recorder.step_back(&mut vm));
+
+    assert_eq!(vm.processor().fp(), 4);
+    assert_eq!(vm.frames()[0].locals, &[InnerData::INT(6)]);
+    assert_eq!(vm.processor().pc(), 5);
+}
+
+#[test]
+fn test_recorder_step_back_restores_fuel() {
+    let mut vm = Vm::builder().program(program()).stdin(io::empty()).stdout(io::sink()).fuel(20).build();
+
+    let mut recorder = Recorder::new(&mut vm);
+    recorder.run(&mut vm).unwrap();
+
+    assert_eq!(vm.processor().fuel(), Some(11));
+
+    assert!(recorder.step_back(&mut vm));
+    assert_eq!(vm.processor().fuel(), Some(12));
+
+    recorder.rewind(&mut vm);
+    assert_eq!(vm.processor().fuel(), Some(20));
+}
+
+#[test]
+fn test_recorder_step_back_across_fault() {
+    let program = vec![
+        InstructionSet::LOAD(InnerData::INT(1), 2),
+        InstructionSet::LOAD(InnerData::INT(0), 2),
+        InstructionSet::DIV,
+        InstructionSet::HALT,
+    ];
+
+    let mut vm = Vm::builder().program(program).stdin(io::empty()).stdout(io::sink()).build();
+
+    let mut recorder = Recorder::new(&mut vm);
+    assert_eq!(recorder.run(&mut vm), Err(VmError::DivisionByZero));
+    assert_eq!(recorder.position(), 3);
+
+    assert!(recorder.step_back(&mut vm));
+    assert_eq!(vm.processor().pc(), 2);
+    assert_eq!(vm.stack().data(), &[InnerData::INT(1), InnerData::INT(0)]);
+}
+
+#[test]
+fn test_recorder_log_replays_in_another_vm() {
+    let program = vec![
+        InstructionSet::READ,
+        InstructionSet::NEWARRAY(InnerData::INT(1)),
+        InstructionSet::DUP,
+        InstructionSet::LOAD(InnerData::INT(0), 2),
+        InstructionSet::LOAD(InnerData::INT(7), 2),
+        InstructionSet::ASTORE,
+        InstructionSet::SHOW,
+        InstructionSet::MAPNEW,
+        InstructionSet::DUP,
+        InstructionSet::LOAD(InnerData::STR("k".to_string()), 3),
+        InstructionSet::LOAD(InnerData::INT(1), 2),
+        InstructionSet::MAPSET,
+        InstructionSet::DUP,
+        InstructionSet::LOAD(InnerData::INT(2), 2),
+        InstructionSet::LOAD(InnerData::INT(3), 2),
+        InstructionSet::MAPSET,
+        InstructionSet::SHOW,
+        InstructionSet::SHOW,
+        InstructionSet::HALT,
+    ];
+
+    // GC stress makes MAPNEW collect the array, so the log holds every kind of heap write.
+    let mut vm = Vm::builder().program(program.clone()).gc_stress(true)
+        .stdin("hi\n".as_bytes()).stdout(Vec::new()).build();
+
+    let mut recorder = Recorder::new(&mut vm);
+    recorder.run(&mut vm).unwrap();
+
+    let bytes = recorder.to_bytes();
+
+    let mut other = Vm::builder().program(program).gc_stress(true).stdin(io::empty()).stdout(Vec::new()).build();
+
+    let mut replay = Recorder::from_bytes(&bytes, &mut other).unwrap();
+    assert_eq!(replay.steps(), recorder.steps());
+
+    replay.run(&mut other).unwrap();
+
+    assert_eq!(other.stdout(), vm.stdout());
+    assert_eq!(String::from_utf8(other.stdout().to_vec()).unwrap(), "[7]\n{2: 3, k: 1}\nhi\n");
+    assert_eq!(replay.steps(), recorder.steps());
+
+    replay.rewind(&mut other);
+    assert_eq!(other.processor().pc(), 0);
+    assert!(other.data_memory().heap().objects().is_empty());
+}
+
+#[test]
+fn test_recorder_invalid_log() {
+    let mut vm = Vm::builder().program(program()).stdin(io::empty()).stdout(io::sink()).build();
+
+    let mut recorder = Recorder::new(&mut vm);
+    recorder.run(&mut vm).unwrap();
+
+    let bytes = recorder.to_bytes();
+
+    assert!(matches!(Recorder::from_bytes(b"YSNP", &mut vm), Err(SnapshotError::InvalidMagic)));
+    assert!(matches!(Recorder::from_bytes(&bytes[..bytes.len() - 1], &mut vm),
+                     Err(SnapshotError::UnexpectedEnd)));
+}
+
+#[test]
+fn test_recorder_journals_only_its_own_steps() {
+    let mut vm = Vm::builder().program(program()).stdin(io::empty()).stdout(io::sink()).build();
+
+    let mut recorder = Recorder::new(&mut vm);
+    recorder.step(&mut vm).unwrap();
+    vm.run().unwrap();
+
+    assert!(vm.data_memory_mut().take_writes().is_empty());
+    assert!(vm.data_memory_mut().heap_mut().take_writes().is_empty());
+    assert!(vm.data_memory_mut().linear_memory_mut().take_writes().is_empty());
+}
+
+#[test]
+fn test_recorder_discards_log_after_reset() {
+    let mut vm = Vm::builder().program(program()).stdin(io::empty()).stdout(io::sink()).build();
+
+    let mut recorder = Recorder::new(&mut vm);
+    recorder.run(&mut vm).unwrap();
+
+    vm.reset();
+
+    assert!(!recorder.step_back(&mut vm));
+    assert_eq!(recorder.position(), 0);
+
+    // Recording carries on from the reset state.
+    for _ in 0..2 {
+        recorder.step(&mut vm).unwrap();
+    }
+
+    assert_eq!(vm.data_memory().get(0), Some(&InnerData::INT(3)));
+    assert!(recorder.step_back(&mut vm));
+    assert!(vm.data_memory().is_empty());
+}
@@ -0,0 +1,132 @@
+use std::io;
+
+use yamini::processor::{Processor, VmError};
+use yamini::memory::{Stack, ProgramMemory, InnerData, DataMemory, HeapObject, StructType};
+use yamini::instructions::InstructionSet;
+use yamini::snapshot::{Snapshot, SnapshotError, MAX_LINEAR_MEMORY};
+
+fn program() -> Vec<InstructionSet> {
+    vec![
+        InstructionSet::LOAD(InnerData::STR("ab".to_string()), 3),
+        InstructionSet::POP(InnerData::INT(1), 4),
+        InstructionSet::LOAD(InnerData::INT16(300), 2),
+        InstructionSet::LOAD(InnerData::INT(4), 2),
+        InstructionSet::ADD,
+        InstructionSet::SHOW,
+        InstructionSet::LOAD(InnerData::INT(1), 4),
+        InstructionSet::SHOW,
+        InstructionSet::HALT,
+    ]
+}
+
+#[test]
+fn test_snapshot_bytes_round_trip() {
+    let mut memory = ProgramMemory::new();
+    memory.load_program(program());
+
+    let mut data_memory = DataMemory::with_linear_memory(64);
+    data_memory.set_var_value(70000, InnerData::INT32(70000));
+    data_memory.linear_memory_mut().write(10, &[0xef, 0xbe]);
+
+    let array = data_memory.heap_mut().alloc(HeapObject::Array(vec![InnerData::INT(1), InnerData::STR("x".to_string())]));
+    data_memory.heap_mut().alloc(HeapObject::Array(vec![array.clone()]));
+    data_memory.heap_mut().define_types(vec![StructType::new("Point", &["x", "y"])]);
+    data_memory.heap_mut().alloc(HeapObject::Struct(0, vec![InnerData::INT(1), array.clone()]));
+
+    let map = data_memory.heap_mut().alloc(HeapObject::Map(Default::default()));
+    data_memory.heap_mut().map_insert(3, InnerData::STR("k".to_string()), array.clone());
+    data_memory.heap_mut().map_insert(3, InnerData::U64(9), InnerData::BOOL(false));
+    data_memory.set_var_value(8, array);
+    data_memory.set_var_value(9, map);
+
+    let mut stack = Stack::new();
+    stack.push(InnerData::INT(-3));
+    stack.push(InnerData::STR("héllo".to_string()));
+    stack.push(InnerData::BOOL(true));
+    stack.push(InnerData::FUNC(4));
+
+    let mut call_stack = Stack::new();
+    call_stack.push(InnerData::INT(5));
+
+    let mut snapshot = Snapshot::capture(&Processor::new(), &memory, &data_memory, &stack, &call_stack);
+    snapshot.fp = 1;
+    snapshot.program.push(InstructionSet::CALLF(InnerData::INT(0), InnerData::INT(2)));
+    snapshot.program.push(InstructionSet::RETF(InnerData::INT(1)));
+
+    let decoded = Snapshot::from_bytes(&snapshot.to_bytes()).unwrap();
+
+    assert_eq!(decoded, snapshot);
+}
+
+#[test]
+fn test_snapshot_restore_resumes_execution() {
+    let mut memory = ProgramMemory::new();
+    memory.load_program(program());
+
+    let mut data_memory = DataMemory::new();
+    let mut stack = Stack::new();
+    let mut call_stack = Stack::new();
+    let mut stdout = Vec::new();
+
+    let mut processor = Processor::new();
+    processor.set_fuel(Some(4));
+
+    let result = processor.execute_program(
+        &memory, &mut data_memory, &mut stack, &mut call_stack, &mut io::empty(), &mut stdout
+    );
+    assert_eq!(result, Err(VmError::OutOfFuel));
+
+    let bytes = Snapshot::capture(&processor, &memory, &data_memory, &stack, &call_stack).to_bytes();
+
+    let mut restored_processor = Processor::new();
+    let mut restored_memory = ProgramMemory::new();
+    let mut restored_data_memory = DataMemory::new();
+    let mut restored_stack = Stack::new();
+    let mut restored_call_stack = Stack::new();
+
+    Snapshot::from_bytes(&bytes).unwrap().restore(
+        &mut restored_processor,
+        &mut restored_memory,
+        &mut restored_data_memory,
+        &mut restored_stack,
+        &mut restored_call_stack
+    );
+
+    assert_eq!(restored_processor.pc(), 4);
+    assert_eq!(restored_stack.head(), 2);
+
+    restored_processor.execute_program(
+        &restored_memory,
+        &mut restored_data_memory,
+        &mut restored_stack,
+        &mut restored_call_stack,
+        &mut io::empty(),
+        &mut stdout
+    ).unwrap();
+
+    assert_eq!(String::from_utf8(stdout).unwrap(), "304\nab\n");
+}
+
+#[test]
+fn test_snapshot_invalid_bytes() {
+    assert!(matches!(Snapshot::from_bytes(b"ELF\x7f"), Err(SnapshotError::InvalidMagic)));
+    assert!(matches!(Snapshot::from_bytes(b"YSNP\x03\x00"), Err(SnapshotError::UnsupportedVersion(3))));
+    assert!(matches!(Snapshot::from_bytes(b"YSNP\x02\x00\x00"), Err(SnapshotError::UnexpectedEnd)));
+}
+
+#[test]
+fn test_snapshot_oversized_linear_memory() {
+    let snapshot = Snapshot::capture(&Processor::new(), &ProgramMemory::new(), &DataMemory::with_linear_memory(4),
+                                     &Stack::new(), &Stack::new());
+    let mut bytes = snapshot.to_bytes();
+
+    // The size is followed by the count of stored bytes and an empty program.
+    let offset = bytes.len() - 8 - 4 - 4;
+    assert_eq!(bytes[offset..offset + 8], 4u64.to_le_bytes());
+
+    bytes[offset..offset + 8].copy_from_slice(&(MAX_LINEAR_MEMORY as u64 + 1).to_le_bytes());
+    assert!(matches!(Snapshot::from_bytes(&bytes), Err(SnapshotError::InvalidMemorySize)));
+
+    bytes[offset..offset + 8].copy_from_slice(&u64::MAX.to_le_bytes());
+    assert!(matches!(Snapshot::from_bytes(&bytes), Err(SnapshotError::InvalidMemorySize)));
+}
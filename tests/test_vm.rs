@@ -0,0 +1,207 @@
+use std::cell::RefCell;
+use std::io;
+use std::rc::Rc;
+
+use yamini::processor::{VmError, Limits};
+use yamini::memory::{InnerData, StructType};
+use yamini::instructions::InstructionSet;
+use yamini::vm::Vm;
+
+fn program() -> Vec<InstructionSet> {
+    vec![
+        InstructionSet::LOAD(InnerData::INT(3), 2),
+        InstructionSet::LOAD(InnerData::INT(4), 2),
+        InstructionSet::MUL,
+        InstructionSet::SHOW,
+        InstructionSet::HALT,
+    ]
+}
+
+#[test]
+fn test_vm_run() {
+    let mut vm = Vm::builder().program(program()).stdin(io::empty()).stdout(Vec::new()).build();
+
+    vm.run().unwrap();
+
+    assert_eq!(String::from_utf8(vm.stdout().clone()).unwrap(), "12\n");
+    assert_eq!(vm.stack().head(), 0);
+}
+
+#[test]
+fn test_vm_reset_and_rerun() {
+    let mut vm = Vm::builder().program(program()).stdin(io::empty()).stdout(Vec::new()).fuel(5).build();
+
+    vm.run().unwrap();
+    assert_eq!(vm.processor().fuel(), Some(0));
+
+    vm.reset();
+
+    assert_eq!(vm.processor().pc(), 0);
+    assert_eq!(vm.processor().fuel(), Some(5));
+
+    vm.run().unwrap();
+
+    assert_eq!(String::from_utf8(vm.stdout().clone()).unwrap(), "12\n12\n");
+}
+
+#[test]
+fn test_vm_resume_after_out_of_fuel() {
+    let mut vm = Vm::builder().program(program()).stdin(io::empty()).stdout(Vec::new()).fuel(2).build();
+
+    assert_eq!(vm.run(), Err(VmError::OutOfFuel));
+    assert_eq!(vm.stack().data(), &[InnerData::INT(3), InnerData::INT(4)]);
+
+    vm.processor_mut().add_fuel(3);
+    vm.run().unwrap();
+
+    assert_eq!(String::from_utf8(vm.stdout().clone()).unwrap(), "12\n");
+}
+
+#[test]
+fn test_vm_limits() {
+    let mut vm = Vm::builder()
+        .program(program())
+        .stdin(io::empty())
+        .stdout(Vec::new())
+        .limits(Limits { max_stack_depth: 1, ..Limits::new() })
+        .build();
+
+    assert_eq!(vm.run(), Err(VmError::StackOverflow));
+}
+
+#[test]
+fn test_vm_stdin() {
+    let program = vec![
+        InstructionSet::READ,
+        InstructionSet::READ,
+        InstructionSet::ADD,
+        InstructionSet::SHOW,
+        InstructionSet::HALT,
+    ];
+
+    let mut vm = Vm::builder().program(program).stdin("ab\r\ncd\n".as_bytes()).stdout(Vec::new()).build();
+
+    vm.run().unwrap();
+
+    assert_eq!(String::from_utf8(vm.stdout().clone()).unwrap(), "abcd\n");
+}
+
+#[test]
+fn test_vm_instruction_hook() {
+    let trace = Rc::new(RefCell::new(Vec::new()));
+    let hook_trace = Rc::clone(&trace);
+
+    let mut vm = Vm::builder()
+        .program(program())
+        .stdin(io::empty())
+        .stdout(io::sink())
+        .on_instruction(Box::new(move |pc, instruction| {
+            hook_trace.borrow_mut().push((pc, instruction.opcode()));
+        }))
+        .build();
+
+    vm.run().unwrap();
+
+    assert_eq!(*trace.borrow(), vec![(0, 0), (1, 0), (2, 3), (3, 14), (4, 5)]);
+}
+
+#[test]
+fn test_vm_types_survive_reset() {
+    let program = vec![
+        InstructionSet::NEW(InnerData::INT(0)),
+        InstructionSet::SHOW,
+        InstructionSet::HALT,
+    ];
+
+    let mut vm = Vm::builder()
+        .program(program)
+        .types(vec![StructType::new("Pair", &["first", "second"])])
+        .stdin(io::empty())
+        .stdout(Vec::new())
+        .build();
+
+    vm.run().unwrap();
+    vm.reset();
+    vm.run().unwrap();
+
+    assert_eq!(vm.stdout(), b"Pair { first: 0, second: 0 }\nPair { first: 0, second: 0 }\n");
+    assert_eq!(vm.data_memory().heap().objects().len(), 1);
+}
+
+#[test]
+fn test_vm_collect_garbage() {
+    let program = vec![
+        InstructionSet::NEWARRAY(InnerData::INT(4)),
+        InstructionSet::NEWARRAY(InnerData::INT(2)),
+        InstructionSet::DROP,
+        InstructionSet::HALT,
+    ];
+
+    let mut vm = Vm::builder().program(program).stdin(io::empty()).stdout(io::sink()).build();
+
+    vm.run().unwrap();
+    assert_eq!(vm.data_memory().heap().len(), 2);
+
+    vm.collect_garbage();
+
+    let heap = vm.data_memory().heap();
+    assert_eq!(heap.len(), 1);
+    assert_eq!(heap.cells(), 4);
+    assert_eq!(heap.stats().objects_freed, 1);
+    assert_eq!(heap.stats().cells_freed, 2);
+}
+
+#[test]
+fn test_vm_linear_memory_size() {
+    let program = vec![
+        InstructionSet::LOAD(InnerData::INT(100), 2),
+        InstructionSet::LOAD(InnerData::INT(1), 2),
+        InstructionSet::MSTORE(InnerData::INT(4)),
+        InstructionSet::MSIZE,
+        InstructionSet::SHOW,
+        InstructionSet::HALT,
+    ];
+
+    let mut vm = Vm::builder().program(program.clone()).stdin(io::empty()).stdout(Vec::new()).build();
+    vm.run().unwrap();
+    assert_eq!(vm.stdout(), b"65536\n");
+
+    let mut vm = Vm::builder().program(program).linear_memory(100).stdin(io::empty()).stdout(Vec::new()).build();
+    assert_eq!(vm.run(), Err(VmError::MemoryFault));
+
+    vm.reset();
+    assert_eq!(vm.data_memory().linear_memory().size(), 100);
+}
+
+#[test]
+fn test_vm_frames() {
+    // f(a, b) stores a + b in local 2, then calls g(local 2), which stops the
+    // machine while both frames are live.
+    let program = vec![
+        InstructionSet::LOAD(InnerData::INT(3), 2),
+        InstructionSet::LOAD(InnerData::INT(4), 2),
+        InstructionSet::CALLF(InnerData::INT(3), InnerData::INT(2)),
+        InstructionSet::LABEL,
+        InstructionSet::LOADLOCAL(InnerData::INT(0)),
+        InstructionSet::LOADLOCAL(InnerData::INT(1)),
+        InstructionSet::ADD,
+        InstructionSet::STORELOCAL(InnerData::INT(2)),
+        InstructionSet::LOADLOCAL(InnerData::INT(2)),
+        InstructionSet::CALLF(InnerData::INT(10), InnerData::INT(1)),
+        InstructionSet::LABEL,
+        InstructionSet::HALT,
+    ];
+
+    let mut vm = Vm::builder().program(program).stdin(io::empty()).stdout(io::sink()).build();
+    vm.run().unwrap();
+
+    let frames = vm.frames();
+    assert_eq!(frames.len(), 3);
+
+    assert_eq!(frames[0].return_pc, Some(9));
+    assert_eq!(frames[0].locals, &[InnerData::INT(7)]);
+    assert_eq!(frames[1].return_pc, Some(2));
+    assert_eq!(frames[1].locals, &[InnerData::INT(3), InnerData::INT(4), InnerData::INT(7)]);
+    assert_eq!(frames[2].return_pc, None);
+    assert!(frames[2].locals.is_empty());
+}